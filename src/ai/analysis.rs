@@ -0,0 +1,167 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+use crate::core::{hash::position_hash, Board, Chess, Player, State};
+
+use super::{syzygy::Tablebase, Limits, Search, SearchInfo};
+
+/// The number of lines shown while analysing.
+///
+pub const ANALYSIS_LINES: usize = 3;
+
+/// The transposition table size for analysis, in megabytes.
+///
+const ANALYSIS_HASH: usize = 64;
+
+/// A search left running on a position until it changes, as in
+/// [`State::Analysis`].
+///
+pub struct Analysis {
+    /// The hash of the position being searched.
+    key: Option<u64>,
+    stop: Arc<AtomicBool>,
+    /// The deepest result so far.
+    latest: Arc<Mutex<Option<SearchInfo>>>,
+    thread: Option<JoinHandle<Search>>,
+    /// The searcher, while it isn't off searching in another thread.
+    search: Option<Search>,
+}
+
+impl Analysis {
+    fn new(tablebase: Option<Arc<Tablebase>>) -> Self {
+        let mut search = Search::new(ANALYSIS_HASH);
+        search.tablebase = tablebase;
+        search.set_lines(ANALYSIS_LINES);
+        search.set_threads(thread::available_parallelism().map_or(1, |n| n.get()));
+
+        Self {
+            key: None,
+            stop: search.stop_flag(),
+            latest: Arc::new(Mutex::new(None)),
+            thread: None,
+            search: Some(search),
+        }
+    }
+
+    /// Start searching the position, unless it is the one already being
+    /// searched.
+    ///
+    fn follow(&mut self, board: &Board, turn: Player) {
+        let key = position_hash(board, turn);
+        if self.key == Some(key) {
+            return;
+        }
+
+        self.halt();
+        self.key = Some(key);
+        if let Ok(mut latest) = self.latest.lock() {
+            *latest = None;
+        }
+
+        let Some(mut search) = self.search.take() else {
+            return;
+        };
+
+        let latest = self.latest.clone();
+        let board = board.clone();
+
+        self.thread = Some(thread::spawn(move || {
+            search.run(&board, turn, Limits::default(), |info| {
+                if let Ok(mut latest) = latest.lock() {
+                    *latest = Some(info.clone());
+                }
+            });
+            search
+        }));
+    }
+
+    /// Stop the search and take back the searcher.
+    ///
+    fn halt(&mut self) {
+        let Some(thread) = self.thread.take() else {
+            return;
+        };
+
+        self.stop.store(true, Ordering::Relaxed);
+        if let Ok(search) = thread.join() {
+            self.search = Some(search);
+        }
+
+        // a stop that came after the search was over would end the next one
+        //
+        self.stop.store(false, Ordering::Relaxed);
+    }
+
+    /// The deepest result for the position being searched so far.
+    ///
+    pub fn latest(&self) -> Option<SearchInfo> {
+        self.latest.lock().ok()?.clone()
+    }
+}
+
+impl Drop for Analysis {
+    fn drop(&mut self) {
+        self.halt();
+    }
+}
+
+/// Analysing the game's position as the player moves for both sides.
+///
+impl Chess {
+    pub fn is_analysing(&self) -> bool {
+        matches!(self.state, State::Analysis)
+    }
+
+    /// Start analysing, stopping the clocks, or go back to the game.
+    ///
+    pub fn toggle_analysis(&mut self) {
+        if self.is_analysing() {
+            self.extensions.remove::<Analysis>();
+            self.state = State::Paused;
+            match self.outcome {
+                Some(_) => self.state = State::Over,
+                None => self.start(),
+            }
+            return;
+        }
+
+        if self.refereed || self.is_editing() || self.is_puzzle() || !self.board.variant.can_undo()
+        {
+            self.view.message = Some("The position can't be analysed now".into());
+            return;
+        }
+
+        self.stop();
+        self.state = State::Analysis;
+        let analysis = Analysis::new(self.tablebase());
+        self.extensions.insert(analysis);
+    }
+
+    /// Keep the analysis on the position on the board, starting the
+    /// search over whenever the position has changed.
+    ///
+    pub fn follow_analysis(&mut self) {
+        if !self.is_analysing() {
+            self.extensions.remove::<Analysis>();
+            return;
+        }
+
+        let tablebase = self.tablebase();
+        let analysis = self
+            .extensions
+            .get_or_insert_with(|| Analysis::new(tablebase));
+
+        analysis.follow(&self.board, self.turn);
+    }
+
+    /// The latest result of analysing the position on the board.
+    ///
+    pub fn analysis(&self) -> Option<SearchInfo> {
+        self.extensions.get::<Analysis>()?.latest()
+    }
+}
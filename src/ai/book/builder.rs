@@ -0,0 +1,126 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::core::{
+    fen, notation, pgn,
+    variant::{self, Variant},
+    Player,
+};
+
+use super::{encode_move, polyglot_key, Book, BookEntry};
+
+/// Collects the moves played in a set of games into a book. Each time a
+/// move is played it scores 2 for the player if they went on to win, 1
+/// for a draw or unknown result and nothing for a loss, and a move's
+/// weight is its total score.
+///
+pub struct BookBuilder {
+    /// How many plies into each game to record.
+    max_ply: usize,
+    scores: HashMap<(u64, u16), u32>,
+}
+
+impl BookBuilder {
+    pub fn new(max_ply: usize) -> Self {
+        Self {
+            max_ply,
+            scores: HashMap::new(),
+        }
+    }
+
+    /// Record the opening of every game in a PGN collection, returning
+    /// the number of games used and a description of each game that
+    /// could not be read to the end of its opening.
+    ///
+    pub fn add_pgn(&mut self, text: &str) -> (usize, Vec<String>) {
+        let mut used = 0;
+        let mut errors = Vec::new();
+
+        for (i, game) in pgn::parse(text).iter().enumerate() {
+            match self.add_game(game) {
+                Ok(()) => used += 1,
+                Err(err) => errors.push(format!("game {}: {}", i + 1, err)),
+            }
+        }
+
+        (used, errors)
+    }
+
+    /// Record the opening of one game. The moves read before any that
+    /// can't be are kept.
+    ///
+    pub fn add_game(&mut self, game: &pgn::Game) -> Result<(), String> {
+        let name = game.tag("Variant").unwrap_or("standard");
+        let variant = variant_named(name).ok_or_else(|| format!("unknown variant '{}'", name))?;
+
+        let (mut board, mut turn) = match game.tag("FEN") {
+            Some(text) => {
+                let fen = fen::parse_variant(text, variant).map_err(|err| err.to_string())?;
+                (fen.board, fen.turn)
+            }
+            None => (variant.start_position(), Player::White),
+        };
+
+        let winner = match game.result.as_deref() {
+            Some("1-0") => Some(Player::White),
+            Some("0-1") => Some(Player::Black),
+            _ => None,
+        };
+        let decisive = winner.is_some();
+
+        for text in game.moves.iter().take(self.max_ply) {
+            let (from, m) = notation::parse_move(&board, turn, text)
+                .ok_or_else(|| format!("illegal move '{}'", text))?;
+
+            let Some(key) = polyglot_key(&board, turn) else {
+                return Err("the position can't be kept in a Polyglot book".into());
+            };
+
+            let score = match (decisive, winner == Some(turn)) {
+                (true, true) => 2,
+                (true, false) => 0,
+                (false, _) => 1,
+            };
+            *self.scores.entry((key, encode_move(from, &m))).or_default() += score;
+
+            board.make_move(from, &m);
+            turn = turn.opponent();
+        }
+
+        Ok(())
+    }
+
+    /// The book of every move that scored, with the weights scaled down
+    /// to fit the format if need be.
+    ///
+    pub fn build(self) -> Book {
+        let max = self.scores.values().copied().max().unwrap_or(0);
+        let scale = (max as f64 / u16::MAX as f64).max(1.0);
+
+        let mut entries: Vec<BookEntry> = self
+            .scores
+            .into_iter()
+            .filter(|(_, score)| *score > 0)
+            .map(|((key, mv), score)| BookEntry {
+                key,
+                mv,
+                weight: ((score as f64 / scale) as u16).max(1),
+                learn: 0,
+            })
+            .collect();
+
+        entries.sort_by_key(|e| (e.key, std::cmp::Reverse(e.weight), e.mv));
+
+        Book { entries }
+    }
+}
+
+/// The variant named by a PGN `Variant` tag, which is written in words
+/// rather than as in [`variant::NAMES`].
+///
+fn variant_named(name: &str) -> Option<Arc<dyn Variant>> {
+    match name.to_lowercase().replace(' ', "-").as_str() {
+        "standard" | "normal" | "chess960" | "from-position" => Some(Arc::new(variant::Standard)),
+        "king-of-the-hill" => Some(Arc::new(variant::KingOfTheHill)),
+        name => variant::by_name(name),
+    }
+}
@@ -0,0 +1,478 @@
+use std::{fs, io, path::Path, sync::Arc};
+
+use crate::core::{
+    notation, pawn_direction, random, Board, Chess, Move, MoveResult, PieceType, Player, Vec2,
+};
+
+mod builder;
+mod keys;
+
+pub use builder::BookBuilder;
+use keys::RANDOM64;
+
+/// One move for one position, as stored in a Polyglot `.bin` file.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BookEntry {
+    /// The [`polyglot_key`] of the position.
+    pub key: u64,
+    /// The move, packed as Polyglot does: the destination file and rank
+    /// in the low six bits, then the origin's, then any promotion.
+    pub mv: u16,
+    /// How often the move should be chosen, relative to the others.
+    pub weight: u16,
+    /// Kept for the learning some programs do, unused here.
+    pub learn: u32,
+}
+
+const ENTRY_SIZE: usize = 16;
+
+/// A move the book offers in a position.
+///
+#[derive(Copy, Clone)]
+pub struct BookMove {
+    pub from: Vec2,
+    pub m: Move,
+    pub weight: u16,
+}
+
+/// An opening book in the Polyglot format, which covers standard 8x8
+/// chess and the variants played on its board without pockets or
+/// fairy pieces.
+///
+#[derive(Default)]
+pub struct Book {
+    /// Sorted by key, as in the file, with each position's moves together.
+    entries: Vec<BookEntry>,
+}
+
+impl Book {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Book> {
+        Book::from_bytes(&fs::read(path)?)
+    }
+
+    /// Read a book from the contents of a `.bin` file.
+    ///
+    pub fn from_bytes(data: &[u8]) -> io::Result<Book> {
+        if !data.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a Polyglot book, the size isn't a whole number of entries",
+            ));
+        }
+
+        let mut entries: Vec<BookEntry> = data
+            .chunks_exact(ENTRY_SIZE)
+            .map(|chunk| BookEntry {
+                key: u64::from_be_bytes(chunk[0..8].try_into().unwrap()),
+                mv: u16::from_be_bytes(chunk[8..10].try_into().unwrap()),
+                weight: u16::from_be_bytes(chunk[10..12].try_into().unwrap()),
+                learn: u32::from_be_bytes(chunk[12..16].try_into().unwrap()),
+            })
+            .collect();
+
+        // lookups rely on the order, which a badly made book might not keep
+        //
+        entries.sort_by_key(|e| e.key);
+
+        Ok(Book { entries })
+    }
+
+    /// The book as the contents of a `.bin` file.
+    ///
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.entries
+            .iter()
+            .flat_map(|e| {
+                let mut bytes = Vec::with_capacity(ENTRY_SIZE);
+                bytes.extend(e.key.to_be_bytes());
+                bytes.extend(e.mv.to_be_bytes());
+                bytes.extend(e.weight.to_be_bytes());
+                bytes.extend(e.learn.to_be_bytes());
+                bytes
+            })
+            .collect()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    /// The number of entries, that is of position and move pairs.
+    ///
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entries stored for the position with hash `key`.
+    ///
+    pub fn entries(&self, key: u64) -> &[BookEntry] {
+        let start = self.entries.partition_point(|e| e.key < key);
+        let end = self.entries.partition_point(|e| e.key <= key);
+
+        &self.entries[start..end]
+    }
+
+    /// The book's moves for `turn` in the position on `board`, heaviest
+    /// first. Moves that aren't legal in the position are left out, as
+    /// they come from another position sharing the key.
+    ///
+    pub fn moves(&self, board: &Board, turn: Player) -> Vec<BookMove> {
+        let Some(key) = polyglot_key(board, turn) else {
+            return Vec::new();
+        };
+
+        let mut moves: Vec<BookMove> = self
+            .entries(key)
+            .iter()
+            .filter_map(|e| {
+                decode_move(board, turn, e.mv).map(|(from, m)| BookMove {
+                    from,
+                    m,
+                    weight: e.weight,
+                })
+            })
+            .collect();
+
+        moves.sort_by_key(|m| std::cmp::Reverse(m.weight));
+        moves
+    }
+
+    /// One of the book's moves, chosen at random in proportion to the
+    /// weights, or evenly if they are all zero.
+    ///
+    pub fn pick(&self, board: &Board, turn: Player) -> Option<BookMove> {
+        let moves = self.moves(board, turn);
+
+        let total: u64 = moves.iter().map(|m| m.weight as u64).sum();
+        if total == 0 {
+            let i = random::below(moves.len() as u64) as usize;
+            return moves.get(i).copied();
+        }
+
+        let mut roll = random::below(total);
+        for m in &moves {
+            if roll < m.weight as u64 {
+                return Some(*m);
+            }
+            roll -= m.weight as u64;
+        }
+
+        None
+    }
+}
+
+/// Playing from the opening book loaded into the game.
+///
+impl Chess {
+    /// The opening book to look positions up in, if one was loaded.
+    ///
+    pub fn book(&self) -> Option<Arc<Book>> {
+        self.extensions.shared()
+    }
+
+    pub fn set_book(&mut self, book: Arc<Book>) {
+        self.extensions.share(book);
+    }
+
+    /// The book moves for the current position.
+    ///
+    pub fn book_moves(&self) -> Vec<BookMove> {
+        match self.book() {
+            Some(book) => book.moves(&self.board, self.turn),
+            None => Vec::new(),
+        }
+    }
+
+    /// Play a book move chosen at random by weight, returning whether
+    /// there was one.
+    ///
+    pub fn play_book_move(&mut self) -> bool {
+        let Some(book) = self.book() else {
+            return false;
+        };
+
+        match book.pick(&self.board, self.turn) {
+            Some(m) => {
+                let san = notation::san(&self.board, m.from, &m.m);
+                self.play_notation(&san)
+            }
+            None => false,
+        }
+    }
+}
+
+/// The Polyglot hash of the position, for looking it up in a book.
+/// There is none for boards other than 8x8, or positions with pieces
+/// the format has no keys for.
+///
+pub fn polyglot_key(board: &Board, turn: Player) -> Option<u64> {
+    if board.width() != 8 || board.height() != 8 || board.variant.has_pockets() {
+        return None;
+    }
+
+    let mut key = 0;
+
+    for player in [Player::White, Player::Black] {
+        for (pos, piece) in board.pieces(player) {
+            let role = match piece.ty {
+                PieceType::Pawn => 0,
+                PieceType::Knight => 1,
+                PieceType::Bishop => 2,
+                PieceType::Rook => 3,
+                PieceType::Queen => 4,
+                PieceType::King => 5,
+                PieceType::Fairy(_) => return None,
+            };
+            let kind = role * 2 + (player == Player::White) as usize;
+
+            key ^= RANDOM64[64 * kind + square(pos)];
+        }
+    }
+
+    for (i, (player, kingside)) in [
+        (Player::White, true),
+        (Player::White, false),
+        (Player::Black, true),
+        (Player::Black, false),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        if can_castle(board, player, kingside) {
+            key ^= RANDOM64[768 + i];
+        }
+    }
+
+    // the en passant file only counts when a pawn is there to take
+    //
+    if let Some(ep) = board.en_passant {
+        let behind = ep + pawn_direction(turn) * -1;
+        let capturer = [-1, 1].into_iter().any(|dx| {
+            matches!(board.get(behind + Vec2::new(dx, 0)),
+                Some(p) if p.ty == PieceType::Pawn && p.player == turn)
+        });
+
+        if capturer {
+            key ^= RANDOM64[772 + ep.x as usize];
+        }
+    }
+
+    if turn == Player::White {
+        key ^= RANDOM64[780];
+    }
+
+    Some(key)
+}
+
+/// Whether `player` keeps the right to castle on the side given, with
+/// the king and a rook on that side not yet moved.
+///
+fn can_castle(board: &Board, player: Player, kingside: bool) -> bool {
+    let Some(king) = board.find_king(player) else {
+        return false;
+    };
+
+    let unmoved = |pos: Vec2| board.get(pos).is_some_and(|p| p.move_count == 0);
+
+    unmoved(king)
+        && board.pieces(player).any(|(pos, p)| {
+            p.ty == PieceType::Rook
+                && p.move_count == 0
+                && pos.y == king.y
+                && (pos.x > king.x) == kingside
+        })
+}
+
+/// The Polyglot number of a square, counting from a1 along the ranks.
+///
+fn square(pos: Vec2) -> usize {
+    ((7 - pos.y) * 8 + pos.x) as usize
+}
+
+fn from_square(square: u16) -> Vec2 {
+    Vec2::new((square % 8) as i16, 7 - (square / 8) as i16)
+}
+
+const PROMOTIONS: [PieceType; 4] = [
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+];
+
+/// Pack a move for a book. Castling is written as the king taking its
+/// own rook, e.g. `e1h1`.
+///
+fn encode_move(from: Vec2, m: &Move) -> u16 {
+    let to = match m.result {
+        MoveResult::Castle(rook) => rook,
+        _ => m.pos,
+    };
+
+    let promotion = match m.result {
+        MoveResult::Promotion(ty) => PROMOTIONS
+            .iter()
+            .position(|p| *p == ty)
+            .map_or(0, |i| i + 1),
+        _ => 0,
+    };
+
+    (square(to) | square(from) << 6 | promotion << 12) as u16
+}
+
+/// The legal move a book entry stands for.
+///
+fn decode_move(board: &Board, turn: Player, mv: u16) -> Option<(Vec2, Move)> {
+    let to = from_square(mv & 0x3f);
+    let from = from_square((mv >> 6) & 0x3f);
+    let promotion = match (mv >> 12) & 0x7 {
+        0 => None,
+        n => Some(*PROMOTIONS.get(n as usize - 1)?),
+    };
+
+    board.all_legal_moves(turn).into_iter().find(|(pos, m)| {
+        *pos == from
+            && match m.result {
+                MoveResult::Castle(rook) => rook == to,
+                MoveResult::Promotion(ty) => m.pos == to && promotion == Some(ty),
+                _ => m.pos == to && promotion.is_none(),
+            }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::fen;
+
+    fn key(text: &str) -> u64 {
+        let fen = fen::parse(text).unwrap();
+        polyglot_key(&fen.board, fen.turn).unwrap()
+    }
+
+    /// The legal move from `from` to `to` with the given result.
+    ///
+    fn find(board: &Board, turn: Player, from: Vec2, to: Vec2, result: MoveResult) -> Move {
+        board
+            .all_legal_moves(turn)
+            .into_iter()
+            .find(|(pos, m)| *pos == from && m.pos == to && m.result == result)
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn reference_keys() {
+        // the positions and keys given with the Polyglot format
+        //
+        let positions = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                0x463b96181691fc9c,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+                0x823c9b50fd114196,
+            ),
+            (
+                "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+                0x0756b94461c50fb0,
+            ),
+            (
+                "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2",
+                0x662fafb965db29d4,
+            ),
+            (
+                "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+                0x22a48b5a8e47ff78,
+            ),
+            (
+                "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 0 3",
+                0x652a607ca3f242c1,
+            ),
+            (
+                "rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 0 4",
+                0x00fdd303c946bdd9,
+            ),
+            (
+                "rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3",
+                0x3c8123ea7b067637,
+            ),
+            (
+                "rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 0 4",
+                0x5c3f9b829b279560,
+            ),
+        ];
+
+        for (text, expected) in positions {
+            assert_eq!(key(text), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let book = Book {
+            entries: vec![
+                BookEntry {
+                    key: 0x463b96181691fc9c,
+                    mv: 0x031c,
+                    weight: 10,
+                    learn: 0,
+                },
+                BookEntry {
+                    key: 0x823c9b50fd114196,
+                    mv: 0x0ce3,
+                    weight: 3,
+                    learn: 7,
+                },
+            ],
+        };
+
+        let data = book.to_bytes();
+        assert_eq!(data.len(), 2 * ENTRY_SIZE);
+        assert_eq!(Book::from_bytes(&data).unwrap().entries, book.entries);
+
+        assert!(Book::from_bytes(&data[1..]).is_err());
+    }
+
+    #[test]
+    fn castling_is_the_king_taking_its_rook() {
+        let fen = fen::parse("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let e1 = Vec2::new(4, 7);
+
+        let sides = [
+            (Vec2::new(6, 7), Vec2::new(7, 7), 0x0107),
+            (Vec2::new(2, 7), Vec2::new(0, 7), 0x0100),
+        ];
+        for (to, rook, expected) in sides {
+            let castle = find(&fen.board, fen.turn, e1, to, MoveResult::Castle(rook));
+            let mv = encode_move(e1, &castle);
+
+            assert_eq!(mv, expected);
+            let (from, m) = decode_move(&fen.board, fen.turn, mv).unwrap();
+            assert_eq!((from, m.result), (e1, MoveResult::Castle(rook)));
+        }
+    }
+
+    #[test]
+    fn under_promotions() {
+        let fen = fen::parse("1r5k/P7/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        let (a7, a8) = (Vec2::new(0, 1), Vec2::new(0, 0));
+
+        for (i, ty) in PROMOTIONS.into_iter().enumerate() {
+            let promotion = find(&fen.board, fen.turn, a7, a8, MoveResult::Promotion(ty));
+            let mv = encode_move(a7, &promotion);
+
+            // a7a8 with the piece in the top bits
+            //
+            assert_eq!(mv, 56 | 48 << 6 | (i as u16 + 1) << 12);
+            let (from, m) = decode_move(&fen.board, fen.turn, mv).unwrap();
+            assert_eq!((from, m.pos, m.result), (a7, a8, MoveResult::Promotion(ty)));
+        }
+    }
+}
@@ -0,0 +1,566 @@
+use std::{
+    fmt::Display,
+    ops::{AddAssign, Sub},
+};
+
+use crate::core::{Board, PieceType, Player, Vec2};
+
+/// A score in centipawns, in favour of White unless said otherwise.
+///
+pub type Centipawns = i32;
+
+/// A score as it stands in the middlegame and in the endgame, blended
+/// by how much material is left on the board.
+///
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Score {
+    pub mg: Centipawns,
+    pub eg: Centipawns,
+}
+
+impl Score {
+    pub const fn new(mg: Centipawns, eg: Centipawns) -> Self {
+        Self { mg, eg }
+    }
+
+    /// Blend the two scores, `phase` running from 0 in a bare endgame
+    /// to [`MAX_PHASE`] with all the pieces on the board.
+    ///
+    pub fn taper(self, phase: i32) -> Centipawns {
+        (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl AddAssign for Score {
+    fn add_assign(&mut self, other: Self) {
+        self.mg += other.mg;
+        self.eg += other.eg;
+    }
+}
+
+impl Sub for Score {
+    type Output = Score;
+
+    fn sub(self, other: Self) -> Score {
+        Score::new(self.mg - other.mg, self.eg - other.eg)
+    }
+}
+
+/// The phase of the game with every piece of the standard set still on
+/// the board.
+///
+pub const MAX_PHASE: i32 = 24;
+
+/// One part of the evaluation.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Term {
+    Material,
+    PieceSquares,
+    Mobility,
+    Pawns,
+    KingSafety,
+}
+
+impl Term {
+    pub const ALL: [Term; 5] = [
+        Term::Material,
+        Term::PieceSquares,
+        Term::Mobility,
+        Term::Pawns,
+        Term::KingSafety,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Term::Material => "Material",
+            Term::PieceSquares => "Piece squares",
+            Term::Mobility => "Mobility",
+            Term::Pawns => "Pawn structure",
+            Term::KingSafety => "King safety",
+        }
+    }
+
+    fn score(&self, board: &Board, player: Player) -> Score {
+        match self {
+            Term::Material => material(board, player),
+            Term::PieceSquares => piece_squares(board, player),
+            Term::Mobility => mobility(board, player),
+            Term::Pawns => pawns(board, player),
+            Term::KingSafety => king_safety(board, player),
+        }
+    }
+}
+
+/// The evaluation of a position term by term, to see where a score
+/// comes from.
+///
+pub struct Breakdown {
+    /// How much material is left, see [`Score::taper`].
+    pub phase: i32,
+    /// Each term with its score for White and for Black.
+    pub terms: Vec<(Term, [Score; 2])>,
+}
+
+impl Breakdown {
+    /// What `term` is worth to White over Black.
+    ///
+    pub fn term(&self, term: Term) -> Centipawns {
+        self.terms
+            .iter()
+            .filter(|(t, _)| *t == term)
+            .map(|(_, [white, black])| (*white - *black).taper(self.phase))
+            .sum()
+    }
+
+    /// The whole evaluation, in White's favour.
+    ///
+    pub fn total(&self) -> Centipawns {
+        Term::ALL.iter().map(|term| self.term(*term)).sum()
+    }
+}
+
+impl Display for Breakdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<16}{:>13}{:>13}{:>8}",
+            "Term", "White mg/eg", "Black mg/eg", "Total"
+        )?;
+
+        for (term, [white, black]) in &self.terms {
+            writeln!(
+                f,
+                "{:<16}{:>6}/{:<6}{:>6}/{:<6}{:>8}",
+                term.name(),
+                white.mg,
+                white.eg,
+                black.mg,
+                black.eg,
+                pawns_text(self.term(*term))
+            )?;
+        }
+
+        writeln!(f, "Phase {}/{}", self.phase, MAX_PHASE)?;
+        write!(f, "Total {}", pawns_text(self.total()))
+    }
+}
+
+/// A score in pawns, e.g. `+1.25`.
+///
+pub fn pawns_text(score: Centipawns) -> String {
+    format!("{:+.2}", score as f64 / 100.0)
+}
+
+/// Score the position for the player to move, `turn`: positive when
+/// they stand better. Positions are judged by standard chess principles
+/// whatever the variant.
+///
+pub fn evaluate(board: &Board, turn: Player) -> Centipawns {
+    let total = breakdown(board).total();
+
+    match turn {
+        Player::White => total,
+        Player::Black => -total,
+    }
+}
+
+/// Evaluate the position term by term.
+///
+pub fn breakdown(board: &Board) -> Breakdown {
+    let terms = Term::ALL
+        .iter()
+        .map(|term| {
+            let scores = [Player::White, Player::Black].map(|p| term.score(board, p));
+            (*term, scores)
+        })
+        .collect();
+
+    Breakdown {
+        phase: phase(board),
+        terms,
+    }
+}
+
+fn phase(board: &Board) -> i32 {
+    let weight = |ty: PieceType| match ty {
+        PieceType::Knight | PieceType::Bishop => 1,
+        PieceType::Rook => 2,
+        PieceType::Queen => 4,
+        PieceType::Fairy(f) => (f.value / 2).min(4),
+        PieceType::King | PieceType::Pawn => 0,
+    };
+
+    let total: i32 = [Player::White, Player::Black]
+        .into_iter()
+        .flat_map(|player| board.pieces(player))
+        .map(|(_, p)| weight(p.ty))
+        .sum();
+
+    total.min(MAX_PHASE)
+}
+
+/// What a piece is worth on its own.
+///
+pub fn piece_value(ty: PieceType) -> Score {
+    match ty {
+        PieceType::Pawn => Score::new(82, 94),
+        PieceType::Knight => Score::new(337, 281),
+        PieceType::Bishop => Score::new(365, 297),
+        PieceType::Rook => Score::new(477, 512),
+        PieceType::Queen => Score::new(1025, 936),
+        PieceType::King => Score::new(0, 0),
+        PieceType::Fairy(f) => Score::new(f.value * 100, f.value * 100),
+    }
+}
+
+/// The pieces on the board, and any waiting in the pocket to be dropped.
+///
+fn material(board: &Board, player: Player) -> Score {
+    let mut score = Score::default();
+
+    for (_, piece) in board.pieces(player) {
+        score += piece_value(piece.ty);
+    }
+
+    for (ty, count) in board.pocket(player) {
+        for _ in 0..count {
+            score += piece_value(ty);
+        }
+    }
+
+    score
+}
+
+/// Where each piece stands. Boards other than 8x8 are mapped onto the
+/// tables, and fairy pieces use the knight's, as leapers that also like
+/// the centre.
+///
+fn piece_squares(board: &Board, player: Player) -> Score {
+    let mut score = Score::default();
+
+    for (pos, piece) in board.pieces(player) {
+        let (mg, eg) = match piece.ty {
+            PieceType::Pawn => (&MG_PAWN, &EG_PAWN),
+            PieceType::Knight | PieceType::Fairy(_) => (&MG_KNIGHT, &EG_KNIGHT),
+            PieceType::Bishop => (&MG_BISHOP, &EG_BISHOP),
+            PieceType::Rook => (&MG_ROOK, &EG_ROOK),
+            PieceType::Queen => (&MG_QUEEN, &EG_QUEEN),
+            PieceType::King => (&MG_KING, &EG_KING),
+        };
+
+        let i = table_index(board, pos, player);
+        score += Score::new(mg[i], eg[i]);
+    }
+
+    score
+}
+
+/// The square in the tables, which are laid out from a8 to h1 as White
+/// sees the board.
+///
+fn table_index(board: &Board, pos: Vec2, player: Player) -> usize {
+    let x = pos.x as usize * 8 / board.width() as usize;
+    let y = pos.y as usize * 8 / board.height() as usize;
+
+    match player {
+        Player::White => y * 8 + x,
+        Player::Black => (7 - y) * 8 + x,
+    }
+}
+
+/// How many squares the pieces reach, against what they would reach on
+/// an average square.
+///
+fn mobility(board: &Board, player: Player) -> Score {
+    let mut score = Score::default();
+
+    for (pos, piece) in board.pieces(player) {
+        let (average, mg, eg) = match piece.ty {
+            PieceType::Knight | PieceType::Fairy(_) => (4, 4, 4),
+            PieceType::Bishop => (7, 3, 3),
+            PieceType::Rook => (7, 2, 4),
+            PieceType::Queen => (14, 1, 2),
+            PieceType::King | PieceType::Pawn => continue,
+        };
+
+        let reach = board
+            .attacks(pos)
+            .into_iter()
+            .filter(|square| !matches!(board.get(*square), Some(p) if p.player == player))
+            .count() as i32;
+
+        score += Score::new((reach - average) * mg, (reach - average) * eg);
+    }
+
+    score
+}
+
+const DOUBLED: Score = Score::new(-10, -20);
+const ISOLATED: Score = Score::new(-10, -15);
+
+/// The bonus for a passed pawn by how far it has come, from its own back
+/// rank to the one it promotes on.
+///
+const PASSED: [Score; 8] = [
+    Score::new(0, 0),
+    Score::new(0, 5),
+    Score::new(5, 10),
+    Score::new(10, 20),
+    Score::new(20, 35),
+    Score::new(35, 60),
+    Score::new(60, 100),
+    Score::new(0, 0),
+];
+
+/// Doubled, isolated and passed pawns.
+///
+fn pawns(board: &Board, player: Player) -> Score {
+    let mut score = Score::default();
+
+    let own = pawn_squares(board, player);
+    let theirs = pawn_squares(board, player.opponent());
+
+    let on_file = |pawns: &[Vec2], x: i16| pawns.iter().filter(|p| p.x == x).count();
+
+    for x in 0..board.width() {
+        let count = on_file(&own, x) as i32;
+
+        if count > 1 {
+            score += Score::new(DOUBLED.mg * (count - 1), DOUBLED.eg * (count - 1));
+        }
+        if count > 0 && on_file(&own, x - 1) == 0 && on_file(&own, x + 1) == 0 {
+            score += Score::new(ISOLATED.mg * count, ISOLATED.eg * count);
+        }
+    }
+
+    for pawn in &own {
+        let ahead = |other: &Vec2| match player {
+            Player::White => other.y < pawn.y,
+            Player::Black => other.y > pawn.y,
+        };
+
+        let passed = !theirs
+            .iter()
+            .any(|other| (other.x - pawn.x).abs() <= 1 && ahead(other));
+
+        if passed {
+            let advanced = (pawn.y - board.home_rank(player)).unsigned_abs() as usize;
+            score += PASSED[(advanced * 8 / board.height() as usize).min(7)];
+        }
+    }
+
+    score
+}
+
+fn pawn_squares(board: &Board, player: Player) -> Vec<Vec2> {
+    board
+        .pieces(player)
+        .filter(|(_, p)| p.ty == PieceType::Pawn)
+        .map(|(pos, _)| pos)
+        .collect()
+}
+
+const SHIELD: Centipawns = 12;
+
+/// The penalty for the number of attacks on the squares around the king.
+///
+const KING_DANGER: [Centipawns; 10] = [0, 5, 15, 30, 50, 75, 105, 140, 180, 225];
+
+/// Pawns sheltering the king, and enemy pieces bearing down on it. This
+/// matters while there are pieces enough to mount an attack, so only
+/// counts in the middlegame.
+///
+fn king_safety(board: &Board, player: Player) -> Score {
+    if !board.variant.royal_king() {
+        return Score::default();
+    }
+
+    let Some(king) = board.find_king(player) else {
+        return Score::default();
+    };
+
+    let forward = match player {
+        Player::White => -1,
+        Player::Black => 1,
+    };
+
+    let shield = board
+        .pieces(player)
+        .filter(|(pos, p)| {
+            p.ty == PieceType::Pawn
+                && (pos.x - king.x).abs() <= 1
+                && [1, 2].contains(&((pos.y - king.y) * forward))
+        })
+        .count() as i32;
+
+    let zone: Vec<Vec2> = Vec2::AXIS
+        .iter()
+        .chain(Vec2::DIAG.iter())
+        .map(|dir| king + *dir)
+        .chain([king])
+        .filter(|pos| board.contains(*pos))
+        .collect();
+
+    let attacks = board
+        .pieces(player.opponent())
+        .filter(|(_, p)| !matches!(p.ty, PieceType::Pawn | PieceType::King))
+        .map(|(pos, _)| {
+            board
+                .attacks(pos)
+                .into_iter()
+                .filter(|square| zone.contains(square))
+                .count()
+        })
+        .sum::<usize>();
+
+    let danger = KING_DANGER[attacks.min(KING_DANGER.len() - 1)];
+
+    Score::new(shield * SHIELD - danger, 0)
+}
+
+// piece-square tables from PeSTO, tuned with the piece values above
+//
+#[rustfmt::skip]
+const MG_PAWN: [Centipawns; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     98, 134,  61,  95,  68, 126,  34, -11,
+     -6,   7,  26,  31,  65,  56,  25, -20,
+    -14,  13,   6,  21,  23,  12,  17, -23,
+    -27,  -2,  -5,  12,  17,   6,  10, -25,
+    -26,  -4,  -4, -10,   3,   3,  33, -12,
+    -35,  -1, -20, -23, -15,  24,  38, -22,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const EG_PAWN: [Centipawns; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+    178, 173, 158, 134, 147, 132, 165, 187,
+     94, 100,  85,  67,  56,  53,  82,  84,
+     32,  24,  13,   5,  -2,   4,  17,  17,
+     13,   9,  -3,  -7,  -7,  -8,   3,  -1,
+      4,   7,  -6,   1,   0,  -5,  -1,  -8,
+     13,   8,   8,  10,  13,   0,   2,  -7,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const MG_KNIGHT: [Centipawns; 64] = [
+   -167, -89, -34, -49,  61, -97, -15,-107,
+    -73, -41,  72,  36,  23,  62,   7, -17,
+    -47,  60,  37,  65,  84, 129,  73,  44,
+     -9,  17,  19,  53,  37,  69,  18,  22,
+    -13,   4,  16,  13,  28,  19,  21,  -8,
+    -23,  -9,  12,  10,  19,  17,  25, -16,
+    -29, -53, -12,  -3,  -1,  18, -14, -19,
+   -105, -21, -58, -33, -17, -28, -19, -23,
+];
+
+#[rustfmt::skip]
+const EG_KNIGHT: [Centipawns; 64] = [
+    -58, -38, -13, -28, -31, -27, -63, -99,
+    -25,  -8, -25,  -2,  -9, -25, -24, -52,
+    -24, -20,  10,   9,  -1,  -9, -19, -41,
+    -17,   3,  22,  22,  22,  11,   8, -18,
+    -18,  -6,  16,  25,  16,  17,   4, -18,
+    -23,  -3,  -1,  15,  10,  -3, -20, -22,
+    -42, -20, -10,  -5,  -2, -20, -23, -44,
+    -29, -51, -23, -15, -22, -18, -50, -64,
+];
+
+#[rustfmt::skip]
+const MG_BISHOP: [Centipawns; 64] = [
+    -29,   4, -82, -37, -25, -42,   7,  -8,
+    -26,  16, -18, -13,  30,  59,  18, -47,
+    -16,  37,  43,  40,  35,  50,  37,  -2,
+     -4,   5,  19,  50,  37,  37,   7,  -2,
+     -6,  13,  13,  26,  34,  12,  10,   4,
+      0,  15,  15,  15,  14,  27,  18,  10,
+      4,  15,  16,   0,   7,  21,  33,   1,
+    -33,  -3, -14, -21, -13, -12, -39, -21,
+];
+
+#[rustfmt::skip]
+const EG_BISHOP: [Centipawns; 64] = [
+    -14, -21, -11,  -8,  -7,  -9, -17, -24,
+     -8,  -4,   7, -12,  -3, -13,  -4, -14,
+      2,  -8,   0,  -1,  -2,   6,   0,   4,
+     -3,   9,  12,   9,  14,  10,   3,   2,
+     -6,   3,  13,  19,   7,  10,  -3,  -9,
+    -12,  -3,   8,  10,  13,   3,  -7, -15,
+    -14, -18,  -7,  -1,   4,  -9, -15, -27,
+    -23,  -9, -23,  -5,  -9, -16,  -5, -17,
+];
+
+#[rustfmt::skip]
+const MG_ROOK: [Centipawns; 64] = [
+     32,  42,  32,  51,  63,   9,  31,  43,
+     27,  32,  58,  62,  80,  67,  26,  44,
+     -5,  19,  26,  36,  17,  45,  61,  16,
+    -24, -11,   7,  26,  24,  35,  -8, -20,
+    -36, -26, -12,  -1,   9,  -7,   6, -23,
+    -45, -25, -16, -17,   3,   0,  -5, -33,
+    -44, -16, -20,  -9,  -1,  11,  -6, -71,
+    -19, -13,   1,  17,  16,   7, -37, -26,
+];
+
+#[rustfmt::skip]
+const EG_ROOK: [Centipawns; 64] = [
+     13,  10,  18,  15,  12,  12,   8,   5,
+     11,  13,  13,  11,  -3,   3,   8,   3,
+      7,   7,   7,   5,   4,  -3,  -5,  -3,
+      4,   3,  13,   1,   2,   1,  -1,   2,
+      3,   5,   8,   4,  -5,  -6,  -8, -11,
+     -4,   0,  -5,  -1,  -7, -12,  -8, -16,
+     -6,  -6,   0,   2,  -9,  -9, -11,  -3,
+     -9,   2,   3,  -1,  -5, -13,   4, -20,
+];
+
+#[rustfmt::skip]
+const MG_QUEEN: [Centipawns; 64] = [
+    -28,   0,  29,  12,  59,  44,  43,  45,
+    -24, -39,  -5,   1, -16,  57,  28,  54,
+    -13, -17,   7,   8,  29,  56,  47,  57,
+    -27, -27, -16, -16,  -1,  17,  -2,   1,
+     -9, -26,  -9, -10,  -2,  -4,   3,  -3,
+    -14,   2, -11,  -2,  -5,   2,  14,   5,
+    -35,  -8,  11,   2,   8,  15,  -3,   1,
+     -1, -18,  -9,  10, -15, -25, -31, -50,
+];
+
+#[rustfmt::skip]
+const EG_QUEEN: [Centipawns; 64] = [
+     -9,  22,  22,  27,  27,  19,  10,  20,
+    -17,  20,  32,  41,  58,  25,  30,   0,
+    -20,   6,   9,  49,  47,  35,  19,   9,
+      3,  22,  24,  45,  57,  40,  57,  36,
+    -18,  28,  19,  47,  31,  34,  39,  23,
+    -16, -27,  15,   6,   9,  17,  10,   5,
+    -22, -23, -30, -16, -16, -23, -36, -32,
+    -33, -28, -22, -43,  -5, -32, -20, -41,
+];
+
+#[rustfmt::skip]
+const MG_KING: [Centipawns; 64] = [
+    -65,  23,  16, -15, -56, -34,   2,  13,
+     29,  -1, -20,  -7,  -8,  -4, -38, -29,
+     -9,  24,   2, -16, -20,   6,  22, -22,
+    -17, -20, -12, -27, -30, -25, -14, -36,
+    -49,  -1, -27, -39, -46, -44, -33, -51,
+    -14, -14, -22, -46, -44, -30, -15, -27,
+      1,   7,  -8, -64, -43, -16,   9,   8,
+    -15,  36,  12, -54,   8, -28,  24,  14,
+];
+
+#[rustfmt::skip]
+const EG_KING: [Centipawns; 64] = [
+    -74, -35, -18, -18, -11,  15,   4, -17,
+    -12,  17,  14,  17,  17,  38,  23,  11,
+     10,  17,  23,  15,  20,  45,  44,  13,
+     -8,  22,  24,  27,  26,  33,  26,   3,
+    -18,  -4,  21,  24,  27,  23,   9, -11,
+    -19,  -3,  11,  21,  23,  16,   7,  -9,
+    -27, -11,   4,  13,  14,   4,  -5, -17,
+    -53, -34, -21, -11, -28, -14, -24, -43,
+];
@@ -0,0 +1,134 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::core::{hash::position_hash, notation, Chess, Move, Player, Vec2};
+
+use super::{Limits, Search};
+
+/// How long the search for a hint may take.
+///
+const HINT_TIME: Duration = Duration::from_millis(500);
+
+/// The transposition table size for a hint, in megabytes.
+///
+const HINT_HASH: usize = 8;
+
+/// A search for a hint, running in another thread so the game carries on
+/// while it looks.
+///
+struct PendingHint {
+    /// The hash of the position the hint is for.
+    key: u64,
+    stop: Arc<AtomicBool>,
+    /// The search, until its move is taken.
+    thread: Option<JoinHandle<Option<(Vec2, Move)>>>,
+}
+
+impl Drop for PendingHint {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Hints for the player to move.
+///
+impl Chess {
+    /// Start searching the position for a moment. The move found is
+    /// highlighted by [`Chess::update_hint`], without playing it, and kept
+    /// in the game record with the move that is then played.
+    ///
+    pub fn hint(&mut self) {
+        if self.outcome.is_some() || self.is_editing() {
+            return;
+        }
+
+        // the referee knows things about the position that we don't
+        //
+        if self.refereed {
+            self.view.message = Some("No hints in a refereed game".into());
+            return;
+        }
+
+        // a puzzle has its own answer
+        //
+        if self.puzzle_hint() {
+            return;
+        }
+
+        let key = position_hash(&self.board, self.turn);
+        if self
+            .extensions
+            .get::<PendingHint>()
+            .is_some_and(|p| p.key == key)
+        {
+            return;
+        }
+
+        let mut search = Search::new(HINT_HASH);
+        search.tablebase = self.tablebase();
+
+        let stop = search.stop_flag();
+        let board = self.board.clone();
+        let turn = self.turn;
+
+        let thread = thread::spawn(move || {
+            search
+                .run(&board, turn, Limits::time(HINT_TIME), |_| {})
+                .and_then(|info| info.best())
+        });
+
+        self.extensions.insert(PendingHint {
+            key,
+            stop,
+            thread: Some(thread),
+        });
+        self.view.message = Some("Looking for a hint...".into());
+    }
+
+    /// Highlight the move found by a search for a hint once it is over,
+    /// or give the search up if the position has changed since.
+    ///
+    pub fn update_hint(&mut self) {
+        let Some(pending) = self.extensions.get::<PendingHint>() else {
+            return;
+        };
+
+        let key = position_hash(&self.board, self.turn);
+        if pending.key != key {
+            self.extensions.remove::<PendingHint>();
+            return;
+        }
+
+        if !pending.thread.as_ref().is_some_and(JoinHandle::is_finished) {
+            return;
+        }
+
+        let best = self
+            .extensions
+            .remove::<PendingHint>()
+            .and_then(|mut pending| pending.thread.take()?.join().ok().flatten());
+
+        let Some((from, m)) = best else {
+            self.view.message = Some("No move to suggest".into());
+            return;
+        };
+
+        self.view.hint = Some((key, from, m.pos));
+        self.view.message = Some(format!("Hint: {}", notation::san(&self.board, from, &m)));
+    }
+
+    /// The number of moves `player` asked for a hint before playing.
+    ///
+    pub fn hints(&self, player: Player) -> usize {
+        self.history
+            .iter()
+            .filter(|record| record.player == player && record.hinted)
+            .count()
+    }
+}
@@ -0,0 +1,18 @@
+mod analysis;
+pub mod book;
+pub mod eval;
+mod hint;
+mod ordering;
+mod review;
+mod search;
+mod solver;
+pub mod syzygy;
+mod tt;
+pub mod uci;
+
+pub use analysis::*;
+pub use ordering::*;
+pub use review::*;
+pub use search::*;
+pub use solver::*;
+pub use tt::*;
@@ -0,0 +1,266 @@
+use crate::core::{Board, Move, MoveResult, Player, Vec2};
+
+/// The deepest a search goes, counting from the root.
+///
+pub const MAX_PLY: usize = 128;
+
+/// Whether two moves are the same move of the same piece.
+///
+pub fn same_move(a: &(Vec2, Move), b: &(Vec2, Move)) -> bool {
+    a.0 == b.0 && a.1.pos == b.1.pos && a.1.result == b.1.result
+}
+
+/// Whether a move changes the material on the board, by capturing or
+/// promoting. Quiet moves are everything else.
+///
+pub fn is_tactical(board: &Board, m: &Move) -> bool {
+    board.is_capture(m) || matches!(m.result, MoveResult::Promotion(_))
+}
+
+/// Most valuable victim, least valuable attacker: capturing a queen
+/// with a pawn is tried before capturing a pawn with a queen.
+///
+pub fn mvv_lva(board: &Board, from: Vec2, m: &Move) -> i32 {
+    let victim = match m.result {
+        MoveResult::Capture(pos) => board.get(pos),
+        MoveResult::Promotion(_) => board.get(m.pos),
+        _ => None,
+    };
+    let victim = victim.map_or(0, |p| p.ty.value());
+
+    let attacker = board.get(from).map_or(0, |p| p.ty.value());
+
+    let promotion = match m.result {
+        MoveResult::Promotion(ty) => ty.value(),
+        _ => 0,
+    };
+
+    (victim + promotion) * 100 - attacker
+}
+
+/// Quiet moves that caused a cutoff at each ply, which are likely to do
+/// so again in sibling positions.
+///
+pub struct Killers {
+    moves: Vec<[Option<(Vec2, Move)>; 2]>,
+}
+
+impl Killers {
+    pub fn new() -> Self {
+        Self {
+            moves: vec![[None; 2]; MAX_PLY],
+        }
+    }
+
+    pub fn add(&mut self, ply: usize, m: (Vec2, Move)) {
+        let Some(slots) = self.moves.get_mut(ply) else {
+            return;
+        };
+
+        if !matches!(&slots[0], Some(first) if same_move(first, &m)) {
+            slots[1] = slots[0];
+            slots[0] = Some(m);
+        }
+    }
+
+    /// Which of the ply's killers the move is, 0 being the most recent.
+    ///
+    pub fn rank(&self, ply: usize, m: &(Vec2, Move)) -> Option<usize> {
+        self.moves
+            .get(ply)?
+            .iter()
+            .position(|killer| matches!(killer, Some(k) if same_move(k, m)))
+    }
+
+    pub fn clear(&mut self) {
+        self.moves.fill([None; 2]);
+    }
+}
+
+impl Default for Killers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How often each quiet move, by player and squares, has caused a cutoff,
+/// weighted towards cutoffs found deeper in the tree.
+///
+pub struct History {
+    squares: usize,
+    scores: Vec<i32>,
+}
+
+impl History {
+    /// A history for boards of up to `squares` squares.
+    ///
+    pub fn new(squares: usize) -> Self {
+        Self {
+            squares,
+            scores: vec![0; 2 * squares * squares],
+        }
+    }
+
+    pub fn add(&mut self, board: &Board, player: Player, m: &(Vec2, Move), depth: u32) {
+        if let Some(i) = self.index(board, player, m) {
+            self.scores[i] = self.scores[i].saturating_add((depth * depth) as i32);
+        }
+    }
+
+    pub fn get(&self, board: &Board, player: Player, m: &(Vec2, Move)) -> i32 {
+        self.index(board, player, m).map_or(0, |i| self.scores[i])
+    }
+
+    /// Halve every score, so what was learned in earlier searches counts
+    /// for less than what is learned now.
+    ///
+    pub fn age(&mut self) {
+        for score in self.scores.iter_mut() {
+            *score /= 2;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.scores.fill(0);
+    }
+
+    fn index(&self, board: &Board, player: Player, (from, m): &(Vec2, Move)) -> Option<usize> {
+        let square = |pos: Vec2| (pos.y * board.width() + pos.x) as usize;
+        let (from, to) = (square(*from), square(m.pos));
+
+        (from < self.squares && to < self.squares)
+            .then(|| (player as usize * self.squares + from) * self.squares + to)
+    }
+}
+
+/// The heuristics a search keeps for putting the moves most likely to be
+/// best first, which lets alpha-beta prune far more of the tree.
+///
+pub struct MoveOrdering {
+    pub killers: Killers,
+    pub history: History,
+}
+
+impl MoveOrdering {
+    pub fn new(squares: usize) -> Self {
+        Self {
+            killers: Killers::new(),
+            history: History::new(squares),
+        }
+    }
+
+    /// Sort `moves` of `player` at `ply`: the move the transposition
+    /// table remembers as best, then captures and promotions by
+    /// [`mvv_lva`], then killer moves, then quiet moves by history.
+    ///
+    pub fn sort(
+        &self,
+        board: &Board,
+        player: Player,
+        ply: usize,
+        best: Option<(Vec2, Move)>,
+        moves: &mut [(Vec2, Move)],
+    ) {
+        let score = |m: &(Vec2, Move)| {
+            if matches!(&best, Some(best) if same_move(best, m)) {
+                return i32::MAX;
+            }
+            if is_tactical(board, &m.1) {
+                return (1 << 30) + mvv_lva(board, m.0, &m.1);
+            }
+            match self.killers.rank(ply, m) {
+                Some(rank) => (1 << 29) - rank as i32,
+                None => self.history.get(board, player, m).min((1 << 29) - 2),
+            }
+        };
+
+        moves.sort_by_cached_key(|m| std::cmp::Reverse(score(m)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::fen;
+
+    /// The legal move from `from` to `to` on the board.
+    ///
+    fn find(board: &Board, from: (i16, i16), to: (i16, i16)) -> (Vec2, Move) {
+        board
+            .all_legal_moves(Player::White)
+            .into_iter()
+            .find(|(pos, m)| *pos == Vec2::new(from.0, from.1) && m.pos == Vec2::new(to.0, to.1))
+            .unwrap()
+    }
+
+    #[test]
+    fn killers() {
+        let board = fen::parse("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap().board;
+        let (d1, e2, f2) = (
+            find(&board, (4, 7), (3, 7)),
+            find(&board, (4, 7), (4, 6)),
+            find(&board, (4, 7), (5, 6)),
+        );
+        let mut killers = Killers::new();
+
+        killers.add(3, d1);
+        killers.add(3, e2);
+        killers.add(3, e2);
+        assert_eq!(killers.rank(3, &e2), Some(0));
+        assert_eq!(killers.rank(3, &d1), Some(1));
+        assert_eq!(killers.rank(2, &e2), None);
+
+        // only two are kept
+        //
+        killers.add(3, f2);
+        assert_eq!(killers.rank(3, &f2), Some(0));
+        assert_eq!(killers.rank(3, &e2), Some(1));
+        assert_eq!(killers.rank(3, &d1), None);
+    }
+
+    #[test]
+    fn history() {
+        let board = fen::parse("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap().board;
+        let e2 = find(&board, (4, 7), (4, 6));
+        let mut history = History::new(64);
+
+        history.add(&board, Player::White, &e2, 3);
+        history.add(&board, Player::White, &e2, 2);
+        assert_eq!(history.get(&board, Player::White, &e2), 13);
+        assert_eq!(history.get(&board, Player::Black, &e2), 0);
+
+        history.age();
+        assert_eq!(history.get(&board, Player::White, &e2), 6);
+    }
+
+    #[test]
+    fn move_order() {
+        // the pawn and queen can both take the queen, and the queen a pawn
+        //
+        let board = fen::parse("4k3/8/8/3q4/p3P3/8/8/3QK3 w - - 0 1")
+            .unwrap()
+            .board;
+        let pxq = find(&board, (4, 4), (3, 3));
+        let qxq = find(&board, (3, 7), (3, 3));
+        let qxp = find(&board, (3, 7), (0, 4));
+        let hash = find(&board, (4, 7), (5, 7));
+        let killer = find(&board, (4, 7), (4, 6));
+        let quiet = find(&board, (4, 7), (5, 6));
+
+        assert!(mvv_lva(&board, pxq.0, &pxq.1) > mvv_lva(&board, qxq.0, &qxq.1));
+        assert!(mvv_lva(&board, qxq.0, &qxq.1) > mvv_lva(&board, qxp.0, &qxp.1));
+
+        let mut ordering = MoveOrdering::new(64);
+        ordering.killers.add(0, killer);
+        ordering.history.add(&board, Player::White, &quiet, 4);
+
+        let mut moves = board.all_legal_moves(Player::White);
+        ordering.sort(&board, Player::White, 0, Some(hash), &mut moves);
+
+        let expected = [hash, pxq, qxq, qxp, killer, quiet];
+        assert!(moves.len() > expected.len());
+        for (m, expected) in moves.iter().zip(&expected) {
+            assert!(same_move(m, expected));
+        }
+    }
+}
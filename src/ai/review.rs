@@ -0,0 +1,603 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use crate::core::{
+    fen::{self, CastlingNotation},
+    notation,
+    pgn::{self, Annotated},
+    Board, Chess, Move, Outcome, Player, Vec2,
+};
+
+use super::{eval::Centipawns, mate_in, Limits, MoveCode, Search, MATE};
+
+/// How deep each position of the game is searched.
+///
+pub const REVIEW_DEPTH: u32 = 4;
+
+/// Scores are capped at this many centipawns either way when judging
+/// moves.
+///
+const SCORE_CAP: Centipawns = 1000;
+
+/// The transposition table size for a review, in megabytes.
+///
+const REVIEW_HASH: usize = 16;
+
+/// What the review makes of a move, by how much worse it left the
+/// position than the best move would have.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Judgement {
+    Best,
+    /// Not the best move, but not much worse.
+    Good,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Judgement {
+    /// The judgement on a move that lost `drop` centipawns against the
+    /// best move.
+    ///
+    fn of(drop: Centipawns) -> Self {
+        match drop {
+            d if d <= 0 => Judgement::Best,
+            d if d < 50 => Judgement::Good,
+            d if d < 100 => Judgement::Inaccuracy,
+            d if d < 300 => Judgement::Mistake,
+            _ => Judgement::Blunder,
+        }
+    }
+
+    /// The symbol a move is annotated with, e.g. `??` for a blunder.
+    ///
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Judgement::Best | Judgement::Good => "",
+            Judgement::Inaccuracy => "?!",
+            Judgement::Mistake => "?",
+            Judgement::Blunder => "??",
+        }
+    }
+
+    /// The numeric annotation glyph for the judgement in PGN.
+    ///
+    pub fn nag(&self) -> Option<u8> {
+        match self {
+            Judgement::Best | Judgement::Good => None,
+            Judgement::Inaccuracy => Some(6),
+            Judgement::Mistake => Some(2),
+            Judgement::Blunder => Some(4),
+        }
+    }
+}
+
+impl fmt::Display for Judgement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Judgement::Best => "Best",
+            Judgement::Good => "Good",
+            Judgement::Inaccuracy => "Inaccuracy",
+            Judgement::Mistake => "Mistake",
+            Judgement::Blunder => "Blunder",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The review of one move.
+///
+#[derive(Clone)]
+pub struct MoveReview {
+    pub player: Player,
+    pub san: String,
+    /// The search's score before and after the move, for the player
+    /// making it.
+    pub before: Centipawns,
+    pub after: Centipawns,
+    /// The move the search preferred, in SAN, if it wasn't the one played.
+    pub best: Option<String>,
+    pub judgement: Judgement,
+    /// How close to the best move it was, from 0 to 100.
+    pub accuracy: f64,
+}
+
+impl MoveReview {
+    /// The score after the move in white's favour, e.g. `+0.35` or `#-2`,
+    /// unless the move ended the game.
+    ///
+    pub fn eval(&self) -> Option<String> {
+        let sign = match self.player {
+            Player::White => 1,
+            Player::Black => -1,
+        };
+
+        // the score for the opponent, to move next, counts the mate from
+        // their move
+        //
+        match mate_in(-self.after) {
+            Some(0) => None,
+            Some(mate) => Some(format!("#{}", -mate * sign)),
+            None => Some(format!("{:+.2}", (self.after * sign) as f64 / 100.0)),
+        }
+    }
+}
+
+/// A move by move report on a game, as searched at [`REVIEW_DEPTH`].
+///
+#[derive(Clone)]
+pub struct Review {
+    pub moves: Vec<MoveReview>,
+    /// The first move's number, and whether black played it.
+    pub first_move: u32,
+    pub black_first: bool,
+}
+
+impl Review {
+    /// Search every position of a game and judge each move played. The
+    /// count of positions searched so far is kept in `progress`.
+    ///
+    fn new(game: GameMoves, progress: &AtomicUsize) -> Self {
+        let GameMoves {
+            positions,
+            moves,
+            first_move,
+        } = game;
+        let mut search = Search::new(REVIEW_HASH);
+
+        // each position is searched once, as the position after one move
+        // and before the next
+        //
+        let mut scores = Vec::with_capacity(positions.len());
+        for (board, turn) in &positions {
+            let info = search.run(board, *turn, Limits::depth(REVIEW_DEPTH), |_| {});
+            let score = info
+                .as_ref()
+                .map_or_else(|| final_score(board, *turn), |info| info.score);
+            scores.push((score, info.and_then(|info| info.best())));
+            progress.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let moves = moves
+            .iter()
+            .enumerate()
+            .map(|(i, (from, m, san))| {
+                let (board, player) = &positions[i];
+                let (before, best) = scores[i];
+                let after = -scores[i + 1].0;
+
+                let played = best.is_some_and(|best| MoveCode::new(*from, m).matches(&best));
+                let judgement = match played {
+                    true => Judgement::Best,
+                    false => Judgement::of(capped(before) - capped(after)).max(Judgement::Good),
+                };
+
+                MoveReview {
+                    player: *player,
+                    san: san.clone(),
+                    before,
+                    after,
+                    best: best
+                        .filter(|_| !played)
+                        .map(|(from, m)| notation::san(board, from, &m)),
+                    judgement,
+                    accuracy: accuracy(capped(before), capped(after)),
+                }
+            })
+            .collect();
+
+        Review {
+            moves,
+            first_move,
+            black_first: positions.first().is_some_and(|(_, p)| *p == Player::Black),
+        }
+    }
+
+    /// The average accuracy of `player`'s moves, from 0 to 100.
+    ///
+    pub fn accuracy(&self, player: Player) -> Option<f64> {
+        let moves: Vec<_> = self.moves.iter().filter(|m| m.player == player).collect();
+        if moves.is_empty() {
+            return None;
+        }
+
+        Some(moves.iter().map(|m| m.accuracy).sum::<f64>() / moves.len() as f64)
+    }
+
+    /// How many of `player`'s moves were given the judgement.
+    ///
+    pub fn count(&self, player: Player, judgement: Judgement) -> usize {
+        self.moves
+            .iter()
+            .filter(|m| m.player == player && m.judgement == judgement)
+            .count()
+    }
+
+    /// The game as PGN, each move annotated with the score after it,
+    /// and the bad ones with what was better.
+    ///
+    pub fn to_pgn(&self, game: &Chess) -> String {
+        let mut tags: Vec<(String, String)> = [
+            ("Event", "Casual game"),
+            ("Site", "?"),
+            ("Date", "????.??.??"),
+            ("Round", "-"),
+            ("White", "White"),
+            ("Black", "Black"),
+            ("Result", pgn::result(game.outcome)),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+
+        let variant = game.board.variant.name();
+        if variant != "standard" {
+            tags.push(("Variant".into(), variant.into()));
+        }
+
+        // a game that didn't start from the variant's start position
+        // needs the position it did start from
+        //
+        if let Some(first) = game.history.first() {
+            let start = game.board.variant.start_position();
+            let fen = fen::write(
+                &first.board,
+                first.player,
+                self.first_move,
+                CastlingNotation::XFen,
+            );
+            let standard = fen::write(&start, Player::White, 1, CastlingNotation::XFen);
+            if fen != standard {
+                tags.push(("SetUp".into(), "1".into()));
+                tags.push(("FEN".into(), fen));
+            }
+        }
+
+        for player in [Player::White, Player::Black] {
+            if let Some(accuracy) = self.accuracy(player) {
+                tags.push((format!("{}Accuracy", player), format!("{:.1}", accuracy)));
+            }
+        }
+
+        tags.push(("Annotator".into(), env!("CARGO_PKG_NAME").into()));
+
+        let moves: Vec<_> = self
+            .moves
+            .iter()
+            .map(|m| {
+                let comment = match (m.eval(), &m.best, m.judgement >= Judgement::Inaccuracy) {
+                    (Some(eval), Some(best), true) => {
+                        Some(format!("{}. {}. {} was best.", eval, m.judgement, best))
+                    }
+                    (eval, ..) => eval,
+                };
+
+                Annotated {
+                    san: m.san.clone(),
+                    nag: m.judgement.nag(),
+                    comment,
+                }
+            })
+            .collect();
+
+        pgn::write(
+            &tags,
+            self.first_move,
+            self.black_first,
+            &moves,
+            pgn::result(game.outcome),
+        )
+    }
+}
+
+/// A review worked out in the background, and how far it has got.
+///
+pub struct PendingReview {
+    /// The number of moves in the game when the review was started.
+    moves: usize,
+    progress: Arc<AtomicUsize>,
+    thread: JoinHandle<Review>,
+}
+
+impl PendingReview {
+    /// The positions searched so far, out of the number there are.
+    ///
+    pub fn progress(&self) -> (usize, usize) {
+        (self.progress.load(Ordering::Relaxed), self.moves + 1)
+    }
+}
+
+/// The state of the review of a game.
+///
+pub enum GameReview {
+    Pending(PendingReview),
+    Done(Review),
+}
+
+/// Reviewing the game once it is over, or at any point along the way.
+///
+impl Chess {
+    /// Start reviewing the moves played so far in the background,
+    /// unless they already have been.
+    ///
+    pub fn start_review(&mut self) {
+        let moves = self.history.len();
+
+        let current = match self.extensions.get::<GameReview>() {
+            Some(GameReview::Pending(pending)) => pending.moves == moves,
+            Some(GameReview::Done(review)) => review.moves.len() == moves,
+            None => false,
+        };
+        if current {
+            return;
+        }
+
+        // the search works on a copy of the moves, as the player may go
+        // on moving while it runs
+        //
+        let game = GameMoves::new(self);
+
+        let progress = Arc::new(AtomicUsize::new(0));
+        let counter = progress.clone();
+
+        self.extensions.insert(GameReview::Pending(PendingReview {
+            moves,
+            progress,
+            thread: thread::spawn(move || Review::new(game, &counter)),
+        }));
+    }
+
+    /// The review of the game, once it is done.
+    ///
+    pub fn review(&mut self) -> Option<&Review> {
+        if let Some(GameReview::Pending(pending)) = self.extensions.get::<GameReview>() {
+            if pending.thread.is_finished() {
+                let Some(GameReview::Pending(pending)) = self.extensions.remove::<GameReview>()
+                else {
+                    unreachable!();
+                };
+                if let Ok(review) = pending.thread.join() {
+                    self.extensions.insert(GameReview::Done(review));
+                }
+            }
+        }
+
+        match self.extensions.get::<GameReview>() {
+            Some(GameReview::Done(review)) => Some(review),
+            _ => None,
+        }
+    }
+
+    /// The positions searched so far by a review under way, out of the
+    /// number there are.
+    ///
+    pub fn review_progress(&self) -> Option<(usize, usize)> {
+        match self.extensions.get::<GameReview>() {
+            Some(GameReview::Pending(pending)) => Some(pending.progress()),
+            _ => None,
+        }
+    }
+
+    /// Open the review screen, starting the review if need be, or close it.
+    ///
+    pub fn toggle_review(&mut self) {
+        if self.view.review.is_some() {
+            self.view.review = None;
+            return;
+        }
+
+        if self.history.is_empty() {
+            self.view.message = Some("No moves to review".into());
+            return;
+        }
+
+        if self.refereed {
+            self.view.message = Some("No review of a refereed game".into());
+            return;
+        }
+
+        self.start_review();
+        self.view.review = Some(0);
+    }
+
+    /// Move the selection on the review screen `step` moves on, or back.
+    ///
+    pub fn select_review(&mut self, step: isize) {
+        let Some(selected) = self.view.review else {
+            return;
+        };
+
+        let last = self.history.len().saturating_sub(1);
+        self.view.review = Some(selected.saturating_add_signed(step).min(last));
+    }
+
+    /// Write the reviewed game to `path` as annotated PGN.
+    ///
+    pub fn save_review(&mut self, path: &str) -> Result<(), String> {
+        if self.history.is_empty() {
+            return Err("No moves to review".into());
+        }
+
+        self.start_review();
+
+        let Some(review) = self.review().cloned() else {
+            let (done, total) = self.review_progress().unwrap_or_default();
+            return Err(format!(
+                "Still reviewing, {} of {} positions searched",
+                done, total
+            ));
+        };
+
+        std::fs::write(path, review.to_pgn(self))
+            .map_err(|err| format!("Unable to write {}: {}", path, err))
+    }
+}
+
+/// The positions of a game, the one before each move and the final
+/// one, and each move as played.
+///
+struct GameMoves {
+    positions: Vec<(Board, Player)>,
+    moves: Vec<(Vec2, Move, String)>,
+    first_move: u32,
+}
+
+impl GameMoves {
+    fn new(game: &Chess) -> Self {
+        let mut positions: Vec<_> = game
+            .history
+            .iter()
+            .map(|r| (r.board.clone(), r.player))
+            .collect();
+        positions.push((game.board.clone(), game.turn));
+
+        let moves = game
+            .history
+            .iter()
+            .map(|r| (r.from, Move::new(r.to, r.result), r.san.clone()))
+            .collect();
+
+        Self {
+            positions,
+            moves,
+            first_move: game.first_move,
+        }
+    }
+}
+
+/// The score of a position with no moves to search, for the player to move.
+///
+fn final_score(board: &Board, turn: Player) -> Centipawns {
+    match board.variant.outcome(board, turn) {
+        Some(Outcome::Win(player, _)) if player == turn => MATE,
+        Some(Outcome::Win(..)) => -MATE,
+        _ => 0,
+    }
+}
+
+/// The chance of winning, from 0 to 100, that a score gives, by the
+/// curve fitted to human games that is commonly used for accuracy.
+///
+fn win_chance(score: Centipawns) -> f64 {
+    50.0 + 50.0 * (2.0 / (1.0 + (-0.00368208 * score as f64).exp()) - 1.0)
+}
+
+/// The accuracy of a move that took the score from `before` to `after`,
+/// from 0 to 100.
+///
+fn accuracy(before: Centipawns, after: Centipawns) -> f64 {
+    let lost = (win_chance(before) - win_chance(after)).max(0.0);
+
+    (103.1668 * (-0.04354 * lost).exp() - 3.1669).clamp(0.0, 100.0)
+}
+
+/// A score with mates brought down to [`SCORE_CAP`], so going from a
+/// mate to a won position isn't taken for a blunder.
+///
+fn capped(score: Centipawns) -> Centipawns {
+    score.clamp(-SCORE_CAP, SCORE_CAP)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review(player: Player, after: Centipawns) -> MoveReview {
+        MoveReview {
+            player,
+            san: String::new(),
+            before: 0,
+            after,
+            best: None,
+            judgement: Judgement::Best,
+            accuracy: 100.0,
+        }
+    }
+
+    #[test]
+    fn judgements() {
+        let drops = [
+            (-20, Judgement::Best),
+            (0, Judgement::Best),
+            (49, Judgement::Good),
+            (50, Judgement::Inaccuracy),
+            (99, Judgement::Inaccuracy),
+            (100, Judgement::Mistake),
+            (299, Judgement::Mistake),
+            (300, Judgement::Blunder),
+        ];
+
+        for (drop, judgement) in drops {
+            assert_eq!(Judgement::of(drop), judgement, "{}", drop);
+        }
+    }
+
+    #[test]
+    fn accuracies() {
+        assert!(accuracy(120, 120) > 99.9);
+        assert!(accuracy(0, 200) > 99.9);
+        assert!(accuracy(300, -300) < 20.0);
+        assert!(accuracy(0, -50) > accuracy(0, -150));
+
+        // a mate thrown away for a winning position costs nothing
+        //
+        assert!(accuracy(capped(MATE - 5), capped(1200)) > 99.9);
+    }
+
+    #[test]
+    fn evals_from_whites_side() {
+        assert_eq!(review(Player::White, 35).eval().unwrap(), "+0.35");
+        assert_eq!(review(Player::Black, 35).eval().unwrap(), "-0.35");
+        assert_eq!(review(Player::Black, -120).eval().unwrap(), "+1.20");
+
+        // mate in two from the move, by either side
+        //
+        assert_eq!(review(Player::White, MATE - 4).eval().unwrap(), "#2");
+        assert_eq!(review(Player::White, -(MATE - 3)).eval().unwrap(), "#-2");
+        assert_eq!(review(Player::Black, MATE - 4).eval().unwrap(), "#-2");
+        assert_eq!(review(Player::Black, -(MATE - 3)).eval().unwrap(), "#2");
+
+        // nothing after the mate itself
+        //
+        assert_eq!(review(Player::White, MATE).eval(), None);
+    }
+
+    #[test]
+    fn hanging_the_queen_is_a_blunder() {
+        // Qd4, where the pawn takes it
+        //
+        let board = fen::parse("4k3/8/8/4p3/8/8/8/3QK3 w - - 0 1")
+            .unwrap()
+            .board;
+        let (d1, d4) = (Vec2::new(3, 7), Vec2::new(3, 4));
+        let m = board
+            .get_legal_moves(d1, false)
+            .into_iter()
+            .find(|m| m.pos == d4)
+            .unwrap();
+
+        let mut after = board.clone();
+        after.make_move(d1, &m);
+
+        let game = GameMoves {
+            positions: vec![(board, Player::White), (after, Player::Black)],
+            moves: vec![(d1, m, "Qd4".into())],
+            first_move: 1,
+        };
+        let progress = AtomicUsize::new(0);
+        let review = Review::new(game, &progress);
+
+        assert_eq!(progress.load(Ordering::Relaxed), 2);
+        assert_eq!(review.moves[0].judgement, Judgement::Blunder);
+        assert!(review.moves[0].best.is_some());
+        assert!(review.accuracy(Player::White).unwrap() < 10.0);
+        assert_eq!(review.accuracy(Player::Black), None);
+    }
+}
@@ -0,0 +1,837 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::core::{hash::position_hash, Board, Move, Outcome, Player, Vec2};
+
+use super::{
+    eval::{self, Centipawns},
+    ordering::{is_tactical, same_move, MoveOrdering, MAX_PLY},
+    syzygy::{Tablebase, Wdl},
+    tt::{Bound, MoveCode, TranspositionTable},
+};
+
+/// The score of delivering mate on the move. Mates further off score
+/// less by one for each ply.
+///
+pub const MATE: Centipawns = 30_000;
+
+const INFINITY: Centipawns = 32_000;
+
+/// Scores this close to [`MATE`] are forced mates rather than
+/// evaluations.
+///
+const MATE_BOUND: Centipawns = MATE - MAX_PLY as Centipawns;
+
+/// The score of a position the tablebases say is won, less one for each
+/// ply from the root. It is above any evaluation but below the mates.
+///
+const TABLEBASE_WIN: Centipawns = MATE_BOUND - MAX_PLY as Centipawns;
+
+/// How often, in nodes, the search looks at the clock.
+///
+const CHECK_INTERVAL: u64 = 1024;
+
+/// When a search should stop, if it isn't stopped from outside first.
+/// With no limits at all it goes on until stopped. The first depth is
+/// always finished, however little time or few nodes are allowed.
+///
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Limits {
+    pub depth: Option<u32>,
+    pub time: Option<Duration>,
+    pub nodes: Option<u64>,
+}
+
+impl Limits {
+    pub fn depth(depth: u32) -> Self {
+        Self {
+            depth: Some(depth),
+            ..Default::default()
+        }
+    }
+
+    pub fn time(time: Duration) -> Self {
+        Self {
+            time: Some(time),
+            ..Default::default()
+        }
+    }
+}
+
+/// The result of searching to one depth.
+///
+#[derive(Clone)]
+pub struct SearchInfo {
+    pub depth: u32,
+    /// The score for the player to move.
+    pub score: Centipawns,
+    pub nodes: u64,
+    pub time: Duration,
+    /// The moves both sides are expected to play, best first.
+    pub pv: Vec<(Vec2, Move)>,
+    /// The best few lines, best first, when more than one was asked
+    /// for with [`Search::set_lines`]. The first is `score` and `pv`.
+    pub lines: Vec<Line>,
+}
+
+/// One of the lines a search found, starting with a different move from
+/// the others.
+///
+#[derive(Clone)]
+pub struct Line {
+    /// The score for the player to move.
+    pub score: Centipawns,
+    pub pv: Vec<(Vec2, Move)>,
+}
+
+impl Line {
+    pub fn mate(&self) -> Option<i32> {
+        mate_in(self.score)
+    }
+}
+
+impl SearchInfo {
+    pub fn best(&self) -> Option<(Vec2, Move)> {
+        self.pv.first().copied()
+    }
+
+    /// Nodes searched per second.
+    ///
+    pub fn nps(&self) -> u64 {
+        (self.nodes as f64 / self.time.as_secs_f64().max(0.001)) as u64
+    }
+
+    /// The number of moves to a forced mate, negative when the player
+    /// to move is the one being mated.
+    ///
+    pub fn mate(&self) -> Option<i32> {
+        mate_in(self.score)
+    }
+}
+
+/// The moves to mate that a search score stands for, if it is a mate
+/// score: positive when the player to move mates, negative when mated.
+///
+pub fn mate_in(score: Centipawns) -> Option<i32> {
+    if score > MATE_BOUND {
+        Some((MATE - score + 1) / 2)
+    } else if score < -MATE_BOUND {
+        Some(-(MATE + score) / 2)
+    } else {
+        None
+    }
+}
+
+/// An alpha-beta searcher, deepening one ply at a time, that keeps its
+/// transposition table and move ordering from one search to the next.
+///
+/// With more than one thread the search is a lazy SMP one: every thread
+/// searches the same position, some a ply deeper than others, and they
+/// help each other only through the transposition table they share.
+///
+pub struct Search {
+    pub tt: Arc<TranspositionTable>,
+    /// Endgame tables to look positions up in, if there are any.
+    pub tablebase: Option<Arc<Tablebase>>,
+    /// The move ordering of each thread.
+    orderings: Vec<MoveOrdering>,
+    threads: usize,
+    lines: usize,
+    squares: usize,
+    stop: Arc<AtomicBool>,
+}
+
+impl Search {
+    /// A searcher with a transposition table of about `megabytes`,
+    /// searching on one thread.
+    ///
+    pub fn new(megabytes: usize) -> Self {
+        Self {
+            tt: Arc::new(TranspositionTable::new(megabytes)),
+            tablebase: None,
+            orderings: Vec::new(),
+            threads: 1,
+            lines: 1,
+            squares: 64,
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// A flag that ends the search in progress when set, from any thread.
+    /// It is cleared when the search returns.
+    ///
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Search on `threads` threads, at least one.
+    ///
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+        self.orderings.truncate(self.threads);
+    }
+
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+
+    /// Find the best `lines` moves rather than only the best one, each
+    /// with its own line of play, at least one.
+    ///
+    pub fn set_lines(&mut self, lines: usize) {
+        self.lines = lines.max(1);
+    }
+
+    /// Replace the transposition table with an empty one of about
+    /// `megabytes`.
+    ///
+    pub fn set_hash(&mut self, megabytes: usize) {
+        self.tt = Arc::new(TranspositionTable::new(megabytes));
+    }
+
+    /// Forget everything learned, as for a new game.
+    ///
+    pub fn clear(&mut self) {
+        self.tt.clear();
+        for ordering in &mut self.orderings {
+            ordering.killers.clear();
+            ordering.history.clear();
+        }
+    }
+
+    /// Search the position for the player to move, `turn`, until the
+    /// limits are reached or the search is stopped. `report` is called
+    /// as each depth is completed, and the result of the deepest one is
+    /// returned. There is none when there are no legal moves, or when
+    /// the search was stopped from outside before the first depth was
+    /// done.
+    ///
+    pub fn run(
+        &mut self,
+        board: &Board,
+        turn: Player,
+        limits: Limits,
+        report: impl FnMut(&SearchInfo),
+    ) -> Option<SearchInfo> {
+        let result = self.search(board, turn, limits, report);
+
+        // cleared at the end rather than the start, so a stop sent just
+        // after the search was started isn't lost
+        //
+        self.stop.store(false, Ordering::Relaxed);
+
+        result
+    }
+
+    fn search(
+        &mut self,
+        board: &Board,
+        turn: Player,
+        limits: Limits,
+        mut report: impl FnMut(&SearchInfo),
+    ) -> Option<SearchInfo> {
+        let squares = (board.width() * board.height()) as usize;
+        if squares > self.squares {
+            self.orderings.clear();
+            self.squares = squares;
+        }
+        while self.orderings.len() < self.threads {
+            self.orderings.push(MoveOrdering::new(self.squares));
+        }
+
+        self.tt.new_search();
+        for ordering in &mut self.orderings {
+            ordering.killers.clear();
+            ordering.history.age();
+        }
+
+        if board.all_legal_moves(turn).is_empty() {
+            return None;
+        }
+
+        let shared = Shared {
+            tt: &self.tt,
+            tablebase: self.tablebase.as_deref(),
+            root_moves: self.tablebase_root_moves(board, turn),
+            stop: &self.stop,
+            searched: AtomicBool::new(false),
+            nodes: AtomicU64::new(0),
+            start: Instant::now(),
+            limits,
+        };
+
+        let (main, helpers) = self.orderings.split_first_mut()?;
+
+        std::thread::scope(|scope| {
+            // half the helpers keep a ply ahead of the main thread, so
+            // between them they fill the table for its next depth
+            //
+            for (i, ordering) in helpers.iter_mut().enumerate() {
+                let shared = &shared;
+                scope.spawn(move || {
+                    Worker::new(shared, ordering, 1).deepen(
+                        board,
+                        turn,
+                        1 + (i as u32 + 1) % 2,
+                        |_| {},
+                    )
+                });
+            }
+
+            let result = Worker::new(&shared, main, self.lines).deepen(board, turn, 1, |info| {
+                shared.searched.store(true, Ordering::Relaxed);
+                report(info);
+            });
+
+            // the helpers are only of use while the main thread searches
+            //
+            self.stop.store(true, Ordering::Relaxed);
+
+            result
+        })
+    }
+
+    /// The root moves that keep the best result the tablebases give,
+    /// when the root position is in them. Of the wins only those
+    /// quickest to the next capture or pawn move are kept, so the search
+    /// can't wander and the win is always made.
+    ///
+    fn tablebase_root_moves(&self, board: &Board, turn: Player) -> Vec<(Vec2, Move)> {
+        let Some(moves) = self
+            .tablebase
+            .as_ref()
+            .and_then(|tb| tb.root_moves(board, turn))
+        else {
+            return Vec::new();
+        };
+
+        let Some(best) = moves.first().copied() else {
+            return Vec::new();
+        };
+
+        moves
+            .iter()
+            .filter(|m| m.wdl == best.wdl && (best.wdl < Wdl::CursedWin || m.dtz == best.dtz))
+            .map(|m| (m.from, m.m))
+            .collect()
+    }
+}
+
+/// What the threads of one search have in common.
+///
+struct Shared<'a> {
+    tt: &'a TranspositionTable,
+    tablebase: Option<&'a Tablebase>,
+    /// The root moves worth searching, when the tablebases have ruled
+    /// out the rest.
+    root_moves: Vec<(Vec2, Move)>,
+    stop: &'a AtomicBool,
+    /// Set once the main thread has finished a depth, and so has a move
+    /// to play. Until then the limits are ignored.
+    searched: AtomicBool,
+    /// The nodes searched by all the threads, as each last counted them.
+    nodes: AtomicU64,
+    start: Instant,
+    limits: Limits,
+}
+
+/// One thread of a search.
+///
+struct Worker<'a> {
+    shared: &'a Shared<'a>,
+    ordering: &'a mut MoveOrdering,
+    /// The number of lines to find.
+    lines: usize,
+    /// Root moves left out, as they start the lines already found.
+    excluded: Vec<(Vec2, Move)>,
+    /// The best root move of the last search from the root. Other
+    /// threads share the table, so its root entry may be theirs.
+    root_best: Option<(Vec2, Move)>,
+    nodes: u64,
+    /// The part of `nodes` already added to the shared count.
+    counted: u64,
+}
+
+impl<'a> Worker<'a> {
+    fn new(shared: &'a Shared<'a>, ordering: &'a mut MoveOrdering, lines: usize) -> Self {
+        Self {
+            shared,
+            ordering,
+            lines,
+            excluded: Vec::new(),
+            root_best: None,
+            nodes: 0,
+            counted: 0,
+        }
+    }
+
+    /// Search one ply deeper at a time, starting at `first`, reporting
+    /// each depth completed.
+    ///
+    fn deepen(
+        &mut self,
+        board: &Board,
+        turn: Player,
+        first: u32,
+        mut report: impl FnMut(&SearchInfo),
+    ) -> Option<SearchInfo> {
+        let max_depth = self
+            .shared
+            .limits
+            .depth
+            .unwrap_or(MAX_PLY as u32 - 1)
+            .max(1);
+        let mut result = None;
+
+        let root_moves = match self.shared.root_moves.len() {
+            0 => board.all_legal_moves(turn).len(),
+            n => n,
+        };
+        let wanted = self.lines.min(root_moves);
+
+        'deepen: for depth in first.min(max_depth)..=max_depth {
+            // each line after the first searches the root moves the
+            // lines before it didn't start with
+            //
+            let mut lines = Vec::with_capacity(wanted);
+            self.excluded.clear();
+
+            while lines.len() < wanted {
+                let (score, best) = self.search_root(board, turn, depth as i32);
+
+                if self.stopped() {
+                    break 'deepen;
+                }
+
+                let Some(best) = best else {
+                    break;
+                };
+                self.excluded.push(best);
+                let pv = self.principal_variation(board, turn, best, depth as usize);
+                lines.push(Line { score, pv });
+            }
+
+            lines.sort_by_key(|line| -line.score);
+            let Some(best) = lines.first().cloned() else {
+                break;
+            };
+
+            let info = SearchInfo {
+                depth,
+                score: best.score,
+                nodes: self.total_nodes(),
+                time: self.shared.start.elapsed(),
+                pv: best.pv,
+                lines,
+            };
+            report(&info);
+            result = Some(info);
+
+            // nothing to gain from searching past a forced mate
+            //
+            if mate_in(best.score).is_some_and(|mate| mate.unsigned_abs() * 2 <= depth) {
+                break;
+            }
+        }
+
+        result
+    }
+
+    /// Search the root moves not yet excluded, giving the score and the
+    /// best of them.
+    ///
+    fn search_root(
+        &mut self,
+        board: &Board,
+        turn: Player,
+        depth: i32,
+    ) -> (Centipawns, Option<(Vec2, Move)>) {
+        self.root_best = None;
+        let score = self.negamax(board, turn, depth, 0, -INFINITY, INFINITY);
+
+        (score, self.root_best.take())
+    }
+
+    fn negamax(
+        &mut self,
+        board: &Board,
+        turn: Player,
+        mut depth: i32,
+        ply: usize,
+        mut alpha: Centipawns,
+        beta: Centipawns,
+    ) -> Centipawns {
+        if self.should_stop() {
+            return 0;
+        }
+
+        if ply > 0 {
+            if let Some(outcome) = board.variant.outcome(board, turn) {
+                return outcome_score(outcome, turn, ply);
+            }
+
+            if let Some(score) = self.probe_tablebase(board, turn, ply) {
+                return score;
+            }
+        }
+
+        if ply >= MAX_PLY - 1 {
+            return eval::evaluate(board, turn);
+        }
+
+        // look a ply further when in check, so as not to stop just short
+        // of a mate or the loss of material
+        //
+        let in_check = board.variant.in_check(board, turn);
+        if in_check {
+            depth += 1;
+        }
+
+        if depth <= 0 {
+            return self.quiescence(board, turn, ply, alpha, beta);
+        }
+
+        self.nodes += 1;
+
+        let key = position_hash(board, turn);
+        let entry = self.shared.tt.probe(key);
+
+        if let Some(entry) = entry.filter(|e| ply > 0 && e.depth as i32 >= depth) {
+            let score = from_tt(entry.score, ply);
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if cutoff {
+                return score;
+            }
+        }
+
+        let mut moves = match ply == 0 && !self.shared.root_moves.is_empty() {
+            true => self.shared.root_moves.clone(),
+            false => board.all_legal_moves(turn),
+        };
+        if ply == 0 {
+            moves.retain(|m| !self.excluded.iter().any(|e| same_move(e, m)));
+        }
+        if moves.is_empty() {
+            return match in_check {
+                true => -(MATE - ply as Centipawns),
+                false => 0,
+            };
+        }
+
+        let hash_move = entry
+            .and_then(|e| e.best)
+            .and_then(|code| code.find(&moves));
+        self.ordering.sort(board, turn, ply, hash_move, &mut moves);
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best = None;
+
+        for m in moves {
+            let mut after = board.clone();
+            after.make_move(m.0, &m.1);
+
+            let score = -self.negamax(&after, turn.opponent(), depth - 1, ply + 1, -beta, -alpha);
+
+            if self.stopped() {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best = Some(m);
+
+                if ply == 0 {
+                    self.root_best = Some(m);
+                }
+            }
+
+            if score > alpha {
+                alpha = score;
+            }
+
+            if alpha >= beta {
+                if !is_tactical(board, &m.1) {
+                    self.ordering.killers.add(ply, m);
+                    self.ordering.history.add(board, turn, &m, depth as u32);
+                }
+                break;
+            }
+        }
+
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+
+        // a fail low says nothing about which move is best
+        //
+        let best = best
+            .filter(|_| bound != Bound::Upper)
+            .map(|(from, m)| MoveCode::new(from, &m));
+        self.shared
+            .tt
+            .store(key, depth as u8, to_tt(best_score, ply), bound, best);
+
+        best_score
+    }
+
+    /// Play out the captures and promotions, so the position isn't judged
+    /// in the middle of an exchange. The player to move may also stand
+    /// pat and take the evaluation, except when in check.
+    ///
+    fn quiescence(
+        &mut self,
+        board: &Board,
+        turn: Player,
+        ply: usize,
+        mut alpha: Centipawns,
+        beta: Centipawns,
+    ) -> Centipawns {
+        self.nodes += 1;
+
+        if self.should_stop() {
+            return 0;
+        }
+
+        if let Some(outcome) = board.variant.outcome(board, turn) {
+            return outcome_score(outcome, turn, ply);
+        }
+
+        let in_check = board.variant.in_check(board, turn);
+
+        if ply >= MAX_PLY - 1 {
+            return eval::evaluate(board, turn);
+        }
+
+        if !in_check {
+            let stand_pat = eval::evaluate(board, turn);
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+        }
+
+        let mut moves: Vec<_> = board
+            .all_legal_moves(turn)
+            .into_iter()
+            .filter(|(_, m)| in_check || is_tactical(board, m))
+            .collect();
+        self.ordering.sort(board, turn, ply, None, &mut moves);
+
+        for m in moves {
+            let mut after = board.clone();
+            after.make_move(m.0, &m.1);
+
+            let score = -self.quiescence(&after, turn.opponent(), ply + 1, -beta, -alpha);
+
+            if self.stopped() {
+                return 0;
+            }
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+
+        alpha
+    }
+
+    /// The result the tablebases give a position, as a score. They are
+    /// only looked up just after a capture or pawn move, when the material
+    /// has changed, which is also when they are exact.
+    ///
+    fn probe_tablebase(&self, board: &Board, turn: Player, ply: usize) -> Option<Centipawns> {
+        if board.halfmove_clock != 0 {
+            return None;
+        }
+
+        let wdl = self.shared.tablebase?.probe_wdl(board, turn)?;
+        let ply = ply as Centipawns;
+
+        Some(match wdl {
+            Wdl::Win => TABLEBASE_WIN - ply,
+            Wdl::CursedWin => TABLEBASE_WIN - MAX_PLY as Centipawns - ply,
+            Wdl::Draw => 0,
+            Wdl::BlessedLoss => -(TABLEBASE_WIN - MAX_PLY as Centipawns - ply),
+            Wdl::Loss => -(TABLEBASE_WIN - ply),
+        })
+    }
+
+    /// The line of best moves starting with `first`, read out of the
+    /// transposition table after it.
+    ///
+    fn principal_variation(
+        &self,
+        board: &Board,
+        turn: Player,
+        first: (Vec2, Move),
+        depth: usize,
+    ) -> Vec<(Vec2, Move)> {
+        let mut pv = vec![first];
+        let mut board = board.clone();
+        board.make_move(first.0, &first.1);
+        let mut turn = turn.opponent();
+
+        while pv.len() < depth.max(1) {
+            // a hash collision could suggest a move from another position,
+            // which then isn't among the legal ones
+            //
+            let Some(best) = self
+                .shared
+                .tt
+                .probe(position_hash(&board, turn))
+                .and_then(|e| e.best)
+                .and_then(|code| code.find(&board.all_legal_moves(turn)))
+            else {
+                break;
+            };
+
+            board.make_move(best.0, &best.1);
+            pv.push(best);
+            turn = turn.opponent();
+        }
+
+        pv
+    }
+
+    /// The nodes searched so far by all the threads.
+    ///
+    fn total_nodes(&self) -> u64 {
+        self.shared.nodes.load(Ordering::Relaxed) + self.nodes - self.counted
+    }
+
+    fn stopped(&self) -> bool {
+        self.shared.stop.load(Ordering::Relaxed)
+    }
+
+    /// Whether the search is out of time or nodes, or has been stopped.
+    ///
+    fn should_stop(&mut self) -> bool {
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            self.shared
+                .nodes
+                .fetch_add(self.nodes - self.counted, Ordering::Relaxed);
+            self.counted = self.nodes;
+
+            let limits = &self.shared.limits;
+            let out_of_time = limits
+                .time
+                .is_some_and(|time| self.shared.start.elapsed() >= time);
+            let out_of_nodes = limits
+                .nodes
+                .is_some_and(|nodes| self.total_nodes() >= nodes);
+
+            if (out_of_time || out_of_nodes) && self.shared.searched.load(Ordering::Relaxed) {
+                self.shared.stop.store(true, Ordering::Relaxed);
+            }
+        }
+
+        self.stopped()
+    }
+}
+
+fn outcome_score(outcome: Outcome, turn: Player, ply: usize) -> Centipawns {
+    match outcome {
+        Outcome::Win(player, _) if player == turn => MATE - ply as Centipawns,
+        Outcome::Win(_, _) => -(MATE - ply as Centipawns),
+        Outcome::Draw(_) => 0,
+    }
+}
+
+/// Mate scores count plies from the root, but are stored counting from
+/// the position itself, so they hold wherever it is reached.
+///
+fn to_tt(score: Centipawns, ply: usize) -> Centipawns {
+    match score {
+        s if s > MATE_BOUND => s + ply as Centipawns,
+        s if s < -MATE_BOUND => s - ply as Centipawns,
+        s => s,
+    }
+}
+
+fn from_tt(score: Centipawns, ply: usize) -> Centipawns {
+    match score {
+        s if s > MATE_BOUND => s - ply as Centipawns,
+        s if s < -MATE_BOUND => s + ply as Centipawns,
+        s => s,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::fen;
+
+    #[test]
+    fn finishes_the_first_depth_without_time() {
+        let fen =
+            fen::parse("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+        let mut search = Search::new(1);
+        search.set_threads(2);
+        search.set_lines(3);
+
+        let limits = Limits {
+            time: Some(Duration::ZERO),
+            nodes: Some(1),
+            ..Default::default()
+        };
+        let info = search.run(&fen.board, fen.turn, limits, |_| {}).unwrap();
+
+        assert!(info.depth >= 1);
+        assert_eq!(info.lines.len(), 3);
+        assert!(info.best().is_some());
+
+        // the helper threads share the table, but each line must still
+        // start with a move of its own
+        //
+        let firsts: Vec<_> = info.lines.iter().map(|line| line.pv[0]).collect();
+        for (i, a) in firsts.iter().enumerate() {
+            assert!(!firsts[i + 1..].iter().any(|b| same_move(a, b)));
+        }
+    }
+
+    #[test]
+    fn mate_scores_count_from_the_stored_position() {
+        // mate in 10 plies from the root, found 4 plies in, is mate in 6
+        // from that position, and so mate in 13 from the root when it is
+        // reached 7 plies in
+        //
+        assert_eq!(to_tt(MATE - 10, 4), MATE - 6);
+        assert_eq!(from_tt(MATE - 6, 7), MATE - 13);
+        assert_eq!(to_tt(-(MATE - 10), 4), -(MATE - 6));
+        assert_eq!(from_tt(-(MATE - 6), 7), -(MATE - 13));
+
+        for score in [0, 250, -MATE_BOUND, TABLEBASE_WIN] {
+            assert_eq!(to_tt(score, 9), score);
+            assert_eq!(from_tt(score, 9), score);
+        }
+    }
+
+    #[test]
+    fn stopped_from_outside() {
+        let fen = fen::parse("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let mut search = Search::new(1);
+
+        search.stop_flag().store(true, Ordering::Relaxed);
+        assert!(search
+            .run(&fen.board, fen.turn, Limits::default(), |_| {})
+            .is_none());
+    }
+}
@@ -0,0 +1,601 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use crate::core::{
+    hash::position_hash, notation, Board, Chess, Move, MoveResult, Outcome, Player, Vec2,
+};
+
+/// What a chess problem asks of the side to move.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Goal {
+    /// Mate the opponent, whatever they do.
+    Mate,
+    /// Force the opponent, who resists, to give mate.
+    Selfmate,
+    /// Help the opponent to mate, the two sides working together.
+    Helpmate,
+}
+
+/// A problem's goal and the number of moves it is to be reached in,
+/// written as problemists do: `#2`, `s#3` or `h#2`.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Stipulation {
+    pub goal: Goal,
+    pub moves: u32,
+}
+
+impl FromStr for Stipulation {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        let (goal, moves) = if let Some(moves) = text.strip_prefix("s#") {
+            (Goal::Selfmate, moves)
+        } else if let Some(moves) = text.strip_prefix("h#") {
+            (Goal::Helpmate, moves)
+        } else if let Some(moves) = text.strip_prefix('#') {
+            (Goal::Mate, moves)
+        } else {
+            return Err(format!(
+                "Expected a stipulation such as #2, s#3 or h#2, not '{}'",
+                text
+            ));
+        };
+
+        match moves.parse::<u32>() {
+            Ok(moves) if moves > 0 => Ok(Stipulation { goal, moves }),
+            _ => Err(format!("Expected a number of moves in '{}'", text)),
+        }
+    }
+}
+
+impl fmt::Display for Stipulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let goal = match self.goal {
+            Goal::Mate => "#",
+            Goal::Selfmate => "s#",
+            Goal::Helpmate => "h#",
+        };
+        write!(f, "{}{}", goal, self.moves)
+    }
+}
+
+/// A move of a solution, and the moves that may follow it.
+///
+#[derive(Clone)]
+pub struct Play {
+    pub player: Player,
+    pub from: Vec2,
+    pub m: Move,
+    pub san: String,
+    pub replies: Vec<Play>,
+}
+
+/// Somewhere the solving side could go on in more than one way.
+///
+#[derive(Clone, Debug)]
+pub struct Dual {
+    /// The moves leading there, in SAN.
+    pub line: Vec<String>,
+    pub moves: Vec<String>,
+}
+
+/// Everything found about a problem: each solution, with the defences
+/// to it and the answers to those, and the duals in them.
+///
+#[derive(Clone)]
+pub struct Report {
+    pub stipulation: Stipulation,
+    /// The side to move in the problem.
+    pub turn: Player,
+    /// The first moves of the solutions. More than one means the
+    /// problem is cooked.
+    pub solutions: Vec<Play>,
+    pub duals: Vec<Dual>,
+}
+
+impl Report {
+    pub fn is_cooked(&self) -> bool {
+        self.solutions.len() > 1
+    }
+
+    /// Whether the problem has the one solution and no duals.
+    ///
+    pub fn is_sound(&self) -> bool {
+        self.solutions.len() == 1 && self.duals.is_empty()
+    }
+
+    /// A line about the solutions, for the status line.
+    ///
+    pub fn summary(&self) -> String {
+        let keys: Vec<&str> = self.solutions.iter().map(|p| p.san.as_str()).collect();
+
+        let verdict = match (self.solutions.len(), self.duals.len()) {
+            (0, _) => return format!("{}: no solution", self.stipulation),
+            (1, 0) => "sound".to_string(),
+            (1, n) => format!("{} dual{}", n, plural(n)),
+            (n, 0) => format!("cooked, {} solutions", n),
+            (n, d) => format!("cooked, {} solutions, {} dual{}", n, d, plural(d)),
+        };
+
+        format!("{}: {} ({})", self.stipulation, keys.join(", "), verdict)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.summary())?;
+
+        for play in &self.solutions {
+            writeln!(f)?;
+            write_play(f, play, self.turn, 0, true)?;
+        }
+
+        if !self.duals.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Duals:")?;
+            for dual in &self.duals {
+                writeln!(f, "  {} {}", numbered(&dual.line), dual.moves.join(" / "))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+/// Write a move and everything after it, a line each, indented by how
+/// far into the solution it is. The key is marked `!`.
+///
+fn write_play(
+    f: &mut fmt::Formatter<'_>,
+    play: &Play,
+    first: Player,
+    ply: usize,
+    key: bool,
+) -> fmt::Result {
+    let number = ply / 2 + 1;
+    let dots = match play.player == first {
+        true => ".",
+        false => "...",
+    };
+
+    writeln!(
+        f,
+        "{:indent$}{}{} {}{}",
+        "",
+        number,
+        dots,
+        play.san,
+        if key { "!" } else { "" },
+        indent = ply * 2
+    )?;
+
+    for reply in &play.replies {
+        write_play(f, reply, first, ply + 1, false)?;
+    }
+
+    Ok(())
+}
+
+/// Moves in SAN, numbered from the first, e.g. `1. Qg4 Kf6 2.`.
+///
+fn numbered(line: &[String]) -> String {
+    let mut words = Vec::new();
+
+    for (ply, san) in line.iter().enumerate() {
+        if ply % 2 == 0 {
+            words.push(format!("{}.", ply / 2 + 1));
+        }
+        words.push(san.clone());
+    }
+
+    // the number of the move with the choice
+    //
+    match line.len() % 2 {
+        0 => words.push(format!("{}.", line.len() / 2 + 1)),
+        _ => words.push(format!("{}...", line.len() / 2 + 1)),
+    }
+
+    words.join(" ")
+}
+
+/// Solves chess problems by trying every move, remembering which
+/// positions were found to work in how many moves.
+///
+pub struct Solver {
+    goal: Goal,
+    /// The side solving the problem: the one mating, or being mated in
+    /// a selfmate. In a helpmate it is the side that mates.
+    attacker: Player,
+    known: HashMap<(u64, u32), bool>,
+}
+
+impl Solver {
+    /// Find every solution of the problem on `board` with `turn` to
+    /// move, and the duals in them.
+    ///
+    pub fn solve(board: &Board, turn: Player, stipulation: Stipulation) -> Report {
+        let attacker = match stipulation.goal {
+            Goal::Mate | Goal::Selfmate => turn,
+            Goal::Helpmate => turn.opponent(),
+        };
+
+        let mut solver = Solver {
+            goal: stipulation.goal,
+            attacker,
+            known: HashMap::new(),
+        };
+
+        let solutions = match stipulation.goal {
+            Goal::Mate | Goal::Selfmate => solver.keys(board, stipulation.moves),
+            Goal::Helpmate => solver.helps(board, stipulation.moves),
+        };
+
+        let mut duals = Vec::new();
+        for play in &solutions {
+            solver.duals(play, &mut Vec::new(), &mut duals);
+        }
+
+        Report {
+            stipulation,
+            turn,
+            solutions,
+            duals,
+        }
+    }
+
+    /// The attacker's first moves that reach the goal in `n` moves or
+    /// fewer, each with the play after it.
+    ///
+    fn keys(&mut self, board: &Board, n: u32) -> Vec<Play> {
+        board
+            .all_legal_moves(self.attacker)
+            .into_iter()
+            .filter_map(|(from, m)| {
+                let next = after(board, from, &m);
+                self.succeeds(&next, n)
+                    .then(|| play(board, from, m, self.defences(&next, n)))
+            })
+            .collect()
+    }
+
+    /// The attacker's moves that reach the goal soonest, in no more than
+    /// `n` moves, each with the play after it.
+    ///
+    fn continuations(&mut self, board: &Board, n: u32) -> Vec<Play> {
+        let moves = board.all_legal_moves(self.attacker);
+
+        for k in 1..=n {
+            let working: Vec<_> = moves
+                .iter()
+                .filter(|(from, m)| self.succeeds(&after(board, *from, m), k))
+                .copied()
+                .collect();
+
+            if !working.is_empty() {
+                return working
+                    .into_iter()
+                    .map(|(from, m)| {
+                        let next = after(board, from, &m);
+                        let replies = self.defences(&next, k);
+                        play(board, from, m, replies)
+                    })
+                    .collect();
+            }
+        }
+
+        Vec::new()
+    }
+
+    /// Every defence to the attacker's move that led to `board`, with
+    /// the answers to it, or none if the goal has been reached.
+    ///
+    fn defences(&mut self, board: &Board, n: u32) -> Vec<Play> {
+        let defender = self.attacker.opponent();
+        if self.goal == Goal::Mate && lost(board, defender) {
+            return Vec::new();
+        }
+
+        board
+            .all_legal_moves(defender)
+            .into_iter()
+            .map(|(from, m)| {
+                let next = after(board, from, &m);
+                let answers = match self.goal == Goal::Selfmate && lost(&next, self.attacker) {
+                    true => Vec::new(),
+                    false => self.continuations(&next, n - 1),
+                };
+                play(board, from, m, answers)
+            })
+            .collect()
+    }
+
+    /// Whether the attacker, having just moved to `board`, reaches the
+    /// goal within `n` of their moves however the defender plays.
+    ///
+    fn succeeds(&mut self, board: &Board, n: u32) -> bool {
+        let defender = self.attacker.opponent();
+
+        match self.goal {
+            Goal::Mate if lost(board, defender) => return true,
+            _ if over(board, defender) => return false,
+            Goal::Mate if n == 1 => return false,
+            _ => {}
+        }
+
+        let key = (position_hash(board, defender), n);
+        if let Some(known) = self.known.get(&key) {
+            return *known;
+        }
+
+        let result = board.all_legal_moves(defender).iter().all(|(from, m)| {
+            let next = after(board, *from, m);
+            match self.goal {
+                Goal::Selfmate if lost(&next, self.attacker) => true,
+                _ if n == 1 => false,
+                _ => self.forces(&next, n - 1),
+            }
+        });
+
+        self.known.insert(key, result);
+        result
+    }
+
+    /// Whether the attacker, to move on `board`, has a move that reaches
+    /// the goal within `n` moves.
+    ///
+    fn forces(&mut self, board: &Board, n: u32) -> bool {
+        board
+            .all_legal_moves(self.attacker)
+            .iter()
+            .any(|(from, m)| self.succeeds(&after(board, *from, m), n))
+    }
+
+    /// The helping side's first moves that lead to mate in `n` moves with
+    /// the attacker's help, each with the play after it.
+    ///
+    fn helps(&mut self, board: &Board, n: u32) -> Vec<Play> {
+        let helper = self.attacker.opponent();
+
+        board
+            .all_legal_moves(helper)
+            .into_iter()
+            .filter_map(|(from, m)| {
+                let next = after(board, from, &m);
+                if over(&next, self.attacker) {
+                    return None;
+                }
+
+                let mates = self.helpmates(&next, n);
+                (!mates.is_empty()).then(|| play(board, from, m, mates))
+            })
+            .collect()
+    }
+
+    /// The attacker's moves on `board`, after the helper has moved, that
+    /// lead to the helper being mated on the `n`th of them.
+    ///
+    fn helpmates(&mut self, board: &Board, n: u32) -> Vec<Play> {
+        let helper = self.attacker.opponent();
+
+        board
+            .all_legal_moves(self.attacker)
+            .into_iter()
+            .filter_map(|(from, m)| {
+                let next = after(board, from, &m);
+                let replies = match n {
+                    1 if lost(&next, helper) => Vec::new(),
+                    1 => return None,
+                    _ if over(&next, helper) || !self.can_help(&next, n - 1) => return None,
+                    _ => self.helps(&next, n - 1),
+                };
+                Some(play(board, from, m, replies))
+            })
+            .collect()
+    }
+
+    /// Whether the helper, to move on `board`, can be mated in `n` moves
+    /// with both sides working to it.
+    ///
+    fn can_help(&mut self, board: &Board, n: u32) -> bool {
+        let helper = self.attacker.opponent();
+
+        let key = (position_hash(board, helper), n);
+        if let Some(known) = self.known.get(&key) {
+            return *known;
+        }
+
+        let result = board.all_legal_moves(helper).iter().any(|(from, m)| {
+            let next = after(board, *from, m);
+            !over(&next, self.attacker)
+                && next.all_legal_moves(self.attacker).iter().any(|(from, m)| {
+                    let last = after(&next, *from, m);
+                    match n {
+                        1 => lost(&last, helper),
+                        _ => !over(&last, helper) && self.can_help(&last, n - 1),
+                    }
+                })
+        });
+
+        self.known.insert(key, result);
+        result
+    }
+
+    /// Collect the places after `play` where the solving side had a
+    /// choice of moves: the attacker's in a mate or selfmate, either
+    /// side's in a helpmate.
+    ///
+    fn duals(&self, play: &Play, line: &mut Vec<String>, duals: &mut Vec<Dual>) {
+        line.push(play.san.clone());
+
+        let choice = play
+            .replies
+            .first()
+            .is_some_and(|reply| self.goal == Goal::Helpmate || reply.player == self.attacker);
+
+        if choice && play.replies.len() > 1 {
+            duals.push(Dual {
+                line: line.clone(),
+                moves: play.replies.iter().map(|r| r.san.clone()).collect(),
+            });
+        }
+
+        for reply in &play.replies {
+            self.duals(reply, line, duals);
+        }
+
+        line.pop();
+    }
+}
+
+/// Solving the position set up in the board editor as a problem.
+///
+impl Chess {
+    /// Solve the edited position, highlighting the key if there is just
+    /// the one, and write the full solution to `path` if given.
+    ///
+    pub fn solve_problem(&mut self, stipulation: &str, path: Option<&str>) -> Result<(), String> {
+        let stipulation: Stipulation = stipulation.parse()?;
+        let board = self.edited_position()?;
+
+        let report = Solver::solve(&board, self.turn, stipulation);
+
+        self.view.hint = match report.solutions.as_slice() {
+            [key] => Some((position_hash(&self.board, self.turn), key.from, key.m.pos)),
+            _ => None,
+        };
+
+        if let Some(path) = path {
+            std::fs::write(path, report.to_string())
+                .map_err(|err| format!("Unable to write {}: {}", path, err))?;
+        }
+
+        self.view.message = Some(report.summary());
+
+        Ok(())
+    }
+}
+
+fn play(board: &Board, from: Vec2, m: Move, replies: Vec<Play>) -> Play {
+    let player = match m.result {
+        MoveResult::Drop(player, _) => player,
+        _ => board.get(from).expect("Piece expected at position").player,
+    };
+
+    Play {
+        player,
+        from,
+        m,
+        san: notation::san(board, from, &m),
+        replies,
+    }
+}
+
+fn after(board: &Board, from: Vec2, m: &Move) -> Board {
+    let mut board = board.clone();
+    board.make_move(from, m);
+    board
+}
+
+/// Whether the game is over with `turn` to move.
+///
+fn over(board: &Board, turn: Player) -> bool {
+    board.variant.outcome(board, turn).is_some()
+}
+
+/// Whether `player`, to move, has lost.
+///
+fn lost(board: &Board, player: Player) -> bool {
+    matches!(board.variant.outcome(board, player), Some(Outcome::Win(winner, _)) if winner != player)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::fen;
+
+    fn solve(text: &str, stipulation: &str) -> Report {
+        let fen = fen::parse(text).unwrap();
+        Solver::solve(&fen.board, fen.turn, stipulation.parse().unwrap())
+    }
+
+    fn keys(report: &Report) -> Vec<&str> {
+        report.solutions.iter().map(|p| p.san.as_str()).collect()
+    }
+
+    #[test]
+    fn sound_mate_in_two() {
+        let report = solve("k7/8/2K5/8/8/8/8/1R6 w - - 0 1", "#2");
+
+        assert!(report.is_sound());
+        assert_eq!(keys(&report), ["Kc7"]);
+
+        let defences = &report.solutions[0].replies;
+        assert_eq!(defences.len(), 1);
+        assert_eq!(defences[0].san, "Ka7");
+        assert_eq!(defences[0].replies[0].san, "Ra1#");
+    }
+
+    #[test]
+    fn cooked_mate_in_two() {
+        let report = solve("7k/8/5K2/8/8/8/8/R7 w - - 0 1", "#2");
+
+        assert!(report.is_cooked());
+        assert_eq!(keys(&report), ["Kf7", "Kg6"]);
+    }
+
+    #[test]
+    fn selfmate_in_one() {
+        let report = solve("8/8/8/8/5r2/5R2/Q5PP/2k4K w - - 0 1", "s#1");
+
+        assert!(report.is_sound());
+        assert_eq!(keys(&report), ["Rf1+"]);
+        assert_eq!(report.solutions[0].replies[0].san, "Rxf1#");
+    }
+
+    #[test]
+    fn helpmates() {
+        let report = solve("k7/8/1K6/8/8/8/8/7R b - - 0 1", "h#1");
+        assert!(report.is_sound());
+        assert_eq!(keys(&report), ["Kb8"]);
+
+        // the black king walks into either rook's mate
+        //
+        let report = solve("4k3/8/8/8/8/8/8/R3K2R b KQ - 0 1", "h#2");
+        assert_eq!(keys(&report), ["Ke7", "Kd8", "Kf8", "Kd7", "Kf7"]);
+        assert_eq!(report.duals.len(), 13);
+
+        let dual = report.duals.iter().find(|d| d.line == ["Kf8"]).unwrap();
+        assert_eq!(dual.moves, ["Ra7", "Rh7"]);
+    }
+
+    #[test]
+    fn stipulations() {
+        let parsed = "s#3".parse::<Stipulation>().unwrap();
+        assert_eq!(parsed.goal, Goal::Selfmate);
+        assert_eq!(parsed.moves, 3);
+        assert_eq!(" h#2 ".parse::<Stipulation>().unwrap().to_string(), "h#2");
+
+        assert_eq!(
+            "mate in 2".parse::<Stipulation>(),
+            Err("Expected a stipulation such as #2, s#3 or h#2, not 'mate in 2'".into())
+        );
+        assert_eq!(
+            "#0".parse::<Stipulation>(),
+            Err("Expected a number of moves in '#0'".into())
+        );
+        assert_eq!(
+            "h#two".parse::<Stipulation>(),
+            Err("Expected a number of moves in 'h#two'".into())
+        );
+    }
+}
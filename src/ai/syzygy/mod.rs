@@ -0,0 +1,755 @@
+use std::{
+    collections::HashMap,
+    fmt, fs, io,
+    ops::Neg,
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+};
+
+use crate::core::{hash::position_hash, Board, Chess, Move, PieceType, Player, Vec2};
+
+mod table;
+
+use table::{Kind, Position, Table, KING, PAWN};
+
+/// The result of a position with best play, for the player to move.
+/// A cursed win takes more than fifty moves without a capture or pawn
+/// move, and a blessed loss is the other side of one. This game has no
+/// fifty-move rule, so those are still a win and a loss, only longer.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Wdl {
+    Loss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    Win,
+}
+
+impl Wdl {
+    fn from_value(value: i32) -> Option<Wdl> {
+        match value {
+            -2 => Some(Wdl::Loss),
+            -1 => Some(Wdl::BlessedLoss),
+            0 => Some(Wdl::Draw),
+            1 => Some(Wdl::CursedWin),
+            2 => Some(Wdl::Win),
+            _ => None,
+        }
+    }
+
+    /// From -2 for a loss to 2 for a win, as stored in the tables.
+    ///
+    pub fn value(self) -> i32 {
+        self as i32 - 2
+    }
+
+    /// The DTZ of a position whose best move resets the fifty-move
+    /// counter, with this result.
+    ///
+    fn before_zeroing(self) -> i32 {
+        match self {
+            Wdl::Loss => -1,
+            Wdl::BlessedLoss => -101,
+            Wdl::Draw => 0,
+            Wdl::CursedWin => 101,
+            Wdl::Win => 1,
+        }
+    }
+}
+
+impl Neg for Wdl {
+    type Output = Wdl;
+
+    fn neg(self) -> Wdl {
+        Wdl::from_value(-self.value()).unwrap()
+    }
+}
+
+impl fmt::Display for Wdl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Wdl::Loss => "loss",
+            Wdl::BlessedLoss => "blessed loss",
+            Wdl::Draw => "draw",
+            Wdl::CursedWin => "cursed win",
+            Wdl::Win => "win",
+        })
+    }
+}
+
+/// A move from a position in the tables, with its result for the
+/// player making it and the plies from before it to the next capture
+/// or pawn move, negative when losing.
+///
+#[derive(Copy, Clone)]
+pub struct TableMove {
+    pub from: Vec2,
+    pub m: Move,
+    pub wdl: Wdl,
+    pub dtz: i32,
+}
+
+/// What the tables say about a position and each of its moves, best
+/// first.
+///
+#[derive(Clone)]
+pub struct Verdict {
+    pub wdl: Wdl,
+    pub dtz: i32,
+    pub moves: Vec<TableMove>,
+}
+
+/// The tables found for one material balance. They are read the first
+/// time they are needed.
+///
+#[derive(Default)]
+struct Entry {
+    wdl_path: Option<PathBuf>,
+    dtz_path: Option<PathBuf>,
+    wdl: OnceLock<Option<Table>>,
+    dtz: OnceLock<Option<Table>>,
+}
+
+/// Syzygy endgame tablebases in a directory, which give the result of
+/// every standard chess position with few enough pieces and no castling
+/// rights, and the distance to the next capture or pawn move on the way
+/// to it.
+///
+pub struct Tablebase {
+    /// By material, as in the file names, e.g. `KRPvKR`.
+    tables: HashMap<String, Entry>,
+    max_pieces: usize,
+}
+
+impl Tablebase {
+    /// Find the `.rtbw` and `.rtbz` files in `dir`. They aren't read
+    /// until a position needs them.
+    ///
+    pub fn open(dir: impl AsRef<Path>) -> io::Result<Tablebase> {
+        let mut tables: HashMap<String, Entry> = HashMap::new();
+
+        for file in fs::read_dir(dir.as_ref())? {
+            let path = file?.path();
+
+            let (Some(name), Some(ext)) = (
+                path.file_stem().and_then(|s| s.to_str()),
+                path.extension().and_then(|s| s.to_str()),
+            ) else {
+                continue;
+            };
+
+            let Some((white, black)) = name.split_once('v') else {
+                continue;
+            };
+            if normalise(white, black) != name {
+                continue;
+            }
+
+            let entry = tables.entry(name.to_string()).or_default();
+            match ext {
+                "rtbw" => entry.wdl_path = Some(path),
+                "rtbz" => entry.dtz_path = Some(path),
+                _ => {}
+            }
+        }
+
+        tables.retain(|_, entry| entry.wdl_path.is_some());
+
+        if tables.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no Syzygy tables found",
+            ));
+        }
+
+        let max_pieces = tables.keys().map(|name| name.len() - 1).max().unwrap_or(0);
+
+        Ok(Tablebase { tables, max_pieces })
+    }
+
+    /// The number of tables, counting a WDL and DTZ pair as one.
+    ///
+    pub fn len(&self) -> usize {
+        self.tables.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    /// The most pieces, kings included, of any table found.
+    ///
+    pub fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    /// Whether positions like this may be in the tables: standard chess
+    /// on an 8x8 board, with no castling rights and few enough pieces.
+    ///
+    pub fn covers(&self, board: &Board) -> bool {
+        if board.variant.name() != "standard"
+            || board.width() != 8
+            || board.height() != 8
+            || board.variant.has_pockets()
+        {
+            return false;
+        }
+
+        let mut count = 0;
+        for player in [Player::White, Player::Black] {
+            for (_, piece) in board.pieces(player) {
+                if matches!(piece.ty, PieceType::Fairy(_)) {
+                    return false;
+                }
+                count += 1;
+            }
+        }
+
+        count <= self.max_pieces && !has_castling_rights(board)
+    }
+
+    /// The result of the position with `turn` to move, if it is in the
+    /// tables.
+    ///
+    pub fn probe_wdl(&self, board: &Board, turn: Player) -> Option<Wdl> {
+        if !self.covers(board) {
+            return None;
+        }
+
+        self.search(board, turn, false).map(|(wdl, _)| wdl)
+    }
+
+    /// The plies to the next capture or pawn move with best play, for a
+    /// position in the tables: positive when the player to move wins,
+    /// negative when they lose and zero for a draw. More than 100 is a
+    /// cursed win, or less than -100 a blessed loss.
+    ///
+    pub fn probe_dtz(&self, board: &Board, turn: Player) -> Option<i32> {
+        if !self.covers(board) {
+            return None;
+        }
+
+        self.dtz(board, turn)
+    }
+
+    /// Every legal move from a position in the tables, with its result,
+    /// best first: the quickest of the wins to the next zeroing move,
+    /// then the draws, then the losses that hold out longest.
+    ///
+    pub fn root_moves(&self, board: &Board, turn: Player) -> Option<Vec<TableMove>> {
+        if !self.covers(board) {
+            return None;
+        }
+
+        let opponent = turn.opponent();
+        let mut moves = Vec::new();
+
+        for (from, m) in board.all_legal_moves(turn) {
+            let mut after = board.clone();
+            after.make_move(from, &m);
+
+            let wdl = -self.search(&after, opponent, false)?.0;
+
+            let mut dtz = if after.halfmove_clock == 0 {
+                wdl.before_zeroing()
+            } else {
+                let dtz = -self.dtz(&after, opponent)?;
+                dtz + dtz.signum()
+            };
+
+            // a mate is as quick as a win gets
+            //
+            if dtz == 2 && is_mate(&after, opponent) {
+                dtz = 1;
+            }
+
+            moves.push(TableMove { from, m, wdl, dtz });
+        }
+
+        moves.sort_by_key(|m| std::cmp::Reverse((m.wdl, -m.dtz)));
+
+        Some(moves)
+    }
+
+    /// The result and DTZ of a position in the tables, and of each of
+    /// its moves.
+    ///
+    pub fn verdict(&self, board: &Board, turn: Player) -> Option<Verdict> {
+        let moves = self.root_moves(board, turn)?;
+
+        Some(Verdict {
+            wdl: self.search(board, turn, false)?.0,
+            dtz: self.dtz(board, turn)?,
+            moves,
+        })
+    }
+
+    /// The result with best play, searching the captures first since the
+    /// tables may hold any value when a capture is best, and positions
+    /// with an en passant capture aren't in them at all. With `zeroing`
+    /// pawn moves are searched too, as DTZ tables need. Also returns
+    /// whether the best move was one of those searched.
+    ///
+    fn search(&self, board: &Board, turn: Player, zeroing: bool) -> Option<(Wdl, bool)> {
+        let moves = board.all_legal_moves(turn);
+        if moves.is_empty() {
+            let wdl = match board.variant.in_check(board, turn) {
+                true => Wdl::Loss,
+                false => Wdl::Draw,
+            };
+            return Some((wdl, false));
+        }
+
+        let mut best = Wdl::Loss;
+        let mut searched = 0;
+
+        for (from, m) in &moves {
+            let pawn = board.get(*from).is_some_and(|p| p.ty == PieceType::Pawn);
+            if !(board.is_capture(m) || zeroing && pawn) {
+                continue;
+            }
+            searched += 1;
+
+            let mut after = board.clone();
+            after.make_move(*from, m);
+
+            let value = -self.search(&after, turn.opponent(), false)?.0;
+            if value > best {
+                best = value;
+                if value == Wdl::Win {
+                    return Some((value, true));
+                }
+            }
+        }
+
+        let all = searched == moves.len();
+        let value = match all {
+            true => best,
+            false => self.probe_table(board, turn)?,
+        };
+
+        if best >= value {
+            return Some((best, best > Wdl::Draw || all));
+        }
+
+        Some((value, false))
+    }
+
+    fn dtz(&self, board: &Board, turn: Player) -> Option<i32> {
+        let (wdl, zeroing) = self.search(board, turn, true)?;
+
+        if wdl == Wdl::Draw {
+            return Some(0);
+        }
+        if zeroing {
+            return Some(wdl.before_zeroing());
+        }
+
+        let sign = wdl.value().signum();
+        let cursed = matches!(wdl, Wdl::CursedWin | Wdl::BlessedLoss);
+
+        if let Some(dtz) = self.probe_dtz_table(board, turn, wdl)? {
+            return Some((dtz + 100 * cursed as i32) * sign);
+        }
+
+        // the table is for the other side to move, so look a ply ahead
+        // for the move that keeps the result with the least DTZ
+        //
+        let opponent = turn.opponent();
+        let mut best = 0xffff;
+
+        for (from, m) in board.all_legal_moves(turn) {
+            let zeroing =
+                board.is_capture(&m) || board.get(from).is_some_and(|p| p.ty == PieceType::Pawn);
+
+            let mut after = board.clone();
+            after.make_move(from, &m);
+
+            let mut dtz = match zeroing {
+                true => -self.search(&after, opponent, false)?.0.before_zeroing(),
+                false => -self.dtz(&after, opponent)?,
+            };
+
+            if dtz == 1 && is_mate(&after, opponent) {
+                best = 1;
+            }
+
+            if !zeroing {
+                dtz += dtz.signum();
+            }
+
+            if dtz < best && dtz.signum() == sign {
+                best = dtz;
+            }
+        }
+
+        Some(if best == 0xffff { -1 } else { best })
+    }
+
+    /// Look the position up in its WDL table.
+    ///
+    fn probe_table(&self, board: &Board, turn: Player) -> Option<Wdl> {
+        let (name, flip, position) = material(board, turn)?;
+
+        // bare kings are a draw, and have no table
+        //
+        if name == "KvK" {
+            return Some(Wdl::Draw);
+        }
+
+        let table = self.load(&name, Kind::Wdl)?;
+        Wdl::from_value(table.wdl(&position, flip)?)
+    }
+
+    /// Look the position up in its DTZ table, which gives `None` when it
+    /// is for the other side to move.
+    ///
+    fn probe_dtz_table(&self, board: &Board, turn: Player, wdl: Wdl) -> Option<Option<i32>> {
+        let (name, flip, position) = material(board, turn)?;
+        let table = self.load(&name, Kind::Dtz)?;
+
+        table.dtz(&position, flip, wdl.value())
+    }
+
+    fn load(&self, name: &str, kind: Kind) -> Option<&Table> {
+        let entry = self.tables.get(name)?;
+
+        let (cell, path) = match kind {
+            Kind::Wdl => (&entry.wdl, &entry.wdl_path),
+            Kind::Dtz => (&entry.dtz, &entry.dtz_path),
+        };
+
+        cell.get_or_init(|| {
+            let data = fs::read(path.as_ref()?).ok()?;
+            Table::parse(data, kind, name).ok()
+        })
+        .as_ref()
+    }
+}
+
+/// The name of the tables for the position's material, whether its
+/// colours are the other way round to theirs, and the position as the
+/// tables see it.
+///
+fn material(board: &Board, turn: Player) -> Option<(String, bool, Position)> {
+    let mut position = Position {
+        pieces: Default::default(),
+        black_to_move: turn == Player::Black,
+    };
+    let mut sides = [String::new(), String::new()];
+
+    for player in [Player::White, Player::Black] {
+        let mut letters = Vec::new();
+
+        for (pos, piece) in board.pieces(player) {
+            let code = match piece.ty {
+                PieceType::Pawn => PAWN,
+                PieceType::Knight => 2,
+                PieceType::Bishop => 3,
+                PieceType::Rook => 4,
+                PieceType::Queen => 5,
+                PieceType::King => KING,
+                PieceType::Fairy(_) => return None,
+            };
+
+            let square = ((7 - pos.y) * 8 + pos.x) as u8;
+            position.pieces[player as usize][code as usize].push(square);
+            letters.push(piece.ty.letter().to_ascii_uppercase());
+        }
+
+        letters.sort_by_key(|c| ORDER.find(*c));
+        sides[player as usize] = letters.into_iter().collect();
+    }
+
+    for squares in position.pieces.iter_mut().flatten() {
+        squares.sort_unstable();
+    }
+
+    let [white, black] = sides;
+    let name = normalise(&white, &black);
+
+    // a table whose sides are the same stores the position with white
+    // to move, so black to move is looked up with the colours swapped
+    //
+    let flip = match white == black {
+        true => turn == Player::Black,
+        false => name != format!("{}v{}", white, black),
+    };
+
+    Some((name, flip, position))
+}
+
+/// The strongest piece first, as in the table names.
+///
+const ORDER: &str = "KQRBNP";
+
+/// The table name for the material of the two sides, with the side
+/// with more pieces, or else the stronger ones, first.
+///
+fn normalise(white: &str, black: &str) -> String {
+    let rank = |side: &str| -> Option<Vec<usize>> { side.chars().map(|c| ORDER.find(c)).collect() };
+
+    let (Some(w), Some(b)) = (rank(white), rank(black)) else {
+        return String::new();
+    };
+
+    if (white.len(), b) < (black.len(), w) {
+        format!("{}v{}", black, white)
+    } else {
+        format!("{}v{}", white, black)
+    }
+}
+
+fn has_castling_rights(board: &Board) -> bool {
+    [Player::White, Player::Black].into_iter().any(|player| {
+        let Some(king) = board.find_king(player) else {
+            return false;
+        };
+
+        board.get(king).is_some_and(|k| k.move_count == 0)
+            && board
+                .pieces(player)
+                .any(|(pos, p)| p.ty == PieceType::Rook && p.move_count == 0 && pos.y == king.y)
+    })
+}
+
+fn is_mate(board: &Board, turn: Player) -> bool {
+    board.variant.in_check(board, turn) && board.all_legal_moves(turn).is_empty()
+}
+
+/// The verdict last worked out for the oracle and the hash of its
+/// position, kept until the position changes.
+///
+struct Oracle {
+    key: u64,
+    verdict: Option<Verdict>,
+}
+
+/// Consulting the tablebases loaded into the game.
+///
+impl Chess {
+    /// The endgame tablebases to look positions up in, if any were loaded.
+    ///
+    pub fn tablebase(&self) -> Option<Arc<Tablebase>> {
+        self.extensions.shared()
+    }
+
+    pub fn set_tablebase(&mut self, tablebase: Arc<Tablebase>) {
+        self.extensions.share(tablebase);
+    }
+
+    /// The tablebase verdict on the current position, worked out once
+    /// for each position and kept with the game for the endgame oracle.
+    ///
+    pub fn oracle(&mut self) -> Option<&Verdict> {
+        let tablebase = self.tablebase()?;
+        let key = position_hash(&self.board, self.turn);
+
+        if self.extensions.get::<Oracle>().is_none_or(|o| o.key != key) {
+            let verdict = tablebase.verdict(&self.board, self.turn);
+            self.extensions.insert(Oracle { key, verdict });
+        }
+
+        self.extensions.get::<Oracle>()?.verdict.as_ref()
+    }
+
+    /// Show or hide the endgame oracle.
+    ///
+    pub fn toggle_oracle(&mut self) {
+        if self.tablebase().is_none() {
+            self.view.message = Some("No tablebases loaded".into());
+            return;
+        }
+
+        self.view.show_oracle = !self.view.show_oracle;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::fen;
+
+    fn tablebase() -> Tablebase {
+        Tablebase::open(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/syzygy")).unwrap()
+    }
+
+    fn position(text: &str) -> (Board, Player) {
+        let fen = fen::parse(text).unwrap();
+        (fen.board, fen.turn)
+    }
+
+    fn probe(tablebase: &Tablebase, text: &str) -> (Option<Wdl>, Option<i32>) {
+        let (board, turn) = position(text);
+        (
+            tablebase.probe_wdl(&board, turn),
+            tablebase.probe_dtz(&board, turn),
+        )
+    }
+
+    #[test]
+    fn opens_tables() {
+        let tablebase = tablebase();
+
+        assert_eq!(tablebase.len(), 2);
+        assert_eq!(tablebase.max_pieces(), 3);
+    }
+
+    #[test]
+    fn queen_endings() {
+        let tablebase = tablebase();
+
+        // mate in one, and the position after it
+        //
+        assert_eq!(
+            probe(&tablebase, "7k/8/6K1/8/8/8/8/1Q6 w - - 0 1"),
+            (Some(Wdl::Win), Some(1))
+        );
+        assert_eq!(
+            probe(&tablebase, "1Q5k/8/6K1/8/8/8/8/8 b - - 0 1"),
+            (Some(Wdl::Loss), Some(-1))
+        );
+
+        // the longest win, ten moves to mate
+        //
+        assert_eq!(
+            probe(&tablebase, "8/8/8/5k2/8/8/1Q6/K7 w - - 0 1"),
+            (Some(Wdl::Win), Some(19))
+        );
+
+        // stalemate, and a queen left hanging
+        //
+        assert_eq!(
+            probe(&tablebase, "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"),
+            (Some(Wdl::Draw), Some(0))
+        );
+        assert_eq!(
+            probe(&tablebase, "8/8/8/8/8/2k5/3Q4/7K b - - 0 1"),
+            (Some(Wdl::Draw), Some(0))
+        );
+    }
+
+    #[test]
+    fn rook_endings() {
+        let tablebase = tablebase();
+
+        assert_eq!(
+            probe(&tablebase, "8/8/8/8/8/2k5/1R6/K7 w - - 0 1"),
+            (Some(Wdl::Win), Some(31))
+        );
+        assert_eq!(
+            probe(&tablebase, "8/8/8/8/8/8/1Rk5/K7 b - - 0 1"),
+            (Some(Wdl::Loss), Some(-32))
+        );
+        assert_eq!(
+            probe(&tablebase, "8/8/8/8/8/8/1Rk5/7K b - - 0 1"),
+            (Some(Wdl::Draw), Some(0))
+        );
+    }
+
+    #[test]
+    fn black_pieces_are_flipped() {
+        let tablebase = tablebase();
+
+        assert_eq!(
+            probe(&tablebase, "1q6/8/8/8/8/6k1/8/7K b - - 0 1"),
+            (Some(Wdl::Win), Some(1))
+        );
+        assert_eq!(
+            probe(&tablebase, "k7/1rK5/8/8/8/8/8/8 w - - 0 1"),
+            (Some(Wdl::Loss), Some(-32))
+        );
+    }
+
+    #[test]
+    fn positions_outside_the_tables() {
+        let tablebase = tablebase();
+
+        assert_eq!(
+            probe(&tablebase, "8/8/8/4k3/8/8/8/4K3 w - - 0 1"),
+            (Some(Wdl::Draw), Some(0))
+        );
+        assert_eq!(
+            probe(&tablebase, "4k3/8/8/8/8/8/8/4K2R w K - 0 1"),
+            (None, None)
+        );
+        assert_eq!(
+            probe(&tablebase, "4k3/8/8/8/8/8/8/3QK2R w - - 0 1"),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn root_moves_prefer_mate() {
+        let tablebase = tablebase();
+        let (board, turn) = position("7k/8/6K1/8/8/8/8/1Q6 w - - 0 1");
+
+        let moves = tablebase.root_moves(&board, turn).unwrap();
+        let best = moves[0];
+
+        assert_eq!((best.wdl, best.dtz), (Wdl::Win, 1));
+        assert_eq!(best.m.pos, Vec2::new(1, 0));
+        assert_eq!(moves.len(), board.all_legal_moves(turn).len());
+    }
+
+    #[test]
+    fn table_names() {
+        assert_eq!(normalise("KQ", "K"), "KQvK");
+        assert_eq!(normalise("K", "KQ"), "KQvK");
+        assert_eq!(normalise("KN", "KR"), "KRvKN");
+        assert_eq!(normalise("KB", "KN"), "KBvKN");
+        assert_eq!(normalise("KR", "KRP"), "KRPvKR");
+        assert_eq!(normalise("K", "KNN"), "KNNvK");
+        assert_eq!(normalise("KX", "K"), "");
+    }
+
+    #[test]
+    fn material_flips_colours() {
+        let flip = |text: &str| {
+            let (board, turn) = position(text);
+            let (name, flip, _) = material(&board, turn).unwrap();
+            (name, flip)
+        };
+
+        assert_eq!(
+            flip("4k3/8/8/8/8/8/8/3QK3 w - - 0 1"),
+            ("KQvK".into(), false)
+        );
+        assert_eq!(
+            flip("4k3/8/8/8/8/8/8/3QK3 b - - 0 1"),
+            ("KQvK".into(), false)
+        );
+        assert_eq!(
+            flip("3qk3/8/8/8/8/8/8/4K3 w - - 0 1"),
+            ("KQvK".into(), true)
+        );
+        assert_eq!(
+            flip("3rk3/8/8/8/8/8/8/2BRK3 w - - 0 1"),
+            ("KRBvKR".into(), false)
+        );
+
+        // tables with the same pieces on both sides store white to move
+        //
+        assert_eq!(
+            flip("3rk3/8/8/8/8/8/8/3RK3 w - - 0 1"),
+            ("KRvKR".into(), false)
+        );
+        assert_eq!(
+            flip("3rk3/8/8/8/8/8/8/3RK3 b - - 0 1"),
+            ("KRvKR".into(), true)
+        );
+    }
+
+    #[test]
+    fn material_squares() {
+        let (board, turn) = position("3qk3/8/8/8/8/8/8/R3K3 b - - 0 1");
+        let (_, _, position) = material(&board, turn).unwrap();
+
+        assert!(position.black_to_move);
+        assert_eq!(position.pieces[Player::White as usize][KING as usize], [4]);
+        assert_eq!(position.pieces[Player::White as usize][4], [0]);
+        assert_eq!(position.pieces[Player::Black as usize][KING as usize], [60]);
+        assert_eq!(position.pieces[Player::Black as usize][5], [59]);
+    }
+}
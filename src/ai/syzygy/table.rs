@@ -0,0 +1,1042 @@
+use std::{io, sync::OnceLock};
+
+/// Which of the two kinds of file a table was read from.
+///
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(super) enum Kind {
+    /// `.rtbw`, win/draw/loss.
+    Wdl,
+    /// `.rtbz`, distance to zeroing the fifty-move counter.
+    Dtz,
+}
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xe8, 0x23, 0x5d];
+const DTZ_MAGIC: [u8; 4] = [0xd7, 0x66, 0x0c, 0xa5];
+
+// flags kept with each compressed block of values
+//
+const STM: u8 = 1;
+const MAPPED: u8 = 2;
+const WIN_PLIES: u8 = 4;
+const LOSS_PLIES: u8 = 8;
+const WIDE: u8 = 16;
+const SINGLE_VALUE: u8 = 128;
+
+/// The piece codes used in the files, with 8 added for black.
+///
+pub(super) const PAWN: u8 = 1;
+pub(super) const KING: u8 = 6;
+
+/// The pieces of a position as the tables see them: the squares,
+/// numbered from a1 along the ranks, of each colour's pieces of each
+/// code.
+///
+pub(super) struct Position {
+    pub pieces: [[Vec<u8>; 7]; 2],
+    pub black_to_move: bool,
+}
+
+/// A table file read into memory, with what is needed to find the
+/// value for any position of its material.
+///
+pub(super) struct Table {
+    data: Vec<u8>,
+    kind: Kind,
+    /// The number of pieces, kings included.
+    count: usize,
+    symmetric: bool,
+    has_pawns: bool,
+    /// The number of leading pawns, those of the side with fewer, and
+    /// then of the other side's.
+    pawns: [usize; 2],
+    /// One for each file the leading pawn may be on, a to d, or just
+    /// the one without pawns.
+    files: Vec<File>,
+}
+
+struct File {
+    /// One for each side to move, or just the one when both are the
+    /// same or, for DTZ, only one side is stored.
+    sides: Vec<Side>,
+}
+
+struct Side {
+    /// The piece codes in the order they are encoded.
+    pieces: Vec<u8>,
+    /// The pieces are encoded in groups of the same piece, each group
+    /// given by its length and the factor its index is multiplied by.
+    groups: Vec<(usize, u64)>,
+    pairs: Pairs,
+    /// For DTZ, where each of the four value maps begins.
+    map: [usize; 4],
+}
+
+/// The Huffman-like compression of a side's values: symbols stand for
+/// pairs of smaller symbols, down to the values themselves.
+///
+struct Pairs {
+    flags: u8,
+    /// The value of every position, when it is the same for all.
+    single: Option<u16>,
+    block_size: usize,
+    span: u64,
+    min_len: u32,
+    base: Vec<u64>,
+    symlen: Vec<u32>,
+    lowest: usize,
+    btree: usize,
+    // where the index, block lengths and blocks begin, and their sizes
+    //
+    index: usize,
+    index_size: usize,
+    lengths: usize,
+    lengths_size: usize,
+    blocks: usize,
+    blocks_size: usize,
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// Read-only access to the file's bytes, failing at the end of them
+/// rather than panicking.
+///
+struct Bytes<'a>(&'a [u8]);
+
+impl Bytes<'_> {
+    fn u8(&self, at: usize) -> io::Result<u8> {
+        self.0
+            .get(at)
+            .copied()
+            .ok_or_else(|| invalid("the table ends early"))
+    }
+
+    fn u16(&self, at: usize) -> io::Result<u16> {
+        Ok(u16::from_le_bytes([self.u8(at)?, self.u8(at + 1)?]))
+    }
+
+    fn u32(&self, at: usize) -> io::Result<u32> {
+        Ok(u32::from_le_bytes([
+            self.u8(at)?,
+            self.u8(at + 1)?,
+            self.u8(at + 2)?,
+            self.u8(at + 3)?,
+        ]))
+    }
+}
+
+impl Table {
+    /// Read a table for the material `name`, e.g. `KRPvKR`, from the
+    /// contents of its file.
+    ///
+    pub(super) fn parse(data: Vec<u8>, kind: Kind, name: &str) -> io::Result<Table> {
+        let (white, black) = name.split_once('v').ok_or_else(|| invalid("bad name"))?;
+
+        let count = white.len() + black.len();
+        let symmetric = white == black;
+
+        let mut pawns = [white.matches('P').count(), black.matches('P').count()];
+        if pawns[1] > 0 && (pawns[0] == 0 || pawns[1] < pawns[0]) {
+            pawns.swap(0, 1);
+        }
+        let has_pawns = pawns[0] > 0;
+
+        let bytes = Bytes(&data);
+
+        let magic = match kind {
+            Kind::Wdl => WDL_MAGIC,
+            Kind::Dtz => DTZ_MAGIC,
+        };
+        if data.get(..4) != Some(&magic[..]) {
+            return Err(invalid("not a Syzygy table"));
+        }
+
+        let flags = bytes.u8(4)?;
+        if (flags & 2 != 0) != has_pawns {
+            return Err(invalid("the table doesn't match its name"));
+        }
+
+        let file_count = if has_pawns { 4 } else { 1 };
+        let side_count = match kind {
+            Kind::Wdl if flags & 1 != 0 => 2,
+            _ => 1,
+        };
+
+        // the order the pieces are encoded in, for each file and side
+        //
+        let order_bytes = if has_pawns && pawns[1] > 0 { 2 } else { 1 };
+        let mut at = 5;
+        let mut headers = Vec::new();
+
+        for _ in 0..file_count {
+            let mut sides = Vec::new();
+
+            for side in 0..side_count {
+                let nibble = |byte: u8| (byte >> (4 * side)) & 0xf;
+
+                let order = nibble(bytes.u8(at)?) as usize;
+                let order2 = match order_bytes {
+                    2 => nibble(bytes.u8(at + 1)?) as usize,
+                    _ => 0xf,
+                };
+                let pieces = (0..count)
+                    .map(|i| bytes.u8(at + order_bytes + i).map(nibble))
+                    .collect::<io::Result<Vec<u8>>>()?;
+
+                sides.push((pieces, order, order2));
+            }
+
+            headers.push(sides);
+            at += count + order_bytes;
+        }
+        at += at & 1;
+
+        // the layout of the compressed values
+        //
+        let mut files = Vec::new();
+
+        for (file, sides) in headers.into_iter().enumerate() {
+            let mut parsed = Vec::new();
+
+            for (pieces, order, order2) in sides {
+                let lengths = group_lengths(&pieces, has_pawns, pawns)?;
+                let (groups, size) = group_factors(&lengths, order, order2, file, has_pawns);
+
+                let pairs = Pairs::parse(&bytes, &mut at, size)?;
+
+                parsed.push(Side {
+                    pieces,
+                    groups,
+                    pairs,
+                    map: [0; 4],
+                });
+            }
+
+            files.push(File { sides: parsed });
+        }
+
+        // DTZ values may be stored as indices into maps of the real ones
+        //
+        if kind == Kind::Dtz {
+            for file in &mut files {
+                let side = &mut file.sides[0];
+                if side.pairs.flags & MAPPED == 0 {
+                    continue;
+                }
+
+                if side.pairs.flags & WIDE != 0 {
+                    at += at & 1;
+                    for start in &mut side.map {
+                        *start = at + 2;
+                        at += 2 + 2 * bytes.u16(at)? as usize;
+                    }
+                } else {
+                    for start in &mut side.map {
+                        *start = at + 1;
+                        at += 1 + bytes.u8(at)? as usize;
+                    }
+                }
+            }
+            at += at & 1;
+        }
+
+        for file in &mut files {
+            for side in &mut file.sides {
+                side.pairs.index = at;
+                at += side.pairs.index_size;
+            }
+        }
+
+        for file in &mut files {
+            for side in &mut file.sides {
+                side.pairs.lengths = at;
+                at += side.pairs.lengths_size;
+            }
+        }
+
+        for file in &mut files {
+            for side in &mut file.sides {
+                if side.pairs.blocks_size > 0 {
+                    at = (at + 0x3f) & !0x3f;
+                }
+                side.pairs.blocks = at;
+                at += side.pairs.blocks_size;
+            }
+        }
+
+        if at > data.len() {
+            return Err(invalid("the table ends early"));
+        }
+
+        Ok(Table {
+            data,
+            kind,
+            count,
+            symmetric,
+            has_pawns,
+            pawns,
+            files,
+        })
+    }
+
+    /// The win/draw/loss value, from -2 for a loss to 2 for a win, for
+    /// the player to move. `flip` is set when the position's colours are
+    /// the other way round to the table's.
+    ///
+    pub(super) fn wdl(&self, pos: &Position, flip: bool) -> Option<i32> {
+        debug_assert_eq!(self.kind, Kind::Wdl);
+
+        let (_, value) = self.lookup(pos, flip)?;
+
+        Some(value as i32 - 2)
+    }
+
+    /// The plies to zeroing stored for the position, whose value is
+    /// `wdl`. Only one side to move is stored, so `Some(None)` is
+    /// returned for positions with the other side to move.
+    ///
+    pub(super) fn dtz(&self, pos: &Position, flip: bool, wdl: i32) -> Option<Option<i32>> {
+        debug_assert_eq!(self.kind, Kind::Dtz);
+
+        let Some((side, value)) = self.lookup(pos, flip) else {
+            return match self.stores_other_side(pos, flip) {
+                true => Some(None),
+                false => None,
+            };
+        };
+
+        const MAPS: [usize; 5] = [1, 3, 0, 2, 0];
+        const PLIES: [u8; 5] = [LOSS_PLIES, 0, 0, 0, WIN_PLIES];
+
+        let flags = side.pairs.flags;
+        let bytes = Bytes(&self.data);
+        let i = (wdl + 2) as usize;
+
+        let mut value = value as i32;
+
+        if flags & MAPPED != 0 {
+            let start = side.map[MAPS[i]];
+            value = match flags & WIDE != 0 {
+                true => bytes.u16(start + 2 * value as usize).ok()? as i32,
+                false => bytes.u8(start + value as usize).ok()? as i32,
+            };
+        }
+
+        // some tables count in moves rather than plies
+        //
+        if flags & PLIES[i] == 0 || wdl.abs() == 1 {
+            value *= 2;
+        }
+
+        Some(Some(value + 1))
+    }
+
+    /// Whether a DTZ table has only the values for the other side to
+    /// move to the position's.
+    ///
+    fn stores_other_side(&self, pos: &Position, flip: bool) -> bool {
+        if self.kind != Kind::Dtz || (self.symmetric && !self.has_pawns) {
+            return false;
+        }
+
+        let mut squares = [0u8; 7];
+        let stm = (flip ^ pos.black_to_move) as u8;
+        let file = self.pawn_file(pos, flip, &mut squares);
+
+        self.files[file].sides[0].pairs.flags & STM != stm
+    }
+
+    /// The square of each piece, in the order the table encodes them,
+    /// after placing the leading pawns and finding the file they give.
+    ///
+    fn pawn_file(&self, pos: &Position, flip: bool, squares: &mut [u8; 7]) -> usize {
+        if !self.has_pawns {
+            return 0;
+        }
+
+        let (cmirror, mirror) = if flip { (8, 0x38) } else { (0, 0) };
+
+        let lead = self.files[0].sides[0].pieces[0] ^ cmirror;
+        let colour = (lead >> 3) as usize;
+
+        for (i, sq) in pos.pieces[colour][PAWN as usize]
+            .iter()
+            .take(self.pawns[0])
+            .enumerate()
+        {
+            squares[i] = sq ^ mirror;
+        }
+
+        // the leading pawn is the one nearest the edge, and then the
+        // furthest back
+        //
+        let tables = tables();
+        for i in 1..self.pawns[0] {
+            if tables.map_pawns[squares[i] as usize] > tables.map_pawns[squares[0] as usize] {
+                squares.swap(0, i);
+            }
+        }
+
+        edge_distance(squares[0] % 8)
+    }
+
+    /// The side whose values are stored for the position, and the value.
+    /// There is none when a DTZ table stores the other side's.
+    ///
+    fn lookup(&self, pos: &Position, flip: bool) -> Option<(&Side, u16)> {
+        let (cmirror, mirror) = if flip { (8, 0x38) } else { (0, 0) };
+        let stm = (flip ^ pos.black_to_move) as usize;
+
+        let mut squares = [0u8; 7];
+        let file = self.pawn_file(pos, flip, &mut squares);
+
+        let sides = &self.files[file].sides;
+        let side = &sides[stm.min(sides.len() - 1)];
+
+        if self.kind == Kind::Dtz
+            && (side.pairs.flags & STM) as usize != stm
+            && (self.has_pawns || !self.symmetric)
+        {
+            return None;
+        }
+
+        // the remaining pieces, in the order of the table's
+        //
+        let mut n = self.pawns[0];
+        while n < self.count {
+            let code = side.pieces[n];
+            let colour = ((code ^ cmirror) >> 3) as usize;
+            let ty = (code & 7) as usize;
+
+            let first = n;
+            for sq in pos.pieces.get(colour)?.get(ty)? {
+                *squares.get_mut(n)? = sq ^ mirror;
+                n += 1;
+            }
+
+            if n == first || side.pieces.get(n - 1).is_none_or(|c| *c != code) {
+                return None;
+            }
+        }
+
+        let index = match self.has_pawns {
+            true => self.encode_pawns(side, &mut squares[..self.count]),
+            false => encode_pieces(side, &mut squares[..self.count])?,
+        };
+
+        let value = side.pairs.decompress(&self.data, index)?;
+
+        Some((side, value))
+    }
+
+    /// The index of a position with pawns, whose leading pawn is on
+    /// the a to d files once mirrored.
+    ///
+    fn encode_pawns(&self, side: &Side, squares: &mut [u8]) -> u64 {
+        let tables = tables();
+        let lead = self.pawns[0];
+
+        if squares[0] % 8 > 3 {
+            for sq in squares.iter_mut() {
+                *sq ^= 7;
+            }
+        }
+
+        squares[1..lead].sort_by_key(|sq| tables.map_pawns[*sq as usize]);
+
+        let mut index = tables.lead_pawn_index[lead][squares[0] as usize];
+        for (i, sq) in squares.iter().enumerate().take(lead).skip(1) {
+            index += binomial(tables.map_pawns[*sq as usize] as i64, i);
+        }
+
+        encode_groups(side, squares, index, self.pawns[1] > 0)
+    }
+}
+
+/// The index of a position without pawns. The board is mirrored and
+/// turned so that the first piece is in the a1-d1-d4 triangle, and if
+/// the leading pieces are on the diagonal, below it.
+///
+fn encode_pieces(side: &Side, squares: &mut [u8]) -> Option<u64> {
+    if squares[0] % 8 > 3 {
+        for sq in squares.iter_mut() {
+            *sq ^= 7;
+        }
+    }
+
+    if squares[0] / 8 > 3 {
+        for sq in squares.iter_mut() {
+            *sq ^= 0x38;
+        }
+    }
+
+    let lead = side.groups[0].0;
+    for i in 0..lead {
+        match off_diagonal(squares[i]) {
+            0 => continue,
+            d if d > 0 => {
+                for sq in squares.iter_mut() {
+                    *sq = ((*sq >> 3) | (*sq << 3)) & 63;
+                }
+            }
+            _ => {}
+        }
+        break;
+    }
+
+    let tables = tables();
+
+    let index = if lead == 3 {
+        let [s0, s1, s2] = [squares[0], squares[1], squares[2]].map(|s| s as u64);
+        let adjust1 = (s1 > s0) as u64;
+        let adjust2 = (s2 > s0) as u64 + (s2 > s1) as u64;
+
+        let rank = |sq: u64| sq / 8;
+        let lower = |sq: u64| LOWER[sq as usize] as u64;
+
+        if off_diagonal(squares[0]) != 0 {
+            (TRIANGLE[s0 as usize] as u64 * 63 + (s1 - adjust1)) * 62 + s2 - adjust2
+        } else if off_diagonal(squares[1]) != 0 {
+            (6 * 63 + rank(s0) * 28 + lower(s1)) * 62 + s2 - adjust2
+        } else if off_diagonal(squares[2]) != 0 {
+            6 * 63 * 62 + 4 * 28 * 62 + rank(s0) * 7 * 28 + (rank(s1) - adjust1) * 28 + lower(s2)
+        } else {
+            6 * 63 * 62
+                + 4 * 28 * 62
+                + 4 * 7 * 28
+                + rank(s0) * 7 * 6
+                + (rank(s1) - adjust1) * 6
+                + (rank(s2) - adjust2)
+        }
+    } else {
+        let index = tables.kings[TRIANGLE[squares[0] as usize] as usize][squares[1] as usize];
+        u64::try_from(index).ok()?
+    };
+
+    Some(encode_groups(side, squares, index, false))
+}
+
+/// Add the groups after the leading one to `index`, each as the
+/// combination of squares it takes among those left free.
+///
+fn encode_groups(side: &Side, squares: &mut [u8], index: u64, pawns_next: bool) -> u64 {
+    let mut index = index * side.groups[0].1;
+    let mut start = side.groups[0].0;
+    let mut remaining_pawns = pawns_next;
+
+    for &(len, factor) in &side.groups[1..] {
+        squares[start..start + len].sort_unstable();
+
+        let mut n = 0;
+        for i in 0..len {
+            let sq = squares[start + i];
+            let adjust = squares[..start].iter().filter(|s| sq > **s).count() as i64;
+            let skip = if remaining_pawns { 8 } else { 0 };
+
+            n += binomial(sq as i64 - adjust - skip, i + 1);
+        }
+
+        remaining_pawns = false;
+        index += n * factor;
+        start += len;
+    }
+
+    index
+}
+
+/// The lengths of the groups the pieces are encoded in: the leading
+/// pawns, or the two or three leading pieces, then any other pawns,
+/// then each run of the same piece.
+///
+fn group_lengths(pieces: &[u8], has_pawns: bool, pawns: [usize; 2]) -> io::Result<Vec<usize>> {
+    let mut lengths = Vec::new();
+
+    if has_pawns {
+        lengths.push(pawns[0]);
+        if pawns[1] > 0 {
+            lengths.push(pawns[1]);
+        }
+    } else {
+        let unique = pieces
+            .iter()
+            .filter(|p| pieces.iter().filter(|q| q == p).count() == 1)
+            .count();
+
+        // the other encodings are only used by variants
+        //
+        if unique < 2 {
+            return Err(invalid("unsupported table encoding"));
+        }
+        lengths.push(if unique >= 3 { 3 } else { 2 });
+    }
+
+    let mut i: usize = lengths.iter().sum();
+    while i < pieces.len() {
+        let len = pieces[i..].iter().take_while(|p| **p == pieces[i]).count();
+        lengths.push(len);
+        i += len;
+    }
+
+    Ok(lengths)
+}
+
+/// The factor of each group, given the order they are stored in, and
+/// the total number of positions.
+///
+fn group_factors(
+    lengths: &[usize],
+    order: usize,
+    order2: usize,
+    file: usize,
+    has_pawns: bool,
+) -> (Vec<(usize, u64)>, u64) {
+    let tables = tables();
+    let mut factors = vec![0; lengths.len()];
+
+    let pawns_next = has_pawns && order2 < 0xf;
+    let mut next = if pawns_next { 2 } else { 1 };
+    let mut free = 64 - lengths[..next].iter().sum::<usize>();
+
+    let mut size = 1;
+    let mut k = 0;
+
+    while next < lengths.len() || k == order || k == order2 {
+        if k == order {
+            factors[0] = size;
+            size *= match (has_pawns, lengths[0]) {
+                (true, lead) => tables.lead_pawns_size[lead][file],
+                (false, 3) => 31_332,
+                (false, _) => 462,
+            };
+        } else if k == order2 {
+            factors[1] = size;
+            size *= binomial(48 - lengths[0] as i64, lengths[1]);
+        } else {
+            factors[next] = size;
+            size *= binomial(free as i64, lengths[next]);
+            free -= lengths[next];
+            next += 1;
+        }
+        k += 1;
+    }
+
+    (lengths.iter().copied().zip(factors).collect(), size)
+}
+
+impl Pairs {
+    fn parse(bytes: &Bytes, at: &mut usize, size: u64) -> io::Result<Pairs> {
+        let start = *at;
+        let flags = bytes.u8(start)?;
+
+        let mut pairs = Pairs {
+            flags,
+            single: None,
+            block_size: 0,
+            span: 1,
+            min_len: 0,
+            base: Vec::new(),
+            symlen: Vec::new(),
+            lowest: 0,
+            btree: 0,
+            index: 0,
+            index_size: 0,
+            lengths: 0,
+            lengths_size: 0,
+            blocks: 0,
+            blocks_size: 0,
+        };
+
+        if flags & SINGLE_VALUE != 0 {
+            pairs.single = Some(bytes.u8(start + 1)? as u16);
+            *at = start + 2;
+            return Ok(pairs);
+        }
+
+        pairs.block_size = 1 << bytes.u8(start + 1)?;
+        pairs.span = 1 << bytes.u8(start + 2)?;
+        let padding = bytes.u8(start + 3)? as usize;
+        let blocks = bytes.u32(start + 4)? as usize;
+        let max_len = bytes.u8(start + 8)? as u32;
+        let min_len = bytes.u8(start + 9)? as u32;
+
+        if max_len < min_len || pairs.span < 2 {
+            return Err(invalid("bad symbol lengths"));
+        }
+
+        let lengths = (max_len - min_len + 1) as usize;
+        pairs.min_len = min_len;
+        pairs.lowest = start + 10;
+
+        let symbols = bytes.u16(start + 10 + 2 * lengths)? as usize;
+        pairs.btree = start + 12 + 2 * lengths;
+        *at = pairs.btree + 3 * symbols + (symbols & 1);
+
+        pairs.index_size = 6 * size.div_ceil(pairs.span) as usize;
+        pairs.lengths_size = 2 * (blocks + padding);
+        pairs.blocks_size = blocks * pairs.block_size;
+
+        // the first code of each length, left aligned, so a code's length
+        // is found by comparing it with them
+        //
+        let mut base = vec![0u64; lengths];
+        for i in (0..lengths - 1).rev() {
+            let lowest = bytes.u16(pairs.lowest + 2 * i)? as u64;
+            let above = bytes.u16(pairs.lowest + 2 * i + 2)? as u64;
+            base[i] = base[i + 1].wrapping_add(lowest).wrapping_sub(above) / 2;
+        }
+        for (i, base) in base.iter_mut().enumerate() {
+            *base = base.checked_shl(64 - i as u32 - min_len).unwrap_or(0);
+        }
+        pairs.base = base;
+
+        // how many values each symbol stands for, less one
+        //
+        let mut symlen = vec![None; symbols];
+        for symbol in 0..symbols {
+            pairs.symbol_length(bytes, symbol, &mut symlen)?;
+        }
+        pairs.symlen = symlen.into_iter().map(|l| l.unwrap_or(0)).collect();
+
+        Ok(pairs)
+    }
+
+    /// The two symbols `symbol` stands for. A leaf's right one is 0xfff,
+    /// and its left one is the value.
+    ///
+    fn children(data: &[u8], btree: usize, symbol: usize) -> Option<(usize, usize)> {
+        let node = data.get(btree + 3 * symbol..btree + 3 * symbol + 3)?;
+        let left = ((node[1] as usize & 0xf) << 8) | node[0] as usize;
+        let right = ((node[2] as usize) << 4) | (node[1] as usize >> 4);
+
+        Some((left, right))
+    }
+
+    fn symbol_length(
+        &self,
+        bytes: &Bytes,
+        symbol: usize,
+        lengths: &mut [Option<u32>],
+    ) -> io::Result<u32> {
+        if let Some(len) = lengths.get(symbol).copied().flatten() {
+            return Ok(len);
+        }
+
+        let (left, right) = Pairs::children(bytes.0, self.btree, symbol)
+            .ok_or_else(|| invalid("the table ends early"))?;
+
+        let len = if right == 0xfff {
+            0
+        } else {
+            if left >= lengths.len() || right >= lengths.len() {
+                return Err(invalid("bad symbol tree"));
+            }
+            self.symbol_length(bytes, left, lengths)?
+                + self.symbol_length(bytes, right, lengths)?
+                + 1
+        };
+
+        lengths[symbol] = Some(len);
+        Ok(len)
+    }
+
+    /// The value of the position numbered `index`.
+    ///
+    fn decompress(&self, data: &[u8], index: u64) -> Option<u16> {
+        if let Some(value) = self.single {
+            return Some(value);
+        }
+
+        let bytes = Bytes(data);
+
+        // the sparse index gives a block and an offset near the value,
+        // which is then found by walking the block lengths
+        //
+        let k = (index / self.span) as usize;
+        let mut block = bytes.u32(self.index + 6 * k).ok()? as i64;
+        let mut offset = bytes.u16(self.index + 6 * k + 4).ok()? as i64
+            + (index % self.span) as i64
+            - (self.span / 2) as i64;
+
+        let length = |block: i64| -> Option<i64> {
+            Some(
+                bytes
+                    .u16(self.lengths + 2 * usize::try_from(block).ok()?)
+                    .ok()? as i64,
+            )
+        };
+
+        while offset < 0 {
+            block -= 1;
+            offset += length(block)? + 1;
+        }
+        while offset > length(block)? {
+            offset -= length(block)? + 1;
+            block += 1;
+        }
+
+        // past the end of the file reads as zeroes, as the last code may
+        // be read with some bytes beyond it
+        //
+        let mut at = self.blocks + block as usize * self.block_size;
+        let mut next_u32 = || {
+            let mut word = [0u8; 4];
+            for (i, byte) in word.iter_mut().enumerate() {
+                *byte = data.get(at + i).copied().unwrap_or(0);
+            }
+            at += 4;
+            u32::from_be_bytes(word) as u64
+        };
+
+        let mut buf = (next_u32() << 32) | next_u32();
+        let mut bits = 64;
+
+        let mut symbol;
+        loop {
+            let mut len = 0;
+            while buf < *self.base.get(len)? {
+                len += 1;
+            }
+
+            let shift = 64 - len as u32 - self.min_len;
+            symbol = (buf - self.base[len]).checked_shr(shift).unwrap_or(0) as usize
+                + bytes.u16(self.lowest + 2 * len).ok()? as usize;
+
+            let symlen = *self.symlen.get(symbol)? as i64;
+            if offset < symlen + 1 {
+                break;
+            }
+            offset -= symlen + 1;
+
+            let len = len as u32 + self.min_len;
+            buf = buf.checked_shl(len).unwrap_or(0);
+            bits -= len as i32;
+
+            if bits <= 32 {
+                bits += 32;
+                buf |= next_u32() << (64 - bits);
+            }
+        }
+
+        // then down the tree to the value itself
+        //
+        while self.symlen[symbol] != 0 {
+            let (left, right) = Pairs::children(data, self.btree, symbol)?;
+            let left_len = *self.symlen.get(left)? as i64;
+
+            if offset < left_len + 1 {
+                symbol = left;
+            } else {
+                offset -= left_len + 1;
+                symbol = right;
+            }
+        }
+
+        Pairs::children(data, self.btree, symbol).map(|(value, _)| value as u16)
+    }
+}
+
+/// Rank less file of a square, zero on the a1-h8 diagonal.
+///
+fn off_diagonal(sq: u8) -> i32 {
+    (sq / 8) as i32 - (sq % 8) as i32
+}
+
+fn edge_distance(file: u8) -> usize {
+    file.min(7 - file) as usize
+}
+
+fn binomial(n: i64, k: usize) -> u64 {
+    if n < k as i64 {
+        return 0;
+    }
+
+    let mut result: u64 = 1;
+    for i in 0..k as u64 {
+        result = result * (n as u64 - i) / (i + 1);
+    }
+    result
+}
+
+/// The numbering of the a1-d1-d4 triangle: the squares off the
+/// diagonal, then those on it.
+///
+#[rustfmt::skip]
+const TRIANGLE: [u8; 64] = [
+    6, 0, 1, 2, 2, 1, 0, 6,
+    0, 7, 3, 4, 4, 3, 7, 0,
+    1, 3, 8, 5, 5, 8, 3, 1,
+    2, 4, 5, 9, 9, 5, 4, 2,
+    2, 4, 5, 9, 9, 5, 4, 2,
+    1, 3, 8, 5, 5, 8, 3, 1,
+    0, 7, 3, 4, 4, 3, 7, 0,
+    6, 0, 1, 2, 2, 1, 0, 6,
+];
+
+/// The numbering of the squares below the a1-h8 diagonal, b1 to h7.
+///
+#[rustfmt::skip]
+const LOWER: [u8; 64] = [
+    28,  0,  1,  2,  3,  4,  5,  6,
+     0, 29,  7,  8,  9, 10, 11, 12,
+     1,  7, 30, 13, 14, 15, 16, 17,
+     2,  8, 13, 31, 18, 19, 20, 21,
+     3,  9, 14, 18, 32, 22, 23, 24,
+     4, 10, 15, 19, 22, 33, 25, 26,
+     5, 11, 16, 20, 23, 25, 34, 27,
+     6, 12, 17, 21, 24, 26, 27, 35,
+];
+
+/// Tables worked out once, on first use.
+///
+struct Tables {
+    /// For each square of the triangle and square of the other king,
+    /// the index of the pair of kings.
+    kings: [[i32; 64]; 10],
+    /// For the leading pawn on a square, how many squares the others
+    /// may be on, which orders the pawns.
+    map_pawns: [u8; 64],
+    /// By the number of leading pawns, the index the positions of the
+    /// leading pawn on a square begin at, within its file.
+    lead_pawn_index: [[u64; 64]; 6],
+    /// By the number of leading pawns, the positions for each file.
+    lead_pawns_size: [[u64; 4]; 6],
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+
+    TABLES.get_or_init(|| {
+        let mut tables = Tables {
+            kings: [[-1; 64]; 10],
+            map_pawns: [0; 64],
+            lead_pawn_index: [[0; 64]; 6],
+            lead_pawns_size: [[0; 4]; 6],
+        };
+
+        // two kings, the first in the triangle, with both on the diagonal
+        // numbered last
+        //
+        let mut code = 0;
+        let mut both_on_diagonal = Vec::new();
+
+        for (index, row) in tables.kings.iter_mut().enumerate() {
+            let first = (0..64u8)
+                .find(|sq| {
+                    TRIANGLE[*sq as usize] as usize == index
+                        && sq % 8 <= 3
+                        && off_diagonal(*sq) <= 0
+                })
+                .unwrap();
+
+            for second in 0..64u8 {
+                let (df, dr) = (
+                    (first % 8) as i32 - (second % 8) as i32,
+                    (first / 8) as i32 - (second / 8) as i32,
+                );
+                if df.abs() <= 1 && dr.abs() <= 1 {
+                    continue;
+                }
+
+                match (off_diagonal(first), off_diagonal(second)) {
+                    (0, d) if d > 0 => {}
+                    (0, 0) => both_on_diagonal.push((index, second)),
+                    _ => {
+                        row[second as usize] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+
+        for (index, second) in both_on_diagonal {
+            tables.kings[index][second as usize] = code;
+            code += 1;
+        }
+
+        // a leading pawn on a2 leaves 47 squares for the others, and each
+        // step further from the edge or up the board fewer
+        //
+        let mut available = 47;
+        for file in 0..4u8 {
+            for rank in 1..7u8 {
+                let sq = rank * 8 + file;
+                tables.map_pawns[sq as usize] = available;
+                tables.map_pawns[(sq ^ 7) as usize] = available - 1;
+                available = available.saturating_sub(2);
+            }
+        }
+
+        for lead in 1..6 {
+            for file in 0..4u8 {
+                let mut index = 0;
+                for rank in 1..7u8 {
+                    let sq = (rank * 8 + file) as usize;
+                    tables.lead_pawn_index[lead][sq] = index;
+                    index += binomial(tables.map_pawns[sq] as i64, lead - 1);
+                }
+                tables.lead_pawns_size[lead][file as usize] = index;
+            }
+        }
+
+        tables
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A node of the symbol tree, packed as the tables do.
+    ///
+    fn node(left: usize, right: usize) -> [u8; 3] {
+        [
+            left as u8,
+            (left >> 8) as u8 & 0xf | (right as u8 & 0xf) << 4,
+            (right >> 4) as u8,
+        ]
+    }
+
+    #[test]
+    fn pair_symbols() {
+        // four two-bit symbols: the values 5 and 7, the pair 5 5, and
+        // that pair followed by 7
+        //
+        let mut data = vec![
+            0, // flags
+            5, // 32 byte blocks
+            4, // 16 positions for each entry of the index
+            0, // no padding
+            1, 0, 0, 0, // one block
+            2, 2, // codes of two bits
+            0, 0, // the lowest symbol with them
+            4, 0, // symbols
+        ];
+        for (left, right) in [(5, 0xfff), (7, 0xfff), (0, 0), (2, 1)] {
+            data.extend(node(left, right));
+        }
+
+        let mut at = 0;
+        let mut pairs = Pairs::parse(&Bytes(&data), &mut at, 10).unwrap();
+        assert_eq!(at, data.len());
+        assert_eq!(pairs.symlen, [0, 0, 1, 2]);
+
+        // the index points at the middle of its span, the eighth value
+        //
+        pairs.index = data.len();
+        data.extend([0, 0, 0, 0, 8, 0]);
+
+        pairs.lengths = data.len();
+        data.extend([9, 0]);
+
+        // 5 5 7, 5 5 7, 7, 5 5, 5
+        //
+        pairs.blocks = data.len();
+        data.extend([0b1111_0110, 0b0000_0000]);
+
+        let values: Vec<u16> = (0..10)
+            .map(|i| pairs.decompress(&data, i).unwrap())
+            .collect();
+        assert_eq!(values, [5, 5, 7, 5, 5, 7, 7, 5, 5, 5]);
+    }
+}
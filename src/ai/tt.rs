@@ -0,0 +1,362 @@
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+use crate::core::{Move, MoveResult, Vec2};
+
+/// How a stored score relates to the true score of the position, which
+/// is only known exactly when it fell inside the search window.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    /// The search failed high: the position is worth at least the score.
+    Lower,
+    /// The search failed low: the position is worth at most the score.
+    Upper,
+}
+
+/// A move squeezed into a few bits for the table: the squares it goes
+/// from and to, the kind of move, and the letter of a piece promoted to
+/// or dropped. That is enough to tell it apart from the other legal
+/// moves of its position, which it is matched against to be played.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MoveCode(u32);
+
+impl MoveCode {
+    const BITS: u32 = 31;
+
+    pub fn new(from: Vec2, m: &Move) -> Self {
+        let (kind, letter) = match m.result {
+            MoveResult::Cancel | MoveResult::Nothing | MoveResult::Invalid => (0, 0),
+            MoveResult::Capture(_) => (1, 0),
+            MoveResult::Castle(_) => (2, 0),
+            MoveResult::Promotion(ty) => (3, ty.letter() as u32 & 0x7f),
+            MoveResult::Drop(_, ty) => (4, ty.letter() as u32 & 0x7f),
+        };
+
+        let square = |pos: Vec2| (pos.x as u32 & 0x1f) | (pos.y as u32 & 0x1f) << 5;
+
+        Self(square(from) | square(m.pos) << 10 | kind << 20 | letter << 23 | 1 << 30)
+    }
+
+    /// Whether this is the code of the move.
+    ///
+    pub fn matches(&self, m: &(Vec2, Move)) -> bool {
+        *self == Self::new(m.0, &m.1)
+    }
+
+    /// The move this is the code of, out of the legal moves.
+    ///
+    pub fn find(&self, moves: &[(Vec2, Move)]) -> Option<(Vec2, Move)> {
+        moves.iter().find(|m| self.matches(m)).copied()
+    }
+}
+
+/// What a search found out about one position.
+///
+#[derive(Copy, Clone)]
+pub struct Entry {
+    pub key: u64,
+    pub depth: u8,
+    pub score: i32,
+    pub bound: Bound,
+    /// The best move found.
+    pub best: Option<MoveCode>,
+    /// The search that stored the entry, see [`TranspositionTable::new_search`].
+    age: u8,
+}
+
+impl Entry {
+    // the entry is packed into 64 bits, and zero is an empty slot:
+    //
+    //   0..31   best move, see `MoveCode`
+    //  31..47   score
+    //  47..55   depth
+    //  55..57   bound, never zero
+    //  57..63   age
+    //
+    fn pack(&self) -> u64 {
+        let best = self.best.map_or(0, |code| code.0 as u64);
+        let bound = match self.bound {
+            Bound::Exact => 1,
+            Bound::Lower => 2,
+            Bound::Upper => 3,
+        };
+
+        best | (self.score as i16 as u16 as u64) << 31
+            | (self.depth as u64) << 47
+            | bound << 55
+            | (self.age as u64 & AGE_MASK) << 57
+    }
+
+    fn unpack(key: u64, data: u64) -> Option<Entry> {
+        let bound = match data >> 55 & 3 {
+            1 => Bound::Exact,
+            2 => Bound::Lower,
+            3 => Bound::Upper,
+            _ => return None,
+        };
+        let best = data & ((1 << MoveCode::BITS) - 1);
+
+        Some(Entry {
+            key,
+            depth: (data >> 47) as u8,
+            score: (data >> 31) as u16 as i16 as i32,
+            bound,
+            best: (best != 0).then_some(MoveCode(best as u32)),
+            age: (data >> 57 & AGE_MASK) as u8,
+        })
+    }
+}
+
+/// Ages wrap around in the bits an entry has for them.
+///
+const AGE_MASK: u64 = 0x3f;
+
+/// A slot holds the entry's key xor its data next to the data, so an
+/// entry torn by two threads writing at once no longer matches its key
+/// and is ignored rather than believed.
+///
+#[derive(Default)]
+struct Slot {
+    check: AtomicU64,
+    data: AtomicU64,
+}
+
+/// A fixed-size cache of search results keyed on position hashes, so a
+/// position reached by another move order isn't searched twice. Each
+/// key has one slot, and newer or deeper results push out older ones.
+///
+/// The table takes no locks, so any number of search threads can share
+/// it.
+///
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+    age: AtomicU8,
+}
+
+impl TranspositionTable {
+    /// A table taking up about `megabytes` of memory.
+    ///
+    pub fn new(megabytes: usize) -> Self {
+        let wanted = (megabytes * 1024 * 1024 / std::mem::size_of::<Slot>()).max(1);
+
+        // a power of two, so a key finds its slot with a mask
+        //
+        let size = match wanted.is_power_of_two() {
+            true => wanted,
+            false => wanted.next_power_of_two() / 2,
+        };
+
+        Self {
+            slots: (0..size).map(|_| Slot::default()).collect(),
+            age: AtomicU8::new(0),
+        }
+    }
+
+    /// The number of slots.
+    ///
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot.check.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+        self.age.store(0, Ordering::Relaxed);
+    }
+
+    /// Mark the start of a new search, so entries left by earlier ones
+    /// give way to new results whatever their depth.
+    ///
+    pub fn new_search(&self) {
+        self.age.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The entry for the position with hash `key`, if there is one.
+    ///
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        let slot = &self.slots[self.slot(key)];
+        let data = slot.data.load(Ordering::Relaxed);
+
+        if slot.check.load(Ordering::Relaxed) ^ data != key {
+            return None;
+        }
+
+        Entry::unpack(key, data)
+    }
+
+    /// Store the result of searching the position with hash `key` to
+    /// `depth`, unless its slot holds a deeper result from this search.
+    ///
+    pub fn store(&self, key: u64, depth: u8, score: i32, bound: Bound, best: Option<MoveCode>) {
+        let age = self.age();
+        let slot = &self.slots[self.slot(key)];
+
+        let data = slot.data.load(Ordering::Relaxed);
+        let old_key = slot.check.load(Ordering::Relaxed) ^ data;
+        let old = Entry::unpack(old_key, data);
+
+        if let Some(old) = old {
+            if old.key != key && old.age == age && old.depth > depth {
+                return;
+            }
+        }
+
+        // a shallow fail low has no best move, but an earlier search of
+        // the same position may have found one
+        //
+        let best = best.or(old.filter(|old| old.key == key).and_then(|old| old.best));
+
+        let data = Entry {
+            key,
+            depth,
+            score,
+            bound,
+            best,
+            age,
+        }
+        .pack();
+
+        slot.check.store(key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+
+    /// How full the table is, in thousandths, judged from a sample.
+    ///
+    pub fn hashfull(&self) -> usize {
+        let age = self.age();
+        let sample = &self.slots[..self.slots.len().min(1000)];
+        let used = sample
+            .iter()
+            .filter_map(|slot| Entry::unpack(0, slot.data.load(Ordering::Relaxed)))
+            .filter(|entry| entry.age == age)
+            .count();
+
+        used * 1000 / sample.len()
+    }
+
+    fn age(&self) -> u8 {
+        (self.age.load(Ordering::Relaxed) as u64 & AGE_MASK) as u8
+    }
+
+    fn slot(&self, key: u64) -> usize {
+        key as usize & (self.slots.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::PieceType;
+
+    fn code(from: (i16, i16), to: (i16, i16), result: MoveResult) -> MoveCode {
+        let m = Move {
+            pos: Vec2::new(to.0, to.1),
+            result,
+        };
+        MoveCode::new(Vec2::new(from.0, from.1), &m)
+    }
+
+    #[test]
+    fn entries_pack_and_unpack() {
+        let entries = [
+            Entry {
+                key: 1,
+                depth: 255,
+                score: -29_990,
+                bound: Bound::Upper,
+                best: None,
+                age: 63,
+            },
+            Entry {
+                key: 2,
+                depth: 7,
+                score: 412,
+                bound: Bound::Exact,
+                best: Some(code(
+                    (4, 1),
+                    (4, 0),
+                    MoveResult::Promotion(PieceType::Knight),
+                )),
+                age: 0,
+            },
+            Entry {
+                key: 3,
+                depth: 0,
+                score: 0,
+                bound: Bound::Lower,
+                best: Some(code((0, 0), (9, 7), MoveResult::Castle(Vec2::new(9, 7)))),
+                age: 17,
+            },
+        ];
+
+        for entry in entries {
+            let data = entry.pack();
+            assert_ne!(data, 0);
+
+            let unpacked = Entry::unpack(entry.key, data).unwrap();
+            assert_eq!(unpacked.depth, entry.depth);
+            assert_eq!(unpacked.score, entry.score);
+            assert_eq!(unpacked.bound, entry.bound);
+            assert_eq!(unpacked.best, entry.best);
+            assert_eq!(unpacked.age, entry.age);
+        }
+
+        // an empty slot
+        //
+        assert!(Entry::unpack(0, 0).is_none());
+    }
+
+    #[test]
+    fn torn_entries_are_ignored() {
+        let tt = TranspositionTable::new(1);
+        let key = 0x1234_5678_9abc_def0;
+
+        tt.store(key, 4, 100, Bound::Exact, None);
+        assert_eq!(tt.probe(key).unwrap().score, 100);
+
+        // another key for the same slot
+        //
+        assert!(tt.probe(key ^ 1 << 63).is_none());
+
+        // the data of one write with the check of another
+        //
+        let slot = &tt.slots[tt.slot(key)];
+        slot.data.fetch_xor(1 << 40, Ordering::Relaxed);
+        assert!(tt.probe(key).is_none());
+    }
+
+    #[test]
+    fn deeper_and_newer_entries_stay() {
+        // every key shares the one slot
+        //
+        let tt = TranspositionTable::new(0);
+        assert_eq!(tt.capacity(), 1);
+
+        let best = code((4, 6), (4, 4), MoveResult::Nothing);
+
+        tt.store(1, 8, 10, Bound::Exact, Some(best));
+        tt.store(2, 3, 20, Bound::Exact, None);
+        assert!(tt.probe(1).is_some_and(|e| e.score == 10));
+        assert!(tt.probe(2).is_none());
+
+        // the same position always takes the newest result, keeping its
+        // best move when the new one has none
+        //
+        tt.store(1, 2, 30, Bound::Upper, None);
+        let entry = tt.probe(1).unwrap();
+        assert_eq!((entry.depth, entry.score, entry.best), (2, 30, Some(best)));
+
+        tt.store(1, 8, 10, Bound::Exact, Some(best));
+
+        // anything from an earlier search gives way
+        //
+        tt.new_search();
+        tt.store(2, 1, 20, Bound::Lower, None);
+        assert!(tt.probe(1).is_none());
+        assert!(tt.probe(2).is_some_and(|e| e.score == 20));
+    }
+}
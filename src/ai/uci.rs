@@ -0,0 +1,356 @@
+use std::{
+    io::{self, BufRead},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::core::{notation, variant::Variant, Board, Chess, Move, MoveResult, Player, Vec2};
+
+use super::{syzygy::Tablebase, Limits, Search, SearchInfo};
+
+/// The transposition table size to start with, in megabytes.
+///
+const DEFAULT_HASH: usize = 16;
+
+const MAX_HASH: usize = 4096;
+
+const MAX_THREADS: usize = 256;
+
+const MAX_LINES: usize = 64;
+
+/// The share of the remaining clock time spent on a move, when the
+/// number of moves to the next time control isn't given.
+///
+const CLOCK_SHARE: u32 = 30;
+
+/// Time kept back from the clock for sending the move.
+///
+const OVERHEAD: Duration = Duration::from_millis(50);
+
+/// Speak the Universal Chess Interface on standard input and output, so
+/// a GUI can play the search. Positions are set up in the variant of
+/// `game`, and its tablebases are used if it has any.
+///
+pub fn run(game: &Chess) -> io::Result<()> {
+    let mut uci = Uci::new(game);
+
+    for line in io::stdin().lock().lines() {
+        if !uci.execute(&line?) {
+            break;
+        }
+    }
+
+    uci.stop();
+
+    Ok(())
+}
+
+struct Uci {
+    variant: Arc<dyn Variant>,
+    /// The position the GUI set up.
+    game: Chess,
+    /// The searcher, while it isn't off thinking in another thread.
+    search: Option<Search>,
+    thinking: Option<JoinHandle<Search>>,
+    stop: Arc<AtomicBool>,
+    /// Set by the `stop` command, which an infinite search waits for
+    /// before answering.
+    stopped: Arc<AtomicBool>,
+}
+
+impl Uci {
+    fn new(game: &Chess) -> Self {
+        let variant = game.board.variant.clone();
+
+        let mut search = Search::new(DEFAULT_HASH);
+        search.tablebase = game.tablebase();
+
+        Self {
+            game: Chess::with_variant(variant.clone()),
+            variant,
+            stop: search.stop_flag(),
+            stopped: Arc::new(AtomicBool::new(false)),
+            search: Some(search),
+            thinking: None,
+        }
+    }
+
+    /// Carry out one command from the GUI, returning false on `quit`.
+    ///
+    fn execute(&mut self, line: &str) -> bool {
+        let mut words = line.split_whitespace();
+
+        match words.next() {
+            Some("uci") => {
+                println!(
+                    "id name {} {}",
+                    env!("CARGO_PKG_NAME"),
+                    env!("CARGO_PKG_VERSION")
+                );
+                println!("id author {}", env!("CARGO_PKG_NAME"));
+                println!(
+                    "option name Threads type spin default 1 min 1 max {}",
+                    MAX_THREADS
+                );
+                println!(
+                    "option name Hash type spin default {} min 1 max {}",
+                    DEFAULT_HASH, MAX_HASH
+                );
+                println!(
+                    "option name MultiPV type spin default 1 min 1 max {}",
+                    MAX_LINES
+                );
+                println!("option name SyzygyPath type string default <empty>");
+                println!("uciok");
+            }
+            Some("isready") => println!("readyok"),
+            Some("setoption") => {
+                if let Err(msg) = self.set_option(words) {
+                    println!("info string {}", msg);
+                }
+            }
+            Some("ucinewgame") => {
+                self.stop();
+                if let Some(search) = &mut self.search {
+                    search.clear();
+                }
+                self.game = Chess::with_variant(self.variant.clone());
+            }
+            Some("position") => {
+                self.stop();
+                if let Err(msg) = self.position(words) {
+                    println!("info string {}", msg);
+                }
+            }
+            Some("go") => {
+                self.stop();
+                self.go(words);
+            }
+            Some("stop") => self.stop(),
+            Some("quit") => return false,
+            _ => {}
+        }
+
+        true
+    }
+
+    /// `setoption name <name> value <value>`, where the name and value
+    /// may be more than one word.
+    ///
+    fn set_option<'a>(&mut self, words: impl Iterator<Item = &'a str>) -> Result<(), String> {
+        let text = words.collect::<Vec<_>>().join(" ");
+        let text = text
+            .strip_prefix("name ")
+            .ok_or("setoption expects a name")?;
+        let (name, value) = match text.split_once(" value ") {
+            Some((name, value)) => (name.trim(), value.trim()),
+            None => (text.trim(), ""),
+        };
+
+        self.stop();
+        let Some(search) = &mut self.search else {
+            return Ok(());
+        };
+
+        let number = |max: usize| match value.parse::<usize>() {
+            Ok(n) if (1..=max).contains(&n) => Ok(n),
+            _ => Err(format!("{} expects a number from 1 to {}", name, max)),
+        };
+
+        match name.to_ascii_lowercase().as_str() {
+            "threads" => search.set_threads(number(MAX_THREADS)?),
+            "multipv" => search.set_lines(number(MAX_LINES)?),
+            "hash" => search.set_hash(number(MAX_HASH)?),
+            "syzygypath" => {
+                let tablebase = match value {
+                    "" | "<empty>" => None,
+                    dir => Some(Arc::new(Tablebase::open(dir).map_err(|err| {
+                        format!("unable to load tablebases from '{}': {}", dir, err)
+                    })?)),
+                };
+                search.tablebase = tablebase;
+            }
+            _ => return Err(format!("no option '{}'", name)),
+        }
+
+        Ok(())
+    }
+
+    /// `position startpos|fen <fen> [moves <move>...]`
+    ///
+    fn position<'a>(&mut self, mut words: impl Iterator<Item = &'a str>) -> Result<(), String> {
+        let mut game = match words.next() {
+            Some("startpos") => Chess::with_variant(self.variant.clone()),
+            Some("fen") => {
+                let fen: Vec<_> = words.by_ref().take_while(|w| *w != "moves").collect();
+                Chess::from_fen_variant(&fen.join(" "), self.variant.clone())
+                    .map_err(|err| format!("unable to load FEN: {}", err))?
+            }
+            _ => return Err("position expects startpos or fen".into()),
+        };
+
+        // after `fen` the `moves` keyword has already been taken
+        //
+        for text in words.filter(|w| *w != "moves") {
+            if !game.play_notation(text) {
+                return Err(format!("illegal move '{}'", text));
+            }
+        }
+
+        self.game = game;
+
+        Ok(())
+    }
+
+    /// `go` with any of `depth`, `nodes`, `movetime`, `wtime`, `btime`,
+    /// `winc`, `binc`, `movestogo` and `infinite`. The search runs in
+    /// the background, reporting each depth, and ends with `bestmove`.
+    ///
+    fn go<'a>(&mut self, mut words: impl Iterator<Item = &'a str>) {
+        let Some(mut search) = self.search.take() else {
+            return;
+        };
+
+        let mut limits = Limits::default();
+        let mut infinite = false;
+        let (mut clock, mut increment, mut moves_to_go) = (None, Duration::ZERO, CLOCK_SHARE);
+
+        let ours = match self.game.turn {
+            Player::White => ("wtime", "winc"),
+            Player::Black => ("btime", "binc"),
+        };
+
+        while let Some(word) = words.next() {
+            let mut value = || words.next().and_then(|v| v.parse::<u64>().ok());
+
+            match word {
+                "infinite" => infinite = true,
+                "depth" => limits.depth = value().map(|d| d as u32),
+                "nodes" => limits.nodes = value(),
+                "movetime" => limits.time = value().map(Duration::from_millis),
+                "movestogo" => moves_to_go = value().map_or(CLOCK_SHARE, |n| n.max(1) as u32),
+                w if w == ours.0 => clock = value().map(Duration::from_millis),
+                w if w == ours.1 => {
+                    increment = value().map_or(Duration::ZERO, Duration::from_millis)
+                }
+                _ => {}
+            }
+        }
+
+        if let (None, Some(clock)) = (limits.time, clock) {
+            let budget = clock / moves_to_go + increment / 2;
+            limits.time = Some(
+                budget
+                    .min(clock.saturating_sub(OVERHEAD))
+                    .max(Duration::from_millis(1)),
+            );
+        }
+
+        let board = self.game.board.clone();
+        let turn = self.game.turn;
+        let stopped = self.stopped.clone();
+        stopped.store(false, Ordering::Relaxed);
+        let tt = search.tt.clone();
+
+        self.thinking = Some(thread::spawn(move || {
+            let result = search.run(&board, turn, limits, |info| {
+                for line in info_lines(&board, info, tt.hashfull()) {
+                    println!("{}", line);
+                }
+            });
+
+            // an infinite search may only answer once told to stop
+            //
+            while infinite && !stopped.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(5));
+            }
+
+            match result.and_then(|info| info.best()) {
+                Some((from, m)) => println!("bestmove {}", uci_move(&board, from, &m)),
+                None => println!("bestmove 0000"),
+            }
+
+            search
+        }));
+    }
+
+    /// Stop the search in progress, if there is one, and wait for it to
+    /// send its move.
+    ///
+    fn stop(&mut self) {
+        let Some(thinking) = self.thinking.take() else {
+            return;
+        };
+
+        self.stop.store(true, Ordering::Relaxed);
+        self.stopped.store(true, Ordering::Relaxed);
+        if let Ok(search) = thinking.join() {
+            self.search = Some(search);
+        }
+
+        // a stop that came after the search was over would end the next one
+        //
+        self.stop.store(false, Ordering::Relaxed);
+    }
+}
+
+/// The `info` lines for a depth completed, one for each line found.
+///
+fn info_lines(board: &Board, info: &SearchInfo, hashfull: usize) -> Vec<String> {
+    info.lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let score = match line.mate() {
+                Some(mate) => format!("mate {}", mate),
+                None => format!("cp {}", line.score),
+            };
+
+            let mut board = board.clone();
+            let mut pv = Vec::new();
+            for (from, m) in &line.pv {
+                pv.push(uci_move(&board, *from, m));
+                board.make_move(*from, m);
+            }
+
+            format!(
+                "info depth {} multipv {} score {} nodes {} nps {} time {} hashfull {} pv {}",
+                info.depth,
+                i + 1,
+                score,
+                info.nodes,
+                info.nps(),
+                info.time.as_millis(),
+                hashfull,
+                pv.join(" ")
+            )
+        })
+        .collect()
+}
+
+/// A move in the coordinate notation UCI uses, e.g. `e2e4`, `e7e8q` or
+/// `N@f3` for a drop. Castling is the king's move, or the king taking
+/// its rook in Chess960.
+///
+fn uci_move(board: &Board, from: Vec2, m: &Move) -> String {
+    match m.result {
+        MoveResult::Drop(_, ty) => {
+            format!("{}@{}", ty.letter(), notation::square_name(board, m.pos))
+        }
+        MoveResult::Promotion(ty) => format!(
+            "{}{}{}",
+            notation::square_name(board, from),
+            notation::square_name(board, m.pos),
+            ty.letter().to_ascii_lowercase()
+        ),
+        _ => format!(
+            "{}{}",
+            notation::square_name(board, from),
+            notation::square_name(board, m.pos)
+        ),
+    }
+}
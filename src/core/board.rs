@@ -1,4 +1,31 @@
-use super::{piece::Piece, vec::Vec2, Move, MoveResult, PieceType, Player};
+use std::sync::Arc;
+
+use super::{
+    piece::Piece,
+    variant::{Standard, Variant},
+    vec::Vec2,
+    Move, MoveResult, PieceType, Player,
+};
+
+/// The pieces a pawn may promote to, in order of preference.
+///
+pub const PROMOTIONS: [PieceType; 4] = [
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+];
+
+/// The pieces that may be held in a pocket and dropped, in the
+/// order they are shown.
+///
+pub const DROPS: [PieceType; 5] = [
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Pawn,
+];
 
 pub enum QueryResult<'a> {
     Occupied(&'a Piece),
@@ -6,61 +33,169 @@ pub enum QueryResult<'a> {
     Invalid,
 }
 
+#[derive(Clone)]
 pub struct Board {
-    squares: [[Option<Piece>; 8]; 8],
+    /// The squares rank by rank from the top of the board.
+    squares: Vec<Option<Piece>>,
+    width: i16,
+    height: i16,
+    /// The square skipped over by a pawn's double step on the last
+    /// move, which an opposing pawn may capture en passant.
+    pub en_passant: Option<Vec2>,
+    /// Plies since the last capture or pawn move.
+    pub halfmove_clock: u32,
+    /// Castling moves target the rook rather than the king's
+    /// destination, as needed by Chess960 start positions.
+    pub chess960: bool,
+    /// The rules being played.
+    pub variant: Arc<dyn Variant>,
+    /// The number of checks each player has given, for three-check.
+    pub checks: [u32; 2],
+    /// The number of each piece type held by each player, indexed by
+    /// player and then by the piece's place in [`DROPS`], for Crazyhouse.
+    pockets: [[u32; 5]; 2],
 }
 
 impl Board {
+    pub fn empty() -> Self {
+        Board::with_size(8, 8)
+    }
+
+    /// An empty board `width` files wide and `height` ranks high.
+    ///
+    pub fn with_size(width: i16, height: i16) -> Self {
+        Self {
+            squares: vec![None; (width * height) as usize],
+            width,
+            height,
+            en_passant: None,
+            halfmove_clock: 0,
+            chess960: false,
+            variant: Arc::new(Standard),
+            checks: [0; 2],
+            pockets: [[0; 5]; 2],
+        }
+    }
+
     pub fn from_string(data: &str) -> Self {
         let mut iter = data.chars();
 
-        let mut squares: [[Option<Piece>; 8]; 8] = Default::default();
-
-        for y in 0..8 {
-            for x in 0..8 {
-                let c = iter.next().expect("insufficient data to setup board");
-                if c == '.' {
-                    continue;
-                }
+        let mut board = Board::empty();
 
-                squares[y][x] = Some(Piece {
-                    ty: get_char_type(c),
-                    player: get_char_player(c),
-                    move_count: 0,
-                });
+        for square in board.squares.iter_mut() {
+            let c = iter.next().expect("insufficient data to setup board");
+            if c == '.' {
+                continue;
             }
+
+            *square = Some(Piece {
+                ty: get_char_type(c),
+                player: get_char_player(c),
+                move_count: 0,
+                promoted: false,
+            });
         }
 
-        Self { squares }
+        board
+    }
+
+    /// The number of files.
+    ///
+    pub fn width(&self) -> i16 {
+        self.width
+    }
+
+    /// The number of ranks.
+    ///
+    pub fn height(&self) -> i16 {
+        self.height
+    }
+
+    /// The furthest a piece can slide in one move.
+    ///
+    fn range(&self) -> usize {
+        self.width.max(self.height) as usize
+    }
+
+    fn index(&self, pos: Vec2) -> usize {
+        (pos.y * self.width + pos.x) as usize
+    }
+
+    /// The rank the player's pieces start on.
+    ///
+    pub fn home_rank(&self, player: Player) -> i16 {
+        match player {
+            Player::White => self.height - 1,
+            Player::Black => 0,
+        }
+    }
+
+    /// The files the king and rook end up on when castling: beside
+    /// each other on the second and third files in from the edge.
+    ///
+    pub fn castling_files(&self, king: Vec2, rook: Vec2) -> (i16, i16) {
+        if rook.x > king.x {
+            (self.width - 2, self.width - 3)
+        } else {
+            (2, 3)
+        }
+    }
+
+    /// The Chess960 start position numbered `index` (0-959) in the
+    /// standard numbering scheme, where 518 is the classical setup.
+    ///
+    pub fn chess960(index: usize) -> Self {
+        let rank = chess960_rank(index % 960);
+
+        let black: String = rank.iter().map(|ty| ty.letter()).collect();
+        let white = black.to_lowercase();
+
+        let data = format!("{}PPPPPPPP{}pppppppp{}", black, ".".repeat(32), white);
+
+        let mut board = Board::from_string(&data);
+        board.chess960 = true;
+        board
     }
 
     pub fn get(&self, pos: Vec2) -> Option<&Piece> {
-        if pos.x < 0 || pos.x > 7 || pos.y < 0 || pos.y > 7 {
+        if !self.contains(pos) {
             return None;
         }
-        self.squares[pos.y as usize][pos.x as usize].as_ref()
+        self.squares[self.index(pos)].as_ref()
     }
 
-    pub fn query_square(&self, pos: Vec2) -> QueryResult {
+    pub fn query_square(&self, pos: Vec2) -> QueryResult<'_> {
         // is the query on the board?
         //
-        if pos.x < 0 || pos.x > 7 || pos.y < 0 || pos.y > 7 {
+        if !self.contains(pos) {
             return QueryResult::Invalid;
         }
 
         // what is the state of the board square?
         //
-        match &self.squares[pos.y as usize][pos.x as usize] {
+        match &self.squares[self.index(pos)] {
             Some(piece) => QueryResult::Occupied(piece),
             None => QueryResult::Vacant,
         }
     }
 
     pub fn get_mut(&mut self, pos: Vec2) -> &mut Option<Piece> {
-        &mut self.squares[pos.y as usize][pos.x as usize]
+        let index = self.index(pos);
+        &mut self.squares[index]
     }
 
     pub fn get_valid_moves(&self, pos: Vec2, inc_cancel: bool) -> Vec<Move> {
+        let mut valid = self.piece_moves(pos, inc_cancel);
+
+        self.variant.valid_moves(self, pos, &mut valid);
+
+        valid
+    }
+
+    /// The moves of the piece at `pos` by the standard rules, before
+    /// the variant has had a chance to change them.
+    ///
+    pub fn piece_moves(&self, pos: Vec2, inc_cancel: bool) -> Vec<Move> {
         let piece = self.get(pos).expect("Peice expected at position");
 
         let mut valid: Vec<Move> = Default::default();
@@ -76,109 +211,468 @@ impl Board {
             PieceType::Knight => valid_knight_moves(self, pos, piece, &mut valid),
             PieceType::Rook => valid_rook_moves(self, pos, piece, &mut valid),
             PieceType::Pawn => valid_pawn_moves(self, pos, piece, &mut valid),
+            PieceType::Fairy(fairy) => {
+                for m in fairy.moves {
+                    let limit = m.limit.min(self.range());
+                    valid_linear_moves(self, piece.player, pos, m.steps, limit, &mut valid);
+                }
+            }
         }
 
         valid
     }
 
+    /// Whether the move `m` captures a piece.
+    ///
+    pub fn is_capture(&self, m: &Move) -> bool {
+        match m.result {
+            MoveResult::Capture(_) => true,
+            MoveResult::Promotion(_) => self.get(m.pos).is_some(),
+            _ => false,
+        }
+    }
+
+    /// Whether any piece of `player`'s can capture, by the standard rules.
+    ///
+    pub fn has_capture(&self, player: Player) -> bool {
+        self.pieces(player).any(|(pos, _)| {
+            self.piece_moves(pos, false)
+                .iter()
+                .any(|m| self.is_capture(m))
+        })
+    }
+
+    /// The valid moves for the piece at `pos` that do not leave
+    /// its own king in check.
+    ///
+    pub fn get_legal_moves(&self, pos: Vec2, inc_cancel: bool) -> Vec<Move> {
+        let player = self.get(pos).expect("Peice expected at position").player;
+
+        self.get_valid_moves(pos, inc_cancel)
+            .into_iter()
+            .filter(|m| {
+                if let MoveResult::Cancel = m.result {
+                    return true;
+                }
+                let mut after = self.clone();
+                after.make_move(pos, m);
+                self.variant.is_legal(&after, player)
+            })
+            .collect()
+    }
+
     pub fn move_piece(&mut self, from: Vec2, to: Vec2) {
         if let Some(piece) = self.get_mut(from).take() {
             self.get_mut(to).insert(piece).move_count += 1;
         }
     }
 
-    pub fn take_piece(&mut self, pos: Vec2) {
-        self.get_mut(pos).take();
+    pub fn take_piece(&mut self, pos: Vec2) -> Option<Piece> {
+        self.get_mut(pos).take()
+    }
+
+    /// The pieces `player` holds in their pocket, with how many of each.
+    ///
+    pub fn pocket(&self, player: Player) -> impl Iterator<Item = (PieceType, u32)> + '_ {
+        DROPS
+            .into_iter()
+            .zip(self.pockets[player as usize])
+            .filter(|(_, count)| *count > 0)
+    }
+
+    /// Put a piece in `player`'s pocket. Only the pieces in [`DROPS`]
+    /// can be held, anything else is lost.
+    ///
+    pub fn add_to_pocket(&mut self, player: Player, ty: PieceType) {
+        if let Some(slot) = DROPS.iter().position(|t| *t == ty) {
+            self.pockets[player as usize][slot] += 1;
+        }
+    }
+
+    /// Take a piece out of `player`'s pocket, if they hold one.
+    ///
+    fn take_from_pocket(&mut self, player: Player, ty: PieceType) -> bool {
+        let slot = DROPS.iter().position(|t| *t == ty);
+
+        match slot.map(|slot| &mut self.pockets[player as usize][slot]) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The legal drops of a `ty` from `player`'s pocket.
+    ///
+    pub fn drop_moves(&self, player: Player, ty: PieceType) -> Vec<Move> {
+        if !self.pocket(player).any(|(t, _)| t == ty) {
+            return Vec::new();
+        }
+
+        let mut moves = Vec::new();
+
+        for y in 0..self.height {
+            // pawns may not be dropped on the first or last rank
+            //
+            if ty == PieceType::Pawn && (y == 0 || y == self.height - 1) {
+                continue;
+            }
+
+            for x in 0..self.width {
+                let pos = Vec2::new(x, y);
+
+                if self.get(pos).is_some() {
+                    continue;
+                }
+
+                let m = Move::drop(pos, player, ty);
+                let mut after = self.clone();
+                after.make_move(pos, &m);
+
+                if self.variant.is_legal(&after, player) {
+                    moves.push(m);
+                }
+            }
+        }
+
+        moves
     }
 
-    pub fn is_vacant(&self, pos: Vec2) -> Result<bool, ()> {
-        if pos.x < 0 || pos.x > 7 || pos.y < 0 || pos.y > 7 {
-            return Err(());
+    /// Apply the move of the piece at `from`, returning any piece that
+    /// was captured.
+    ///
+    pub fn make_move(&mut self, from: Vec2, m: &Move) -> Option<Piece> {
+        let mut taken = None;
+
+        let (player, pawn) = match (self.get(from), m.result) {
+            (_, MoveResult::Drop(player, ty)) => (player, ty == PieceType::Pawn),
+            (Some(p), _) => (p.player, p.ty == PieceType::Pawn),
+            (None, _) => return None,
+        };
+
+        self.en_passant = None;
+
+        match m.result {
+            MoveResult::Nothing => {
+                self.move_piece(from, m.pos);
+
+                if pawn && (m.pos.y - from.y).abs() == 2 {
+                    self.en_passant = Some(Vec2::new(from.x, (from.y + m.pos.y) / 2));
+                }
+            }
+            MoveResult::Capture(pos) => {
+                taken = self.take_piece(pos);
+                self.move_piece(from, m.pos);
+            }
+            MoveResult::Castle(rook) => {
+                let (king_x, rook_x) = self.castling_files(from, rook);
+
+                let king = self.take_piece(from);
+                let rook = self.take_piece(rook);
+
+                for (x, piece) in [(king_x, king), (rook_x, rook)] {
+                    if let Some(mut piece) = piece {
+                        piece.move_count += 1;
+                        *self.get_mut(Vec2::new(x, from.y)) = Some(piece);
+                    }
+                }
+            }
+            MoveResult::Promotion(ty) => {
+                taken = self.take_piece(m.pos);
+                self.move_piece(from, m.pos);
+                if let Some(piece) = self.get_mut(m.pos) {
+                    piece.ty = ty;
+                    piece.promoted = true;
+                }
+            }
+            MoveResult::Drop(player, ty) => {
+                if self.get(m.pos).is_some() || !self.take_from_pocket(player, ty) {
+                    return None;
+                }
+
+                // a dropped pawn may only double step from its
+                // starting rank, and a dropped rook never castles.
+                //
+                let start = self.home_rank(player) + pawn_direction(player).y;
+                let move_count = if ty == PieceType::Pawn && m.pos.y == start {
+                    0
+                } else {
+                    1
+                };
+
+                *self.get_mut(m.pos) = Some(Piece {
+                    ty,
+                    player,
+                    move_count,
+                    promoted: false,
+                });
+            }
+            MoveResult::Cancel | MoveResult::Invalid => return None,
+        }
+
+        if pawn || taken.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
         }
-        Ok(self.get(pos).is_none())
+
+        let variant = self.variant.clone();
+        variant.after_move(self, player, from, m, taken.as_ref());
+
+        taken
     }
 
-    pub fn is_opponent(&self, pos: Vec2, player: Player) -> Result<bool, ()> {
-        if pos.x < 0 || pos.x > 7 || pos.y < 0 || pos.y > 7 {
-            return Err(());
+    pub fn contains(&self, pos: Vec2) -> bool {
+        pos.x >= 0 && pos.x < self.width && pos.y >= 0 && pos.y < self.height
+    }
+
+    /// Iterate over the positions of every piece owned by `player`.
+    ///
+    pub fn pieces(&self, player: Player) -> impl Iterator<Item = (Vec2, &Piece)> + '_ {
+        let width = self.width;
+
+        self.squares
+            .iter()
+            .enumerate()
+            .filter_map(move |(i, square)| match square {
+                Some(p) if p.player == player => {
+                    let i = i as i16;
+                    Some((Vec2::new(i % width, i / width), p))
+                }
+                _ => None,
+            })
+    }
+
+    pub fn find_king(&self, player: Player) -> Option<Vec2> {
+        self.pieces(player)
+            .find(|(_, p)| p.ty == PieceType::King)
+            .map(|(pos, _)| pos)
+    }
+
+    /// The squares attacked by the piece at `pos`, including squares
+    /// occupied by pieces of the same colour (i.e. defended squares).
+    ///
+    pub fn attacks(&self, pos: Vec2) -> Vec<Vec2> {
+        let mut results = Vec::new();
+
+        if let Some(piece) = self.get(pos) {
+            match piece.ty {
+                PieceType::King => {
+                    attacked_linear(self, pos, &Vec2::AXIS, 1, &mut results);
+                    attacked_linear(self, pos, &Vec2::DIAG, 1, &mut results);
+                }
+                PieceType::Queen => {
+                    attacked_linear(self, pos, &Vec2::AXIS, self.range(), &mut results);
+                    attacked_linear(self, pos, &Vec2::DIAG, self.range(), &mut results);
+                }
+                PieceType::Bishop => {
+                    attacked_linear(self, pos, &Vec2::DIAG, self.range(), &mut results)
+                }
+                PieceType::Knight => attacked_linear(self, pos, &Vec2::KNIGHT, 1, &mut results),
+                PieceType::Rook => {
+                    attacked_linear(self, pos, &Vec2::AXIS, self.range(), &mut results)
+                }
+                PieceType::Pawn => {
+                    let dir = pawn_direction(piece.player);
+                    let steps = [dir + Vec2::LEFT, dir + Vec2::RIGHT];
+                    attacked_linear(self, pos, &steps, 1, &mut results);
+                }
+                PieceType::Fairy(fairy) => {
+                    for m in fairy.moves {
+                        let limit = m.limit.min(self.range());
+                        attacked_linear(self, pos, m.steps, limit, &mut results);
+                    }
+                }
+            }
+        }
+
+        results
+    }
+
+    /// Is `pos` attacked by any of the pieces owned by `by`?
+    ///
+    pub fn is_attacked(&self, pos: Vec2, by: Player) -> bool {
+        self.pieces(by)
+            .any(|(from, _)| self.attacks(from).contains(&pos))
+    }
+
+    /// Every legal move for `player`, including drops. A drop is
+    /// paired with its destination in place of an origin square.
+    ///
+    pub fn all_legal_moves(&self, player: Player) -> Vec<(Vec2, Move)> {
+        let drops = self
+            .pocket(player)
+            .flat_map(|(ty, _)| self.drop_moves(player, ty))
+            .map(|m| (m.pos, m));
+
+        self.pieces(player)
+            .flat_map(|(pos, _)| {
+                self.get_legal_moves(pos, false)
+                    .into_iter()
+                    .map(move |m| (pos, m))
+            })
+            .chain(drops)
+            .collect()
+    }
+
+    pub fn has_legal_moves(&self, player: Player) -> bool {
+        self.pieces(player)
+            .any(|(pos, _)| !self.get_legal_moves(pos, false).is_empty())
+            || self
+                .pocket(player)
+                .any(|(ty, _)| !self.drop_moves(player, ty).is_empty())
+    }
+
+    pub fn in_check(&self, player: Player) -> bool {
+        match self.find_king(player) {
+            Some(king) => self.is_attacked(king, player.opponent()),
+            None => false,
+        }
+    }
+
+    pub fn is_vacant(&self, pos: Vec2) -> Option<bool> {
+        if !self.contains(pos) {
+            return None;
+        }
+        Some(self.get(pos).is_none())
+    }
+
+    pub fn is_opponent(&self, pos: Vec2, player: Player) -> Option<bool> {
+        if !self.contains(pos) {
+            return None;
         }
         if let Some(p) = self.get(pos) {
-            return Ok(p.player != player);
+            return Some(p.player != player);
         }
-        Ok(false)
+        Some(false)
     }
 }
 
 fn valid_king_moves(board: &Board, pos: Vec2, piece: &Piece, results: &mut Vec<Move>) {
     valid_linear_moves(board, piece.player, pos, &Vec2::AXIS, 1, results);
+    valid_linear_moves(board, piece.player, pos, &Vec2::DIAG, 1, results);
+    valid_castling_moves(board, pos, piece, results);
+}
+
+/// Castling with any unmoved rook on the king's rank, using the
+/// Chess960 rules which reduce to the classical ones: the king ends on
+/// the c or g file and the rook beside it on the d or f file.
+///
+fn valid_castling_moves(board: &Board, pos: Vec2, piece: &Piece, results: &mut Vec<Move>) {
+    let opponent = piece.player.opponent();
+
+    if piece.move_count != 0 || pos.y != board.home_rank(piece.player) {
+        return;
+    }
+
+    if board.is_attacked(pos, opponent) {
+        return;
+    }
+
+    let rooks = board
+        .pieces(piece.player)
+        .filter(|(p, r)| r.ty == PieceType::Rook && r.move_count == 0 && p.y == pos.y)
+        .map(|(p, _)| p);
+
+    for rook in rooks {
+        let (king_x, rook_x) = board.castling_files(pos, rook);
+
+        // every square the king and rook cross must be empty,
+        // apart from the king and rook themselves.
+        //
+        let lo = pos.x.min(rook.x).min(king_x).min(rook_x);
+        let hi = pos.x.max(rook.x).max(king_x).max(rook_x);
+
+        let blocked = (lo..=hi)
+            .map(|x| Vec2::new(x, pos.y))
+            .any(|sq| sq != pos && sq != rook && board.get(sq).is_some());
+
+        // and the king may not pass through check.
+        //
+        let attacked = (pos.x.min(king_x)..=pos.x.max(king_x))
+            .any(|x| board.is_attacked(Vec2::new(x, pos.y), opponent));
+
+        if blocked || attacked {
+            continue;
+        }
+
+        let target = if board.chess960 {
+            rook
+        } else {
+            Vec2::new(king_x, pos.y)
+        };
+
+        results.push(Move::new(target, MoveResult::Castle(rook)));
+    }
 }
 
 fn valid_queen_moves(board: &Board, pos: Vec2, piece: &Piece, results: &mut Vec<Move>) {
-    valid_linear_moves(board, piece.player, pos, &Vec2::AXIS, 8, results);
-    valid_linear_moves(board, piece.player, pos, &Vec2::DIAG, 8, results);
+    valid_linear_moves(
+        board,
+        piece.player,
+        pos,
+        &Vec2::AXIS,
+        board.range(),
+        results,
+    );
+    valid_linear_moves(
+        board,
+        piece.player,
+        pos,
+        &Vec2::DIAG,
+        board.range(),
+        results,
+    );
 }
 
 fn valid_bishop_moves(board: &Board, pos: Vec2, piece: &Piece, results: &mut Vec<Move>) {
-    valid_linear_moves(board, piece.player, pos, &Vec2::DIAG, 8, results);
+    valid_linear_moves(
+        board,
+        piece.player,
+        pos,
+        &Vec2::DIAG,
+        board.range(),
+        results,
+    );
 }
 
 fn valid_knight_moves(board: &Board, pos: Vec2, piece: &Piece, results: &mut Vec<Move>) {
-    for x in [-1i16, -2, 1, 2] {
-        for y in [-1i16, -2, 1, 2] {
-            if x.unsigned_abs() != y.unsigned_abs() {
-                valid_linear_moves(board, piece.player, pos, &[Vec2::new(x, y)], 1, results);
-            }
-        }
-    }
+    valid_linear_moves(board, piece.player, pos, &Vec2::KNIGHT, 1, results);
 }
 
 fn valid_rook_moves(board: &Board, pos: Vec2, piece: &Piece, results: &mut Vec<Move>) {
-    valid_linear_moves(board, piece.player, pos, &Vec2::AXIS, 8, results);
+    valid_linear_moves(
+        board,
+        piece.player,
+        pos,
+        &Vec2::AXIS,
+        board.range(),
+        results,
+    );
 }
 
 fn valid_pawn_moves(board: &Board, mut pos: Vec2, piece: &Piece, results: &mut Vec<Move>) {
-    let dir = match piece.player {
-        Player::White => Vec2::UP,
-        Player::Black => Vec2::DOWN,
-    };
+    let dir = pawn_direction(piece.player);
 
     // capture...
     //
     let cap = pos + dir + Vec2::RIGHT;
-    if let Ok(true) = board.is_opponent(cap, piece.player) {
-        results.push(Move::capture(cap));
+    if let Some(true) = board.is_opponent(cap, piece.player) {
+        push_pawn_move(board, piece.player, Move::capture(cap), results);
     }
 
     let cap = pos + dir + Vec2::LEFT;
-    if let Ok(true) = board.is_opponent(cap, piece.player) {
-        results.push(Move::capture(cap));
+    if let Some(true) = board.is_opponent(cap, piece.player) {
+        push_pawn_move(board, piece.player, Move::capture(cap), results);
     }
 
     // capture en pass...
     //
-    let cap = pos + Vec2::LEFT;
-    if let Ok(true) = board.is_vacant(cap + dir) {
-        if let Some(Piece {
-            ty: PieceType::Pawn,
-            player,
-            ..
-        }) = board.get(cap)
-        {
-            if *player != piece.player {
-                results.push(Move::new(cap + dir, MoveResult::Capture(cap)));
-            }
-        }
-    }
-
-    let cap = pos + Vec2::RIGHT;
-    if let Ok(true) = board.is_vacant(cap + dir) {
-        if let Some(Piece {
-            ty: PieceType::Pawn,
-            player,
-            ..
-        }) = board.get(cap)
-        {
-            if *player != piece.player {
+    for side in [Vec2::LEFT, Vec2::RIGHT] {
+        let cap = pos + side;
+        if board.en_passant == Some(cap + dir) {
+            if let Some(true) = board.is_opponent(cap, piece.player) {
                 results.push(Move::new(cap + dir, MoveResult::Capture(cap)));
             }
         }
@@ -187,18 +681,33 @@ fn valid_pawn_moves(board: &Board, mut pos: Vec2, piece: &Piece, results: &mut V
     // move...
     //
     pos = pos + dir;
-    if let Ok(true) = board.is_vacant(pos) {
-        results.push(Move::to(pos));
+    if let Some(true) = board.is_vacant(pos) {
+        push_pawn_move(board, piece.player, Move::to(pos), results);
+    } else {
+        return;
     }
 
     if piece.move_count == 0 {
         pos = pos + dir;
-        if let Ok(true) = board.is_vacant(pos) {
+        if let Some(true) = board.is_vacant(pos) {
             results.push(Move::to(pos));
         }
     }
 }
 
+/// Add a pawn move, replacing it with the promotions
+/// when the pawn reaches the far side of the board.
+///
+fn push_pawn_move(board: &Board, player: Player, m: Move, results: &mut Vec<Move>) {
+    if m.pos.y == board.home_rank(player.opponent()) {
+        for ty in PROMOTIONS {
+            results.push(Move::new(m.pos, MoveResult::Promotion(ty)));
+        }
+    } else {
+        results.push(m);
+    }
+}
+
 fn valid_linear_moves(
     board: &Board,
     player: Player,
@@ -231,6 +740,95 @@ fn valid_linear_moves(
     }
 }
 
+fn attacked_linear(
+    board: &Board,
+    pos: Vec2,
+    steps: &[Vec2],
+    limit: usize,
+    results: &mut Vec<Vec2>,
+) {
+    for step in steps {
+        let mut test = pos;
+
+        for _ in 0..limit {
+            test = test + *step;
+
+            match board.query_square(test) {
+                QueryResult::Occupied(_) => {
+                    results.push(test);
+                    break;
+                }
+                QueryResult::Vacant => results.push(test),
+                QueryResult::Invalid => break,
+            }
+        }
+    }
+}
+
+pub(crate) fn pawn_direction(player: Player) -> Vec2 {
+    match player {
+        Player::White => Vec2::UP,
+        Player::Black => Vec2::DOWN,
+    }
+}
+
+/// The back rank of Chess960 position `index`, from the a to the h file.
+///
+fn chess960_rank(index: usize) -> [PieceType; 8] {
+    const KNIGHTS: [(usize, usize); 10] = [
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (0, 4),
+        (1, 2),
+        (1, 3),
+        (1, 4),
+        (2, 3),
+        (2, 4),
+        (3, 4),
+    ];
+
+    let mut rank: [Option<PieceType>; 8] = [None; 8];
+
+    // the bishops go on opposite colours, then the queen and knights
+    // fill the free squares, leaving the king between the rooks.
+    //
+    let (n, light) = (index / 4, index % 4);
+    rank[light * 2 + 1] = Some(PieceType::Bishop);
+
+    let (n, dark) = (n / 4, n % 4);
+    rank[dark * 2] = Some(PieceType::Bishop);
+
+    let (n, queen) = (n / 6, n % 6);
+    let (k1, k2) = KNIGHTS[n];
+
+    let free = |rank: &[Option<PieceType>; 8], i: usize| {
+        rank.iter()
+            .enumerate()
+            .filter(|(_, sq)| sq.is_none())
+            .nth(i)
+            .map(|(x, _)| x)
+            .unwrap()
+    };
+
+    let x = free(&rank, queen);
+    rank[x] = Some(PieceType::Queen);
+
+    // place the second knight first so the index of the first is unchanged
+    //
+    let x = free(&rank, k2);
+    rank[x] = Some(PieceType::Knight);
+    let x = free(&rank, k1);
+    rank[x] = Some(PieceType::Knight);
+
+    for ty in [PieceType::Rook, PieceType::King, PieceType::Rook] {
+        let x = free(&rank, 0);
+        rank[x] = Some(ty);
+    }
+
+    rank.map(|ty| ty.unwrap())
+}
+
 fn get_char_player(c: char) -> Player {
     if c.is_lowercase() {
         Player::White
@@ -240,14 +838,104 @@ fn get_char_player(c: char) -> Player {
 }
 
 fn get_char_type(c: char) -> PieceType {
-    let lc = c.to_lowercase().next().unwrap();
-    match lc {
-        'k' => PieceType::King,
-        'q' => PieceType::Queen,
-        'b' => PieceType::Bishop,
-        'n' => PieceType::Knight,
-        'r' => PieceType::Rook,
-        'p' => PieceType::Pawn,
-        _ => panic!("Chess piece char is not known"),
+    PieceType::from_letter(c).expect("Chess piece char is not known")
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::core::{fen, variant};
+
+    fn perft(board: &Board, player: Player, depth: u32) -> u64 {
+        let moves = board.all_legal_moves(player);
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        moves
+            .iter()
+            .map(|(from, m)| {
+                let mut board = board.clone();
+                board.make_move(*from, m);
+                perft(&board, player.opponent(), depth - 1)
+            })
+            .sum()
+    }
+
+    fn assert_perft(fen: &str, expected: &[u64]) {
+        assert_variant_perft("standard", fen, expected);
+    }
+
+    /// Check the number of positions reached from `fen` under the named
+    /// variant's rules, one ply deeper for each of `expected`.
+    ///
+    pub(crate) fn assert_variant_perft(variant: &str, fen: &str, expected: &[u64]) {
+        let variant = variant::by_name(variant).unwrap();
+        let fen = fen::parse_variant(fen, variant).unwrap();
+
+        for (depth, nodes) in expected.iter().enumerate() {
+            let depth = depth as u32 + 1;
+            assert_eq!(
+                perft(&fen.board, fen.turn, depth),
+                *nodes,
+                "depth {}",
+                depth
+            );
+        }
+    }
+
+    #[test]
+    fn perft_start_position() {
+        assert_perft(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            &[20, 400, 8902],
+        );
+    }
+
+    #[test]
+    fn perft_kiwipete() {
+        assert_perft(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &[48, 2039],
+        );
+    }
+
+    #[test]
+    fn perft_en_passant_and_checks() {
+        assert_perft(
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            &[14, 191, 2812],
+        );
+    }
+
+    #[test]
+    fn perft_chess960() {
+        assert_perft(
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            &[21, 528, 12189],
+        );
+        assert_perft(
+            "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+            &[21, 807, 18002],
+        );
+        assert_perft(
+            "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+            &[20, 479, 10471],
+        );
+    }
+
+    #[test]
+    fn chess960_start_positions() {
+        let rank = |index| {
+            let board = Board::chess960(index);
+            let fen = fen::write(&board, Player::White, 1, fen::CastlingNotation::Shredder);
+            fen.split('/').next().unwrap().to_string()
+        };
+
+        assert_eq!(rank(0), "bbqnnrkr");
+        assert_eq!(rank(518), "rnbqkbnr");
+        assert_eq!(rank(959), "rkrnnqbb");
+
+        assert_eq!(perft(&Board::chess960(518), Player::White, 3), 8902);
     }
 }
@@ -0,0 +1,152 @@
+use std::sync::Arc;
+
+use super::{
+    variant::{Bughouse, Variant},
+    Chess, MoveRecord, MoveResult, Outcome, PieceType, Player, Reason,
+};
+
+/// What the two boards are called.
+///
+pub const BOARD_NAMES: [&str; 2] = ["A", "B"];
+
+/// A game of bughouse: two games played at once by two teams of two,
+/// White on board A partnering Black on board B. A piece captured on one
+/// board goes to the capturer's partner to drop on the other, and the
+/// first game to be decided decides the match.
+///
+pub struct BughouseGame {
+    pub boards: [Chess; 2],
+    /// The board the keys play on.
+    pub active: usize,
+    /// How many moves of each board's history have had their captures
+    /// passed on.
+    passed: [usize; 2],
+}
+
+impl BughouseGame {
+    pub fn new() -> Self {
+        let variant: Arc<dyn Variant> = Arc::new(Bughouse);
+
+        let mut boards = [
+            Chess::with_variant(variant.clone()),
+            Chess::with_variant(variant),
+        ];
+
+        // partners sit side by side, so board B faces the other way
+        //
+        for (game, player) in boards.iter_mut().zip([Player::White, Player::Black]) {
+            game.view.perspective = player;
+            game.view.follow_turn = false;
+        }
+
+        Self {
+            boards,
+            active: 0,
+            passed: [0; 2],
+        }
+    }
+
+    /// Start both clocks together.
+    ///
+    pub fn start(&mut self) {
+        for game in &mut self.boards {
+            game.start();
+        }
+    }
+
+    /// The result of the match, once either game has been decided, as the
+    /// board it was decided on and the outcome there.
+    ///
+    pub fn outcome(&self) -> Option<(usize, Outcome)> {
+        self.boards
+            .iter()
+            .enumerate()
+            .find_map(|(i, game)| match game.outcome {
+                Some(Outcome::Win(_, Reason::OtherBoard) | Outcome::Draw(Reason::OtherBoard)) => {
+                    None
+                }
+                outcome => outcome.map(|outcome| (i, outcome)),
+            })
+    }
+
+    /// Bring the two games into line after either has changed: pass on
+    /// the pieces captured since, switch boards if asked to, let a fallen
+    /// flag lose its game, and end one game once the other is decided.
+    ///
+    pub fn update(&mut self) {
+        for i in 0..2 {
+            let [a, b] = &mut self.boards;
+            let (game, partner) = if i == 0 { (a, b) } else { (b, a) };
+
+            for record in &game.history[self.passed[i]..] {
+                if let Some(ty) = passed_piece(record) {
+                    // the capturer's partner plays the other colour
+                    //
+                    partner.board.add_to_pocket(record.player.opponent(), ty);
+                }
+            }
+
+            self.passed[i] = game.history.len();
+        }
+
+        let active = &mut self.boards[self.active].view;
+
+        if active.other_board {
+            active.other_board = false;
+
+            // a board played by someone else over the network stays theirs
+            //
+            if !self.boards[1 - self.active].refereed {
+                self.active = 1 - self.active;
+            }
+        }
+
+        for game in &mut self.boards {
+            // over the network, the server's clock is the one that counts
+            //
+            if game.outcome.is_none()
+                && !game.refereed
+                && game.timers[game.turn as usize].remaining() == 0
+            {
+                game.finish(Outcome::Win(game.turn.opponent(), Reason::Timeout));
+            }
+        }
+
+        if let Some((board, outcome)) = self.outcome() {
+            let other = &mut self.boards[1 - board];
+
+            if other.outcome.is_none() {
+                // whoever won partners the other side of the other board
+                //
+                other.finish(match outcome {
+                    Outcome::Win(player, _) => Outcome::Win(player.opponent(), Reason::OtherBoard),
+                    Outcome::Draw(_) => Outcome::Draw(Reason::OtherBoard),
+                });
+            }
+        }
+    }
+}
+
+impl Default for BughouseGame {
+    fn default() -> Self {
+        BughouseGame::new()
+    }
+}
+
+/// The piece a move captured, as it goes to the partner: a promoted
+/// piece goes back to being a pawn.
+///
+fn passed_piece(record: &MoveRecord) -> Option<PieceType> {
+    let square = match record.result {
+        MoveResult::Capture(pos) => pos,
+        _ => record.to,
+    };
+
+    let piece = record.captured.and(record.board.get(square))?;
+
+    Some(if piece.promoted {
+        PieceType::Pawn
+    } else {
+        piece.ty
+    })
+}
@@ -0,0 +1,244 @@
+use super::fen::{self, CastlingNotation};
+use super::gamestate::DEFAULT_TIME;
+use super::{validate, Board, Chess, CountdownTimer, Mode, Piece, PieceType, Player, State};
+
+/// The pieces the editor offers, in the order the brush cycles through
+/// them, before any fairy pieces of the variant.
+///
+const PIECES: [PieceType; 6] = [
+    PieceType::Pawn,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+    PieceType::King,
+];
+
+/// Setting up a position by hand, in [`Mode::Editing`]. The board is
+/// changed in place, and the game starts over from it once it is played.
+///
+impl Chess {
+    /// Start setting up a position, from the one on the board. Not
+    /// possible when the position belongs to someone else, such as a
+    /// referee or the other board in bughouse.
+    ///
+    pub fn edit(&mut self) {
+        if self.refereed || !self.board.variant.can_undo() {
+            self.view.message = Some("The position can't be edited in this game".into());
+            return;
+        }
+
+        self.stop();
+        self.state = State::Paused;
+        self.outcome = None;
+        self.draw_offer = None;
+        self.board.en_passant = None;
+
+        let brush = match self.cursor_piece() {
+            Some(piece) => (piece.player, piece.ty),
+            None => (Player::White, PieceType::Pawn),
+        };
+
+        self.mode = Mode::Editing(Some(brush));
+    }
+
+    pub fn is_editing(&self) -> bool {
+        matches!(self.mode, Mode::Editing(_))
+    }
+
+    /// Put the brush's piece on the square under the cursor, or clear it
+    /// if the same piece is already there.
+    ///
+    pub fn place(&mut self) {
+        let Mode::Editing(brush) = self.mode else {
+            return;
+        };
+
+        // a pawn that has just double stepped is there no longer
+        //
+        self.board.en_passant = None;
+
+        let square = self.board.get_mut(self.cursor);
+
+        *square = match (brush, &square) {
+            (Some((player, ty)), Some(piece)) if piece.player == player && piece.ty == ty => None,
+            (Some((player, ty)), _) => Some(Piece {
+                ty,
+                player,
+                // castling rights are given separately
+                //
+                move_count: 1,
+                promoted: false,
+            }),
+            (None, _) => None,
+        };
+    }
+
+    /// Pick the next piece to place, White's pieces first, then Black's,
+    /// then clearing squares.
+    ///
+    pub fn next_brush(&mut self) {
+        let Mode::Editing(brush) = self.mode else {
+            return;
+        };
+
+        let pieces: Vec<(Player, PieceType)> = [Player::White, Player::Black]
+            .into_iter()
+            .flat_map(|player| self.piece_types().map(move |ty| (player, ty)))
+            .collect();
+
+        let next = match brush {
+            Some(brush) => pieces.iter().skip_while(|p| **p != brush).nth(1).copied(),
+            None => pieces.first().copied(),
+        };
+
+        self.mode = Mode::Editing(next);
+    }
+
+    /// Choose the piece to place by its letter, upper case for White and
+    /// lower case for Black, or `-` to clear squares.
+    ///
+    pub fn set_brush(&mut self, letter: &str) -> Result<(), String> {
+        let mut chars = letter.chars();
+
+        let brush = match (chars.next(), chars.next()) {
+            (Some('-'), None) => None,
+            (Some(c), None) => {
+                let fairy = self.board.variant.fairy_pieces();
+                let ty = PieceType::from_letter_in(c, fairy)
+                    .ok_or_else(|| format!("No piece '{}'", c))?;
+                let player = match c.is_ascii_uppercase() {
+                    true => Player::White,
+                    false => Player::Black,
+                };
+                Some((player, ty))
+            }
+            _ => return Err(format!("Expected a piece letter, not '{}'", letter)),
+        };
+
+        self.mode = Mode::Editing(brush);
+
+        Ok(())
+    }
+
+    /// Give the move to `player`, or to the other side if `None`.
+    ///
+    pub fn set_turn(&mut self, player: Option<Player>) {
+        self.turn = player.unwrap_or(self.turn.opponent());
+
+        if self.view.follow_turn {
+            self.view.perspective = self.turn;
+        }
+    }
+
+    /// Set the castling rights from FEN notation, e.g. `KQkq` or `-`.
+    ///
+    pub fn set_castling(&mut self, castling: &str) -> Result<(), String> {
+        let mut board = self.board.clone();
+
+        for player in [Player::White, Player::Black] {
+            let squares: Vec<_> = board
+                .pieces(player)
+                .filter(|(_, p)| matches!(p.ty, PieceType::King | PieceType::Rook))
+                .map(|(pos, _)| pos)
+                .collect();
+
+            for pos in squares {
+                if let Some(piece) = board.get_mut(pos) {
+                    piece.move_count = 1;
+                }
+            }
+        }
+        board.chess960 = false;
+
+        fen::parse_castling(&mut board, castling).map_err(|err| err.to_string())?;
+
+        self.board = board;
+
+        Ok(())
+    }
+
+    /// The castling rights of the position, as written in FEN.
+    ///
+    pub fn castling(&self) -> String {
+        fen::write_castling(&self.board, CastlingNotation::XFen)
+    }
+
+    /// Take every piece off the board.
+    ///
+    pub fn clear_board(&mut self) {
+        let squares: Vec<_> = [Player::White, Player::Black]
+            .into_iter()
+            .flat_map(|player| self.board.pieces(player).map(|(pos, _)| pos))
+            .collect();
+
+        for pos in squares {
+            self.board.take_piece(pos);
+        }
+    }
+
+    /// Put the pieces back where the variant starts them.
+    ///
+    pub fn reset_board(&mut self) {
+        self.board = self.board.variant.start_position();
+        self.turn = Player::White;
+    }
+
+    /// Stop editing and start a new game from the position, if it can be
+    /// played.
+    ///
+    pub fn play_position(&mut self) -> Result<(), String> {
+        if !self.is_editing() {
+            return Ok(());
+        }
+
+        self.board = self.edited_position()?;
+        self.history.clear();
+        self.first_move = 1;
+        self.timers = [
+            CountdownTimer::new(DEFAULT_TIME),
+            CountdownTimer::new(DEFAULT_TIME),
+        ];
+        self.mode = Mode::Selecting;
+        self.start();
+
+        Ok(())
+    }
+
+    /// The position set up in the editor, as it would be played on,
+    /// if it can be.
+    ///
+    pub fn edited_position(&self) -> Result<Board, String> {
+        let problems: Vec<String> = validate(&self.board, self.turn)
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        if !problems.is_empty() {
+            return Err(problems.join(", "));
+        }
+
+        // reading the position back as FEN settles which pawns may still
+        // double step
+        //
+        let mut board = self.board.clone();
+        board.halfmove_clock = 0;
+
+        let text = fen::write(&board, self.turn, 1, CastlingNotation::Shredder);
+        let fen =
+            fen::parse_variant(&text, self.board.variant.clone()).map_err(|e| e.to_string())?;
+
+        Ok(fen.board)
+    }
+
+    /// The standard pieces and the variant's own.
+    ///
+    fn piece_types(&self) -> impl Iterator<Item = PieceType> + '_ {
+        PIECES.into_iter().chain(
+            self.board
+                .variant
+                .fairy_pieces()
+                .iter()
+                .map(PieceType::Fairy),
+        )
+    }
+}
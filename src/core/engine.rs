@@ -1,4 +1,4 @@
-use crate::core::{self, Chess, State};
+use crate::core::{self, BughouseGame, Chess, State};
 use crate::input;
 use crate::render::{self, Theme};
 
@@ -25,6 +25,7 @@ pub struct Engine<R: render::Renderer, I1: input::Input, I2: input::Input> {
     renderer: R,
     p1_input: I1,
     p2_input: I2,
+    themes: Vec<Theme>,
 }
 
 #[allow(dead_code)]
@@ -36,9 +37,18 @@ impl<R: render::Renderer, I1: input::Input, I2: input::Input> Engine<R, I1, I2>
             renderer,
             p1_input,
             p2_input,
+            themes: Theme::builtins(),
         }
     }
 
+    /// Replace the themes the player can cycle through at runtime. The
+    /// first theme should match the one the renderer was created with.
+    ///
+    pub fn with_themes(mut self, themes: Vec<Theme>) -> Self {
+        self.themes = themes;
+        self
+    }
+
     #[allow(dead_code)]
     pub fn run(mut self) -> Result<(), Error> {
         let Engine {
@@ -46,25 +56,111 @@ impl<R: render::Renderer, I1: input::Input, I2: input::Input> Engine<R, I1, I2>
             renderer,
             p1_input,
             p2_input,
+            themes,
         } = &mut self;
 
         renderer.init()?;
-        renderer.set_theme(Theme::default());
 
         game.start();
 
+        let mut theme = game.view.theme;
+
         loop {
             match game.turn {
                 core::Player::White => p1_input.update(game)?,
                 core::Player::Black => p2_input.update(game)?,
             }
 
+            if game.view.theme != theme && !themes.is_empty() {
+                theme = game.view.theme;
+                renderer.set_theme(themes[theme % themes.len()].clone());
+            }
+
             renderer.render(game)?;
 
             match game.state {
                 State::Exit => break,
                 State::Paused => {}
                 State::Playing => {}
+                State::Over => {}
+                State::Analysis => {}
+            }
+        }
+
+        renderer.shutdown()?;
+
+        Ok(())
+    }
+}
+
+/// Runs a game of bughouse, with one input playing whichever of the two
+/// boards is active.
+///
+pub struct BughouseEngine<R: render::Renderer, I: input::Input> {
+    game: BughouseGame,
+    renderer: R,
+    input: I,
+    themes: Vec<Theme>,
+}
+
+impl<R: render::Renderer, I: input::Input> BughouseEngine<R, I> {
+    pub fn new(game: BughouseGame, renderer: R, input: I) -> Self {
+        Self {
+            game,
+            renderer,
+            input,
+            themes: Theme::builtins(),
+        }
+    }
+
+    /// Replace the themes the player can cycle through at runtime, as
+    /// with [`Engine::with_themes`].
+    ///
+    pub fn with_themes(mut self, themes: Vec<Theme>) -> Self {
+        self.themes = themes;
+        self
+    }
+
+    pub fn run(mut self) -> Result<(), Error> {
+        let BughouseEngine {
+            game,
+            renderer,
+            input,
+            themes,
+        } = &mut self;
+
+        renderer.init()?;
+
+        game.start();
+
+        let mut theme = game.boards[game.active].view.theme;
+
+        loop {
+            input.update_bughouse(game)?;
+
+            game.update();
+
+            // the theme may be changed from either board
+            //
+            let view = &mut game.boards[game.active].view;
+
+            if view.theme != theme && !themes.is_empty() {
+                theme = view.theme;
+                renderer.set_theme(themes[theme % themes.len()].clone());
+            }
+
+            for board in &mut game.boards {
+                board.view.theme = theme;
+            }
+
+            renderer.render_bughouse(game)?;
+
+            if game
+                .boards
+                .iter()
+                .any(|board| matches!(board.state, State::Exit))
+            {
+                break;
             }
         }
 
@@ -0,0 +1,70 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::Arc,
+};
+
+/// What other parts of the program keep with a game, such as the
+/// computer's opening book or a review of the game's moves, looked up
+/// by type so that the rules needn't know about them.
+///
+#[derive(Default)]
+pub struct Extensions {
+    /// Loaded once and shared with any game that takes this one's
+    /// place, such as an opening book.
+    shared: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+    /// Belonging to this game alone, such as a running analysis.
+    owned: HashMap<TypeId, Box<dyn Any + Send>>,
+}
+
+impl Extensions {
+    /// Share `value` with this game and any that takes its place.
+    ///
+    pub fn share<T: Any + Send + Sync>(&mut self, value: Arc<T>) {
+        self.shared.insert(TypeId::of::<T>(), value);
+    }
+
+    /// The shared value of type `T`, if there is one.
+    ///
+    pub fn shared<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
+        let value = self.shared.get(&TypeId::of::<T>())?;
+        value.clone().downcast().ok()
+    }
+
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.owned.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+
+    pub fn get_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.owned.get_mut(&TypeId::of::<T>())?.downcast_mut()
+    }
+
+    pub fn get_or_insert_with<T: Any + Send>(&mut self, f: impl FnOnce() -> T) -> &mut T {
+        self.owned
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(f()))
+            .downcast_mut()
+            .unwrap()
+    }
+
+    /// Keep `value` with this game, replacing any value of its type.
+    ///
+    pub fn insert<T: Any + Send>(&mut self, value: T) {
+        self.owned.insert(TypeId::of::<T>(), Box::new(value));
+    }
+
+    pub fn remove<T: Any>(&mut self) -> Option<T> {
+        let value = self.owned.remove(&TypeId::of::<T>())?;
+        value.downcast().ok().map(|value| *value)
+    }
+
+    /// The extensions to carry over to a game taking this one's place:
+    /// the shared values, but none of this game's own.
+    ///
+    pub fn fork(&self) -> Extensions {
+        Extensions {
+            shared: self.shared.clone(),
+            owned: HashMap::new(),
+        }
+    }
+}
@@ -0,0 +1,467 @@
+use std::{fmt::Display, sync::Arc};
+
+use super::{
+    notation,
+    variant::{Crazyhouse, Standard, Variant},
+    Board, FairyPiece, Piece, PieceType, Player, Problem, Vec2,
+};
+
+#[derive(Debug)]
+pub enum FenError {
+    MissingField(&'static str),
+    Placement(String),
+    SideToMove(String),
+    Castling(String),
+    EnPassant(String),
+    Counter(String),
+    /// The position is well formed but could not arise in a game.
+    Illegal(Vec<Problem>),
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FenError::MissingField(field) => write!(f, "missing {}", field),
+            FenError::Placement(s) => write!(f, "invalid piece placement '{}'", s),
+            FenError::SideToMove(s) => write!(f, "invalid side to move '{}'", s),
+            FenError::Castling(s) => write!(f, "invalid castling rights '{}'", s),
+            FenError::EnPassant(s) => write!(f, "invalid en passant square '{}'", s),
+            FenError::Counter(s) => write!(f, "invalid move counter '{}'", s),
+            FenError::Illegal(problems) => {
+                let problems: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+                write!(f, "illegal position: {}", problems.join(", "))
+            }
+        }
+    }
+}
+
+/// How castling rights are written out.
+///
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CastlingNotation {
+    /// `KQkq`, falling back to the rook's file when it is not the
+    /// outermost rook on that side of the king (X-FEN).
+    XFen,
+    /// The rook's file for every right, e.g. `HAha` (Shredder-FEN).
+    Shredder,
+}
+
+/// A position read from a FEN string.
+///
+pub struct Fen {
+    pub board: Board,
+    pub turn: Player,
+    pub fullmove: u32,
+}
+
+/// Parse a FEN string. Castling rights may be given as `KQkq`, in X-FEN
+/// or in Shredder-FEN; the board is switched to Chess960 castling if they
+/// name a king or rook away from its classical square.
+///
+pub fn parse(fen: &str) -> Result<Fen, FenError> {
+    parse_variant(fen, Arc::new(Standard))
+}
+
+/// Parse a FEN string for a game of `variant`, which may use its own
+/// pieces and a board of any size. A position with pockets is played
+/// as Crazyhouse unless the variant already has pockets.
+///
+pub fn parse_variant(fen: &str, variant: Arc<dyn Variant>) -> Result<Fen, FenError> {
+    let mut fields = fen.split_whitespace();
+
+    let placement = fields
+        .next()
+        .ok_or(FenError::MissingField("piece placement"))?;
+    let turn = fields
+        .next()
+        .ok_or(FenError::MissingField("side to move"))?;
+    let castling = fields.next().unwrap_or("-");
+    let en_passant = fields.next().unwrap_or("-");
+    let halfmove = fields.next().unwrap_or("0");
+    let fullmove = fields.next().unwrap_or("1");
+
+    let mut board = parse_placement(placement, variant.fairy_pieces())?;
+
+    if !board.variant.has_pockets() || variant.has_pockets() {
+        board.variant = variant;
+    }
+
+    let turn = match turn {
+        "w" => Player::White,
+        "b" => Player::Black,
+        _ => return Err(FenError::SideToMove(turn.into())),
+    };
+
+    parse_castling(&mut board, castling)?;
+
+    if en_passant != "-" {
+        let pos = notation::parse_square(&board, en_passant)
+            .filter(|pos| pos.y == 2 || pos.y == board.height() - 3)
+            .ok_or_else(|| FenError::EnPassant(en_passant.into()))?;
+        board.en_passant = Some(pos);
+    }
+
+    board.halfmove_clock = halfmove
+        .parse()
+        .map_err(|_| FenError::Counter(halfmove.into()))?;
+
+    let fullmove = fullmove
+        .parse()
+        .map_err(|_| FenError::Counter(fullmove.into()))?;
+
+    Ok(Fen {
+        board,
+        turn,
+        fullmove,
+    })
+}
+
+/// Write the position out as a FEN string.
+///
+pub fn write(board: &Board, turn: Player, fullmove: u32, castling: CastlingNotation) -> String {
+    let mut placement = String::new();
+
+    for y in 0..board.height() {
+        let mut empty = 0;
+
+        for x in 0..board.width() {
+            match board.get(Vec2::new(x, y)) {
+                Some(piece) => {
+                    if empty > 0 {
+                        placement.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    placement.push(piece_letter(piece));
+                    if piece.promoted {
+                        placement.push('~');
+                    }
+                }
+                None => empty += 1,
+            }
+        }
+
+        if empty > 0 {
+            placement.push_str(&empty.to_string());
+        }
+        if y < board.height() - 1 {
+            placement.push('/');
+        }
+    }
+
+    if board.variant.has_pockets() {
+        placement.push('[');
+        for player in [Player::White, Player::Black] {
+            for (ty, count) in board.pocket(player) {
+                let piece = Piece {
+                    ty,
+                    player,
+                    move_count: 0,
+                    promoted: false,
+                };
+                for _ in 0..count {
+                    placement.push(piece_letter(&piece));
+                }
+            }
+        }
+        placement.push(']');
+    }
+
+    let turn = match turn {
+        Player::White => "w",
+        Player::Black => "b",
+    };
+
+    let en_passant = match board.en_passant {
+        Some(pos) => notation::square_name(board, pos),
+        None => "-".into(),
+    };
+
+    format!(
+        "{} {} {} {} {} {}",
+        placement,
+        turn,
+        write_castling(board, castling),
+        en_passant,
+        board.halfmove_clock,
+        fullmove
+    )
+}
+
+fn piece_letter(piece: &Piece) -> char {
+    match piece.player {
+        Player::White => piece.ty.letter(),
+        Player::Black => piece.ty.letter().to_ascii_lowercase(),
+    }
+}
+
+/// The widest board that can be written down, with files `a` to `z`.
+///
+const MAX_WIDTH: usize = 26;
+
+fn parse_placement(placement: &str, fairy: &'static [FairyPiece]) -> Result<Board, FenError> {
+    let err = || FenError::Placement(placement.into());
+
+    // a Crazyhouse position lists the pockets after the board, e.g. `[Qn]`
+    //
+    let (placement, pockets) = match placement.split_once('[') {
+        Some((board, pockets)) => (board, Some(pockets.strip_suffix(']').ok_or_else(err)?)),
+        None => (placement, None),
+    };
+
+    // read every rank first, as the ranks give the size of the board
+    //
+    let rows = placement
+        .split('/')
+        .map(|rank| parse_rank(rank, fairy).ok_or_else(err))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let width = rows[0].len();
+    if width == 0 || width > MAX_WIDTH || rows.iter().any(|row| row.len() != width) {
+        return Err(err());
+    }
+
+    let mut board = Board::with_size(width as i16, rows.len() as i16);
+
+    for (y, row) in rows.into_iter().enumerate() {
+        for (x, square) in row.into_iter().enumerate() {
+            if let Some(mut piece) = square {
+                // kings and rooks only keep a move count of zero if they
+                // have castling rights, pawns if they can double step.
+                //
+                let player = piece.player;
+                let start = board.home_rank(player) + pawn_offset(player);
+
+                if piece.ty == PieceType::Pawn && y as i16 == start {
+                    piece.move_count = 0;
+                }
+
+                *board.get_mut(Vec2::new(x as i16, y as i16)) = Some(piece);
+            }
+        }
+    }
+
+    if let Some(pockets) = pockets {
+        for c in pockets.chars() {
+            let ty = PieceType::from_letter(c)
+                .filter(|ty| *ty != PieceType::King)
+                .ok_or_else(err)?;
+            board.add_to_pocket(letter_player(c), ty);
+        }
+        board.variant = Arc::new(Crazyhouse);
+    }
+
+    Ok(board)
+}
+
+/// The squares of one rank of the placement, e.g. `r3k2r`.
+///
+fn parse_rank(rank: &str, fairy: &'static [FairyPiece]) -> Option<Vec<Option<Piece>>> {
+    let mut squares: Vec<Option<Piece>> = Vec::new();
+
+    // runs of empty squares may take more than one digit, e.g. `10`
+    //
+    let mut empty = 0;
+
+    for c in rank.chars() {
+        if let Some(n) = c.to_digit(10) {
+            empty = empty * 10 + n as usize;
+            if empty > MAX_WIDTH {
+                return None;
+            }
+            continue;
+        }
+
+        squares.resize(squares.len() + empty, None);
+        empty = 0;
+
+        // `~` marks the piece before it as a promoted pawn
+        //
+        if c == '~' {
+            squares.last_mut()?.as_mut()?.promoted = true;
+            continue;
+        }
+
+        squares.push(Some(Piece {
+            ty: PieceType::from_letter_in(c, fairy)?,
+            player: letter_player(c),
+            move_count: 1,
+            promoted: false,
+        }));
+    }
+
+    squares.resize(squares.len() + empty, None);
+
+    Some(squares)
+}
+
+fn letter_player(c: char) -> Player {
+    if c.is_ascii_uppercase() {
+        Player::White
+    } else {
+        Player::Black
+    }
+}
+
+fn pawn_offset(player: Player) -> i16 {
+    match player {
+        Player::White => -1,
+        Player::Black => 1,
+    }
+}
+
+/// Give the kings and rooks named in `castling`, e.g. `KQkq`, `HAha` or
+/// `-`, the right to castle.
+///
+pub fn parse_castling(board: &mut Board, castling: &str) -> Result<(), FenError> {
+    if castling == "-" {
+        return Ok(());
+    }
+
+    let err = || FenError::Castling(castling.into());
+
+    for c in castling.chars() {
+        let player = letter_player(c);
+        let rank = board.home_rank(player);
+
+        let king = board
+            .find_king(player)
+            .filter(|king| king.y == rank)
+            .ok_or_else(err)?;
+
+        let rooks: Vec<i16> = board
+            .pieces(player)
+            .filter(|(pos, p)| p.ty == PieceType::Rook && pos.y == rank)
+            .map(|(pos, _)| pos.x)
+            .collect();
+
+        let file = match c.to_ascii_lowercase() {
+            'k' => rooks.iter().filter(|x| **x > king.x).max().copied(),
+            'q' => rooks.iter().filter(|x| **x < king.x).min().copied(),
+            f @ 'a'..='z' => Some(f as i16 - 'a' as i16).filter(|x| rooks.contains(x)),
+            _ => None,
+        }
+        .ok_or_else(err)?;
+
+        let rook = Vec2::new(file, rank);
+
+        for pos in [king, rook] {
+            if let Some(piece) = board.get_mut(pos) {
+                piece.move_count = 0;
+            }
+        }
+
+        let width = board.width();
+
+        if king.x != width / 2 || (file != 0 && file != width - 1) {
+            board.chess960 = true;
+        }
+    }
+
+    Ok(())
+}
+
+/// The castling rights on `board`, as written in a FEN string.
+///
+pub fn write_castling(board: &Board, castling: CastlingNotation) -> String {
+    let mut rights = String::new();
+
+    for player in [Player::White, Player::Black] {
+        let rank = board.home_rank(player);
+
+        let king = match board.find_king(player) {
+            Some(king) if king.y == rank && board.get(king).unwrap().move_count == 0 => king,
+            _ => continue,
+        };
+
+        let mut rooks: Vec<(i16, bool)> = board
+            .pieces(player)
+            .filter(|(pos, p)| p.ty == PieceType::Rook && pos.y == rank)
+            .map(|(pos, p)| (pos.x, p.move_count == 0))
+            .collect();
+
+        // king side first, outermost rook first
+        //
+        rooks.sort_by_key(|(x, _)| -x);
+
+        let outermost = (rooks.first().map(|r| r.0), rooks.last().map(|r| r.0));
+
+        let king_side = rooks.iter().filter(|(x, _)| *x > king.x);
+        let queen_side = rooks.iter().filter(|(x, _)| *x < king.x).rev();
+
+        for (x, unmoved) in king_side.chain(queen_side) {
+            if !unmoved {
+                continue;
+            }
+
+            let letter = match castling {
+                CastlingNotation::XFen if Some(*x) == outermost.0 && *x > king.x => 'k',
+                CastlingNotation::XFen if Some(*x) == outermost.1 && *x < king.x => 'q',
+                _ => (b'a' + *x as u8) as char,
+            };
+
+            rights.push(match player {
+                Player::White => letter.to_ascii_uppercase(),
+                Player::Black => letter,
+            });
+        }
+    }
+
+    if rights.is_empty() {
+        rights.push('-');
+    }
+
+    rights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(text: &str, castling: CastlingNotation) -> String {
+        let fen = parse(text).unwrap();
+        write(&fen.board, fen.turn, fen.fullmove, castling)
+    }
+
+    #[test]
+    fn classical_castling_round_trip() {
+        let text = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+        assert_eq!(round_trip(text, CastlingNotation::XFen), text);
+        assert_eq!(
+            round_trip(text, CastlingNotation::Shredder),
+            text.replace("KQkq", "HAha")
+        );
+        assert!(!parse(text).unwrap().board.chess960);
+    }
+
+    #[test]
+    fn shredder_fen_round_trip() {
+        let text = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+
+        assert!(parse(text).unwrap().board.chess960);
+        assert_eq!(round_trip(text, CastlingNotation::Shredder), text);
+        assert_eq!(
+            round_trip(text, CastlingNotation::XFen),
+            text.replace("HFhf", "KQkq")
+        );
+    }
+
+    #[test]
+    fn x_fen_names_inner_rooks_by_file() {
+        // two rooks on the king side: the inner one is named by its file
+        //
+        let text = "4k3/8/8/8/8/8/8/1R2KR1R w KFQ - 0 1";
+        let fen = parse(text).unwrap();
+
+        assert!(fen.board.chess960);
+        assert_eq!(round_trip(text, CastlingNotation::XFen), text);
+        assert_eq!(
+            round_trip(text, CastlingNotation::Shredder),
+            text.replace("KFQ", "HFB")
+        );
+        assert_eq!(
+            round_trip(&text.replace("KFQ", "HFB"), CastlingNotation::XFen),
+            text
+        );
+    }
+}
@@ -1,20 +1,49 @@
-use std::time::Duration;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crate::{
+    core::fen::{self, CastlingNotation, FenError},
     core::timer::CountdownTimer,
+    core::variant::{Standard, Variant},
     core::Vec2,
-    core::{Board, MoveResult, Piece, Player},
+    core::{hash, notation, random, validate},
+    core::{
+        Board, Extensions, Move, MoveRecord, MoveResult, Outcome, Piece, PieceType, Player,
+        Puzzles, Reason, View,
+    },
 };
 
+pub(crate) const DEFAULT_TIME: Duration = Duration::from_secs(10 * 60);
+
 pub enum State {
     Paused,
     Playing,
+    Over,
     Exit,
+    /// The search runs on the position shown while the player moves
+    /// for both sides, with the clocks stopped.
+    Analysis,
 }
 
 pub enum Mode {
     Selecting,
     Moving(Vec2),
+    /// Choosing where to drop a piece from the pocket.
+    Dropping(PieceType),
+    /// Setting up a position, placing the piece given on the squares
+    /// chosen, or clearing them if there is none.
+    Editing(Option<(Player, PieceType)>),
+}
+
+/// Something the local player asked for in a refereed game, waiting
+/// to be passed on to the referee.
+///
+pub enum Request {
+    /// A move in SAN or coordinate notation.
+    Move(String),
+    Resign,
 }
 
 pub struct Chess {
@@ -24,16 +53,33 @@ pub struct Chess {
     pub board: Board,
     pub mode: Mode,
     pub state: State,
+    pub view: View,
+    pub history: Vec<MoveRecord>,
+    pub outcome: Option<Outcome>,
+    /// The player with a draw offer on the table.
+    pub draw_offer: Option<Player>,
+    /// The number of the first move in the history, which is
+    /// not 1 when the game was set up from a FEN string.
+    pub first_move: u32,
+    /// Set when a referee decides which moves are legal, as in Kriegspiel
+    /// over the network. Moves are then queued in `requests` rather than
+    /// played, and the referee sends back the new position.
+    pub refereed: bool,
+    pub requests: Vec<Request>,
+    /// The computer's opening book and tablebases, a running analysis,
+    /// and anything else kept with the game by the rest of the program.
+    pub extensions: Extensions,
+    /// The puzzles being solved, in puzzle mode.
+    pub puzzles: Option<Puzzles>,
 }
 
 impl Chess {
     pub fn change_player(&mut self) {
         self.stop();
-        self.turn = if let Player::White = self.turn {
-            Player::Black
-        } else {
-            Player::White
-        };
+        self.turn = self.turn.opponent();
+        if self.view.follow_turn {
+            self.view.perspective = self.turn;
+        }
         self.start();
         self.mode = Mode::Selecting;
     }
@@ -50,32 +96,117 @@ impl Chess {
             timers: [CountdownTimer::new(duration), CountdownTimer::new(duration)],
             mode: Mode::Selecting,
             state: State::Paused,
+            view: View::default(),
+            history: Vec::new(),
+            outcome: None,
+            draw_offer: None,
+            first_move: 1,
+            refereed: false,
+            requests: Vec::new(),
+            extensions: Extensions::default(),
+            puzzles: None,
+        }
+    }
+
+    /// Set up a game from a FEN string.
+    ///
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        Chess::from_fen_variant(fen, Arc::new(Standard))
+    }
+
+    /// Set up a game of `variant` from a FEN string, which may use the
+    /// variant's own pieces and board size. The position must be one the
+    /// game could have reached, see [`validate`].
+    ///
+    pub fn from_fen_variant(fen: &str, variant: Arc<dyn Variant>) -> Result<Self, FenError> {
+        let fen = fen::parse_variant(fen, variant)?;
+
+        let problems = validate(&fen.board, fen.turn);
+        if !problems.is_empty() {
+            return Err(FenError::Illegal(problems));
         }
+
+        let mut game = Chess::new(fen.board, DEFAULT_TIME);
+        game.turn = fen.turn;
+        game.first_move = fen.fullmove;
+        game.view.perspective = fen.turn;
+
+        Ok(game)
+    }
+
+    /// The current position as a FEN string, with castling rights in X-FEN.
+    ///
+    pub fn to_fen(&self) -> String {
+        self.to_fen_with(CastlingNotation::XFen)
+    }
+
+    pub fn to_fen_with(&self, castling: CastlingNotation) -> String {
+        fen::write(&self.board, self.turn, self.fullmove(), castling)
+    }
+
+    /// The number of the move currently being played.
+    ///
+    pub fn fullmove(&self) -> u32 {
+        let first = self.history.first().map(|r| r.player).unwrap_or(self.turn);
+        let offset = (first == Player::Black) as usize;
+
+        self.first_move + ((self.history.len() + offset) / 2) as u32
+    }
+
+    /// A new game of `variant` from its start position.
+    ///
+    pub fn with_variant(variant: Arc<dyn Variant>) -> Self {
+        Chess::new(variant.start_position(), DEFAULT_TIME)
+    }
+
+    /// A Chess960 game starting from position `index` (0-959).
+    ///
+    pub fn chess960(index: usize) -> Self {
+        Chess::new(Board::chess960(index), DEFAULT_TIME)
+    }
+
+    /// A Chess960 game from one of the 960 start positions at random.
+    ///
+    pub fn random_chess960() -> Self {
+        Chess::chess960(random::below(960) as usize)
     }
 
     pub fn can_move_cursor_piece(&self) -> bool {
         if let Some(p) = self.cursor_piece() {
-            if p.player as usize == self.turn as usize {
-                if self.board.get_valid_moves(self.cursor, false).len() > 0 {
-                    return true;
-                }
+            if p.player as usize == self.turn as usize
+                && !self.board.get_legal_moves(self.cursor, false).is_empty()
+            {
+                return true;
             }
         }
         false
     }
 
+    /// Move the cursor in screen space, so that "up" always
+    /// moves away from the player facing the board.
+    ///
     pub fn move_cursor(&mut self, dir: Vec2) {
+        let dir = match self.view.perspective {
+            Player::White => dir,
+            Player::Black => dir * -1,
+        };
         let pos = self.cursor + dir;
         self.set_cursor(pos)
     }
 
     pub fn set_cursor(&mut self, pos: Vec2) {
-        if pos.x >= 0 && pos.x < 8 && pos.y >= 0 && pos.y < 8 {
+        if self.board.contains(pos) {
             self.cursor = pos;
         }
     }
 
     pub fn start(&mut self) {
+        // the clocks stand still while analysing
+        //
+        if self.is_analysing() {
+            return;
+        }
+
         self.state = State::Playing;
         self.timers[self.turn as usize].start();
     }
@@ -85,6 +216,10 @@ impl Chess {
     }
 
     pub fn action(&mut self) {
+        if self.outcome.is_some() {
+            return;
+        }
+
         match self.mode {
             Mode::Selecting => {
                 if let Some(p) = self.cursor_piece() {
@@ -93,31 +228,264 @@ impl Chess {
                     }
                 }
             }
+            Mode::Moving(from) if self.refereed => {
+                // only the referee knows whether the move is legal
+                //
+                let to = self.cursor;
+                if to != from {
+                    let text = format!(
+                        "{}{}",
+                        notation::square_name(&self.board, from),
+                        notation::square_name(&self.board, to)
+                    );
+                    self.requests.push(Request::Move(text));
+                }
+                self.mode = Mode::Selecting;
+            }
             Mode::Moving(from) => {
                 let to = self.cursor;
                 match self.get_move_result(from, to) {
-                    MoveResult::Cancel => {
-                        self.mode = Mode::Selecting;
-                        return;
-                    }
-                    MoveResult::Nothing => self.board.move_piece(from, to),
-                    MoveResult::Capture(pos) => {
-                        self.board.take_piece(pos);
-                        self.board.move_piece(from, to);
-                    }
-                    MoveResult::Castle => todo!(),
-                    MoveResult::Promotion(_) => todo!(),
-                    MoveResult::Invalid => return,
+                    MoveResult::Cancel => self.mode = Mode::Selecting,
+                    MoveResult::Invalid => {}
+                    result => self.play(from, Move::new(to, result)),
                 }
+            }
+            Mode::Dropping(ty) if self.refereed => {
+                let text = format!(
+                    "{}@{}",
+                    ty.letter(),
+                    notation::square_name(&self.board, self.cursor)
+                );
+                self.requests.push(Request::Move(text));
+                self.mode = Mode::Selecting;
+            }
+            Mode::Dropping(ty) => {
+                let to = self.cursor;
+                let drop = Move::drop(to, self.turn, ty);
 
-                self.change_player();
+                if self
+                    .board
+                    .drop_moves(self.turn, ty)
+                    .iter()
+                    .any(|m| m.pos == to)
+                {
+                    self.play(to, drop);
+                } else if self.can_move_cursor_piece() {
+                    self.mode = Mode::Moving(to);
+                }
             }
+            Mode::Editing(_) => self.place(),
         }
     }
 
+    /// Pick the next piece type from the pocket of the side to move to
+    /// drop, going back to selecting a piece after the last one.
+    ///
+    pub fn cycle_pocket(&mut self) {
+        if self.is_editing() {
+            self.next_brush();
+            return;
+        }
+
+        if self.outcome.is_some() {
+            return;
+        }
+
+        let pocket: Vec<PieceType> = self.board.pocket(self.turn).map(|(ty, _)| ty).collect();
+
+        let next = match self.mode {
+            Mode::Dropping(ty) => pocket.iter().skip_while(|t| **t != ty).nth(1),
+            _ => pocket.first(),
+        };
+
+        self.mode = match next {
+            Some(ty) => Mode::Dropping(*ty),
+            None => Mode::Selecting,
+        };
+    }
+
+    /// Play a move for the side to move, record it in the
+    /// history and hand the turn to the opponent.
+    ///
+    pub fn play(&mut self, from: Vec2, m: Move) {
+        if !self.puzzle_allows(from, &m) {
+            return;
+        }
+
+        let piece = match m.result {
+            MoveResult::Drop(_, ty) => ty,
+            _ => self.board.get(from).expect("Piece expected at position").ty,
+        };
+        let san = notation::san(&self.board, from, &m);
+
+        let blast = self.board.variant.blast(&self.board, from, &m);
+        if !blast.is_empty() {
+            self.view.explosion = Some((blast, Instant::now()));
+        }
+
+        let hinted = self.current_hint().is_some();
+        self.view.hint = None;
+
+        let board = self.board.clone();
+        let captured = self.board.make_move(from, &m).map(|p| p.ty);
+
+        self.history.push(MoveRecord {
+            player: self.turn,
+            piece,
+            from,
+            to: m.pos,
+            result: m.result,
+            captured,
+            san,
+            board,
+            hinted,
+        });
+
+        // moving instead of accepting declines the opponent's offer
+        //
+        if self.draw_offer == Some(self.turn.opponent()) {
+            self.draw_offer = None;
+        }
+
+        self.change_player();
+
+        let variant = self.board.variant.clone();
+
+        if let Some(outcome) = variant.outcome(&self.board, self.turn) {
+            self.finish(outcome);
+        }
+
+        self.puzzle_reply();
+    }
+
+    /// Play the move for the side to move written in `text`, in SAN or
+    /// coordinate notation. Returns false if there is no such legal move.
+    /// In a refereed game the move is passed on for the referee to judge.
+    ///
+    pub fn play_notation(&mut self, text: &str) -> bool {
+        if self.outcome.is_some() || self.is_editing() {
+            return false;
+        }
+
+        if self.refereed {
+            self.requests.push(Request::Move(text.into()));
+            return true;
+        }
+
+        match notation::parse_move(&self.board, self.turn, text) {
+            Some((from, m)) => {
+                self.play(from, m);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Take back the last move played.
+    ///
+    pub fn undo(&mut self) {
+        if self.refereed || !self.board.variant.can_undo() || self.is_editing() {
+            return;
+        }
+
+        // taking back a move would lose the place in the solution
+        //
+        if self.is_puzzle() {
+            self.view.message = Some("No taking back moves in a puzzle".into());
+            return;
+        }
+
+        if let Some(record) = self.history.pop() {
+            self.stop();
+            self.board = record.board;
+            self.turn = record.player;
+            self.outcome = None;
+            self.draw_offer = None;
+            self.mode = Mode::Selecting;
+            if self.view.follow_turn {
+                self.view.perspective = self.turn;
+            }
+            self.start();
+        }
+    }
+
+    /// The side to move resigns the game.
+    ///
+    pub fn resign(&mut self) {
+        if self.is_editing() {
+            return;
+        }
+
+        if self.refereed {
+            if self.outcome.is_none() {
+                self.requests.push(Request::Resign);
+            }
+        } else {
+            self.resign_player(self.turn);
+        }
+    }
+
+    /// `player` resigns the game, whether or not it is their turn.
+    ///
+    pub fn resign_player(&mut self, player: Player) {
+        if self.outcome.is_none() {
+            self.finish(Outcome::Win(player.opponent(), Reason::Resignation));
+        }
+    }
+
+    /// Offer a draw on behalf of the side to move, or accept
+    /// the opponent's offer if they have made one.
+    ///
+    pub fn offer_draw(&mut self) {
+        if self.outcome.is_some() || self.refereed || self.is_editing() {
+            return;
+        }
+
+        if self.draw_offer == Some(self.turn.opponent()) {
+            self.finish(Outcome::Draw(Reason::Agreement));
+        } else {
+            self.draw_offer = Some(self.turn);
+        }
+    }
+
+    /// Turn the board around, and stop it following the side to move.
+    ///
+    pub fn flip(&mut self) {
+        self.view.perspective = self.view.perspective.opponent();
+        self.view.follow_turn = false;
+    }
+
+    /// End the game with `outcome`.
+    ///
+    pub fn finish(&mut self, outcome: Outcome) {
+        self.stop();
+        self.outcome = Some(outcome);
+        self.draw_offer = None;
+        self.mode = Mode::Selecting;
+        if !self.is_analysing() {
+            self.state = State::Over;
+        }
+    }
+
+    /// The opponent's pieces that `player` has captured so far.
+    ///
+    pub fn captured_by(&self, player: Player) -> Vec<PieceType> {
+        self.history
+            .iter()
+            .filter(|r| r.player == player)
+            .filter_map(|r| r.captured)
+            .collect()
+    }
+
+    /// The total value of the pieces `player` has on the board.
+    ///
+    pub fn material(&self, player: Player) -> i32 {
+        self.board.pieces(player).map(|(_, p)| p.ty.value()).sum()
+    }
+
     pub fn get_move_result(&self, from: Vec2, to: Vec2) -> MoveResult {
-        if let Some(_) = self.board.get(from) {
-            let valid = self.board.get_valid_moves(from, true);
+        if self.board.get(from).is_some() {
+            let valid = self.board.get_legal_moves(from, true);
 
             if let Some(m) = valid.iter().find(|m| m.pos == to) {
                 return m.result;
@@ -126,6 +494,35 @@ impl Chess {
         MoveResult::Invalid
     }
 
+    /// The most recently played move, if any.
+    ///
+    pub fn last_move(&self) -> Option<&MoveRecord> {
+        self.history.last()
+    }
+
+    /// The squares to move from and to that a hint suggested for the
+    /// position on the board, if one was asked for.
+    ///
+    pub fn current_hint(&self) -> Option<(Vec2, Vec2)> {
+        let (key, from, to) = self.view.hint?;
+
+        (key == hash::position_hash(&self.board, self.turn)).then_some((from, to))
+    }
+
+    pub fn toggle_attacks(&mut self) {
+        self.view.show_attacks = !self.view.show_attacks;
+    }
+
+    /// Ask to play on the other board, in bughouse.
+    ///
+    pub fn other_board(&mut self) {
+        self.view.other_board = true;
+    }
+
+    pub fn next_theme(&mut self) {
+        self.view.theme += 1;
+    }
+
     pub fn quit(&mut self) {
         self.state = State::Exit;
     }
@@ -133,18 +530,6 @@ impl Chess {
 
 impl Default for Chess {
     fn default() -> Self {
-        let data = "RNBQKBNR\
-                    PPPPPPPP\
-                    ........\
-                    ........\
-                    ........\
-                    ........\
-                    pppppppp\
-                    rnbqkbnr";
-
-        let board = Board::from_string(data);
-        let duration = Duration::from_secs(10 * 60);
-
-        Chess::new(board, duration)
+        Chess::with_variant(Arc::new(Standard))
     }
 }
@@ -0,0 +1,85 @@
+use super::{Board, PieceType, Player, DROPS};
+
+/// A 64 bit key for the position on `board` with `turn` to move, the
+/// same for every way of reaching it. Keys are made Zobrist style, by
+/// combining a pseudo-random number for each feature of the position,
+/// and the numbers are worked out as needed rather than kept in tables
+/// so that any board size and fairy piece is covered.
+///
+pub fn position_hash(board: &Board, turn: Player) -> u64 {
+    let mut hash = 0;
+
+    for player in [Player::White, Player::Black] {
+        for (pos, piece) in board.pieces(player) {
+            // pieces that haven't moved keep the right to castle or
+            // double step, which makes for a different position
+            //
+            let unmoved = piece.move_count == 0
+                && matches!(
+                    piece.ty,
+                    PieceType::King | PieceType::Rook | PieceType::Pawn
+                );
+
+            let square = (pos.y * board.width() + pos.x) as u64;
+
+            hash ^= key(&[
+                Feature::Piece as u64,
+                player as u64,
+                piece.ty.letter() as u64,
+                unmoved as u64,
+                square,
+            ]);
+        }
+
+        for (ty, count) in board.pocket(player) {
+            let slot = DROPS.iter().position(|t| *t == ty).unwrap_or_default();
+            hash ^= key(&[
+                Feature::Pocket as u64,
+                player as u64,
+                slot as u64,
+                count as u64,
+            ]);
+        }
+
+        let checks = board.checks[player as usize];
+        if checks > 0 {
+            hash ^= key(&[Feature::Checks as u64, player as u64, checks as u64]);
+        }
+    }
+
+    if let Some(pos) = board.en_passant {
+        hash ^= key(&[Feature::EnPassant as u64, pos.x as u64, pos.y as u64]);
+    }
+
+    if turn == Player::Black {
+        hash ^= key(&[Feature::Turn as u64]);
+    }
+
+    hash
+}
+
+enum Feature {
+    Piece = 1,
+    Pocket,
+    Checks,
+    EnPassant,
+    Turn,
+}
+
+/// The random number for one feature, given by its parts.
+///
+fn key(parts: &[u64]) -> u64 {
+    parts
+        .iter()
+        .fold(0, |key, part| mix(key ^ part.wrapping_mul(0x100000001b3)))
+}
+
+/// The SplitMix64 finaliser, which spreads every bit of the input over
+/// the whole output.
+///
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
@@ -1,14 +1,31 @@
 mod board;
+mod bughouse;
+mod editor;
 pub mod engine;
+mod extensions;
+pub mod fen;
 mod gamestate;
+pub mod hash;
+pub mod notation;
+pub mod pgn;
 mod piece;
+mod puzzle;
+pub mod random;
 mod rules;
 mod timer;
+mod validate;
+pub mod variant;
 mod vec;
+mod view;
 
 pub use board::*;
+pub use bughouse::*;
+pub use extensions::*;
 pub use gamestate::*;
 pub use piece::*;
+pub use puzzle::*;
 pub use rules::*;
 pub use timer::*;
+pub use validate::*;
 pub use vec::*;
+pub use view::*;
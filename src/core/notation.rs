@@ -0,0 +1,220 @@
+use super::{Board, Move, MoveResult, PieceType, Player, Vec2};
+
+/// The algebraic name of a square, e.g. `e4`.
+///
+pub fn square_name(board: &Board, pos: Vec2) -> String {
+    format!("{}{}", (b'a' + pos.x as u8) as char, board.height() - pos.y)
+}
+
+/// The square on `board` named by `name`, e.g. `e4` or `j10`.
+///
+pub fn parse_square(board: &Board, name: &str) -> Option<Vec2> {
+    let file = name.chars().next()?;
+    let rank: i16 = name.get(1..)?.parse().ok()?;
+
+    // reject signs and leading zeros the parse would allow
+    //
+    if !name[1..].starts_with(|c: char| ('1'..='9').contains(&c)) {
+        return None;
+    }
+
+    let pos = Vec2::new(file as i16 - 'a' as i16, board.height() - rank);
+
+    Some(pos).filter(|pos| file.is_ascii_lowercase() && board.contains(*pos))
+}
+
+/// Split the square at the start of `text` from whatever follows it.
+///
+fn split_square<'a>(board: &Board, text: &'a str) -> Option<(Vec2, &'a str)> {
+    let end = text
+        .char_indices()
+        .skip(1)
+        .find(|(_, c)| !c.is_ascii_digit())
+        .map_or(text.len(), |(i, _)| i);
+
+    Some((parse_square(board, text.get(..end)?)?, &text[end..]))
+}
+
+/// The piece type for a letter, including any the variant adds.
+///
+fn piece_letter(board: &Board, text: &str) -> Option<PieceType> {
+    let mut chars = text.chars();
+    let c = chars.next()?;
+
+    if chars.next().is_some() {
+        return None;
+    }
+
+    PieceType::from_letter_in(c, board.variant.fairy_pieces())
+}
+
+/// Find the legal move for `player` described by `text`, written either
+/// in SAN (`Nf3`, `exd5`, `e8=Q`, `O-O`, `N@f3`) or in coordinate notation
+/// (`g1f3`, `e7e8q`). Check marks and capture signs are optional.
+///
+pub fn parse_move(board: &Board, player: Player, text: &str) -> Option<(Vec2, Move)> {
+    let moves = board.all_legal_moves(player);
+
+    // drops, where the piece letter may be left out for a pawn
+    //
+    if let Some((piece, square)) = text.split_once('@') {
+        let ty = match piece {
+            "" => PieceType::Pawn,
+            _ => piece_letter(board, piece)?,
+        };
+        let to = parse_square(board, square.trim_end_matches(['+', '#', '!', '?']))?;
+
+        return moves
+            .into_iter()
+            .find(|(_, m)| m.pos == to && m.result == MoveResult::Drop(player, ty));
+    }
+
+    // coordinate notation
+    //
+    let coordinates = split_square(board, text)
+        .and_then(|(from, rest)| split_square(board, rest).map(|(to, rest)| (from, to, rest)));
+
+    if let Some((from, to, rest)) = coordinates {
+        let promotion = match rest {
+            "" => PieceType::Queen,
+            c => piece_letter(board, c)?,
+        };
+
+        return moves.into_iter().find(|(pos, m)| {
+            *pos == from
+                && m.pos == to
+                && match m.result {
+                    MoveResult::Promotion(ty) => ty == promotion,
+                    _ => true,
+                }
+        });
+    }
+
+    // standard algebraic notation
+    //
+    // castling may be written with zeros, e.g. `0-0`
+    //
+    let wanted = if text.starts_with('0') {
+        normalise(&text.replace('0', "O"))
+    } else {
+        normalise(text)
+    };
+
+    moves
+        .into_iter()
+        .find(|(from, m)| normalise(&san(board, *from, m)) == wanted)
+}
+
+fn normalise(san: &str) -> String {
+    san.chars()
+        .filter(|c| !matches!(c, '+' | '#' | '!' | '?' | 'x' | '='))
+        .collect()
+}
+
+/// Describe the move of the piece at `from` in Standard Algebraic
+/// Notation. The board is the position *before* the move is made.
+///
+pub fn san(board: &Board, from: Vec2, m: &Move) -> String {
+    let mut san = String::new();
+
+    let player = match m.result {
+        MoveResult::Drop(player, ty) => {
+            san.push(ty.letter());
+            san.push('@');
+            san.push_str(&square_name(board, m.pos));
+            player
+        }
+        _ => {
+            san_move(board, from, m, &mut san);
+            board.get(from).expect("Piece expected at position").player
+        }
+    };
+
+    // does the move give check, or mate?
+    //
+    let mut after = board.clone();
+    after.make_move(from, m);
+
+    let opponent = player.opponent();
+
+    if after.variant.in_check(&after, opponent) {
+        if after.has_legal_moves(opponent) {
+            san.push('+');
+        } else {
+            san.push('#');
+        }
+    }
+
+    san
+}
+
+/// Append the SAN for a move of the piece at `from`, without any
+/// check or mate suffix.
+///
+fn san_move(board: &Board, from: Vec2, m: &Move, san: &mut String) {
+    let piece = board.get(from).expect("Piece expected at position");
+
+    match m.result {
+        MoveResult::Castle(rook) => {
+            if rook.x > from.x {
+                san.push_str("O-O");
+            } else {
+                san.push_str("O-O-O");
+            }
+        }
+        _ => {
+            let capture = board.is_capture(m);
+
+            if piece.ty == PieceType::Pawn {
+                if capture {
+                    san.push(square_name(board, from).remove(0));
+                }
+            } else {
+                san.push(piece.ty.letter());
+                san.push_str(&disambiguation(board, from, m.pos));
+            }
+
+            if capture {
+                san.push('x');
+            }
+
+            san.push_str(&square_name(board, m.pos));
+
+            if let MoveResult::Promotion(ty) = m.result {
+                san.push('=');
+                san.push(ty.letter());
+            }
+        }
+    }
+}
+
+/// The file and/or rank needed to tell the piece at `from` apart from
+/// other pieces of the same type that could also move to `to`.
+///
+fn disambiguation(board: &Board, from: Vec2, to: Vec2) -> String {
+    let piece = board.get(from).expect("Piece expected at position");
+
+    let others: Vec<Vec2> = board
+        .pieces(piece.player)
+        .filter(|(pos, p)| *pos != from && p.ty == piece.ty)
+        .map(|(pos, _)| pos)
+        .filter(|pos| {
+            board
+                .get_legal_moves(*pos, false)
+                .iter()
+                .any(|m| m.pos == to)
+        })
+        .collect();
+
+    let name = square_name(board, from);
+
+    if others.is_empty() {
+        String::new()
+    } else if others.iter().all(|pos| pos.x != from.x) {
+        name[..1].to_string()
+    } else if others.iter().all(|pos| pos.y != from.y) {
+        name[1..].to_string()
+    } else {
+        name
+    }
+}
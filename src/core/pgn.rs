@@ -0,0 +1,203 @@
+use super::{Outcome, Player};
+
+/// A game read from PGN: its tag pairs, and the moves of the main line
+/// as written, without move numbers, comments or variations.
+///
+#[derive(Clone, Debug, Default)]
+pub struct Game {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<String>,
+    /// The result given after the moves, e.g. `1-0` or `*`.
+    pub result: Option<String>,
+}
+
+impl Game {
+    /// The value of the tag `name`, e.g. `White` or `FEN`.
+    ///
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// Read every game in a PGN collection. Anything that isn't a tag, a
+/// move or a result is skipped, so games are read as far as they can be
+/// rather than rejected.
+///
+pub fn parse(text: &str) -> Vec<Game> {
+    let mut games = Vec::new();
+    let mut game = Game::default();
+
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '[' => {
+                // tags after moves begin the next game
+                //
+                if !game.moves.is_empty() || game.result.is_some() {
+                    games.push(std::mem::take(&mut game));
+                }
+
+                let tag: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                if let Some((name, value)) = tag.trim().split_once(char::is_whitespace) {
+                    let value = value.trim().trim_matches('"').replace("\\\"", "\"");
+                    game.tags.push((name.to_string(), value));
+                }
+            }
+            '{' => {
+                chars.by_ref().find(|c| *c == '}');
+            }
+            ';' => {
+                chars.by_ref().find(|c| *c == '\n');
+            }
+            '(' => {
+                let mut depth = 1;
+                for c in chars.by_ref() {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                }
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut token = String::from(c);
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"{}();[".contains(*c))
+                {
+                    token.push(c);
+                }
+
+                if RESULTS.contains(&token.as_str()) {
+                    game.result = Some(token);
+                    games.push(std::mem::take(&mut game));
+                    continue;
+                }
+
+                // move numbers such as `12.` or `12...`, which may be
+                // written against the move, and NAGs such as `$1`
+                //
+                let number = token
+                    .find(|c: char| !c.is_ascii_digit() && c != '.')
+                    .unwrap_or(token.len());
+                let token = match token[..number].contains('.') {
+                    true => &token[number..],
+                    false => &token,
+                };
+                let token = token.trim_end_matches(['!', '?']);
+
+                if !token.is_empty() && !token.starts_with('$') {
+                    game.moves.push(token.to_string());
+                }
+            }
+        }
+    }
+
+    if !game.moves.is_empty() || !game.tags.is_empty() {
+        games.push(game);
+    }
+
+    games
+}
+
+/// A move to write out, with a NAG such as `$2` (a mistake) and a
+/// comment to follow it.
+///
+#[derive(Clone, Debug, Default)]
+pub struct Annotated {
+    pub san: String,
+    pub nag: Option<u8>,
+    pub comment: Option<String>,
+}
+
+/// The longest line `write` produces.
+///
+const LINE_WIDTH: usize = 80;
+
+/// A game's result the way PGN writes it, `*` while it goes on.
+///
+pub fn result(outcome: Option<Outcome>) -> &'static str {
+    match outcome {
+        Some(Outcome::Win(Player::White, _)) => "1-0",
+        Some(Outcome::Win(Player::Black, _)) => "0-1",
+        Some(Outcome::Draw(_)) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+/// Write a game as PGN: the tags, then the moves numbered from
+/// `first_move` with their annotations, then the result. `black_first`
+/// is for a game set up with black to move.
+///
+pub fn write(
+    tags: &[(String, String)],
+    first_move: u32,
+    black_first: bool,
+    moves: &[Annotated],
+    result: &str,
+) -> String {
+    let mut text = String::new();
+
+    for (name, value) in tags {
+        text += &format!("[{} \"{}\"]\n", name, value.replace('"', "\\\""));
+    }
+    text.push('\n');
+
+    let mut tokens = Vec::new();
+    let mut number = first_move;
+    let mut black = black_first;
+
+    // black's move is numbered too when it doesn't follow white's
+    //
+    let mut interrupted = true;
+
+    for m in moves {
+        match black {
+            false => tokens.push(format!("{}.", number)),
+            true if interrupted => tokens.push(format!("{}...", number)),
+            true => {}
+        }
+
+        tokens.push(m.san.clone());
+        interrupted = false;
+
+        if let Some(nag) = m.nag {
+            tokens.push(format!("${}", nag));
+        }
+        if let Some(comment) = &m.comment {
+            tokens.push(format!("{{{}}}", comment.replace('}', ")")));
+            interrupted = true;
+        }
+
+        if black {
+            number += 1;
+        }
+        black = !black;
+    }
+    tokens.push(result.to_string());
+
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH {
+            text += &line;
+            text.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line += &token;
+    }
+    text += &line;
+    text.push('\n');
+
+    text
+}
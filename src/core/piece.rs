@@ -1,30 +1,153 @@
 use std::fmt::Display;
 
-#[repr(usize)]
-#[derive(Copy, Clone, Debug)]
+use serde::{Deserialize, Serialize};
+
+use super::Vec2;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum PieceType {
-    King = 0,
+    King,
     Queen,
     Bishop,
     Knight,
     Rook,
     Pawn,
+    /// A piece defined by a variant rather than the standard rules.
+    Fairy(&'static FairyPiece),
+}
+
+/// A piece a variant adds to the game, described by how it moves.
+///
+#[derive(Debug, PartialEq, Eq)]
+pub struct FairyPiece {
+    pub name: &'static str,
+    /// The letter used in notation and FEN, in upper case.
+    pub letter: char,
+    /// The symbol drawn on the board.
+    pub glyph: &'static str,
+    pub value: i32,
+    pub moves: &'static [Movement],
 }
 
-#[derive(Copy, Clone, PartialEq)]
+/// One way a piece can move: any number of `steps` in one of the
+/// directions given, up to `limit` squares. A limit of 1 is a leap.
+///
+#[derive(Debug, PartialEq, Eq)]
+pub struct Movement {
+    pub steps: &'static [Vec2],
+    pub limit: usize,
+}
+
+impl Movement {
+    /// Slides any distance, like a rook or bishop.
+    ///
+    pub const RIDE: usize = usize::MAX;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Player {
     White,
     Black,
 }
 
+#[derive(Clone)]
 pub struct Piece {
     pub ty: PieceType,
     pub player: Player,
     pub move_count: usize,
+    /// Whether the piece is a promoted pawn, which becomes a pawn
+    /// again when captured into a Crazyhouse pocket.
+    pub promoted: bool,
+}
+
+impl PieceType {
+    /// Conventional material value in pawns.
+    ///
+    pub fn value(&self) -> i32 {
+        match self {
+            PieceType::King => 0,
+            PieceType::Queen => 9,
+            PieceType::Bishop => 3,
+            PieceType::Knight => 3,
+            PieceType::Rook => 5,
+            PieceType::Pawn => 1,
+            PieceType::Fairy(f) => f.value,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PieceType::King => "King",
+            PieceType::Queen => "Queen",
+            PieceType::Bishop => "Bishop",
+            PieceType::Knight => "Knight",
+            PieceType::Rook => "Rook",
+            PieceType::Pawn => "Pawn",
+            PieceType::Fairy(f) => f.name,
+        }
+    }
+
+    /// The letter used for the piece in algebraic notation.
+    ///
+    pub fn letter(&self) -> char {
+        match self {
+            PieceType::King => 'K',
+            PieceType::Queen => 'Q',
+            PieceType::Bishop => 'B',
+            PieceType::Knight => 'N',
+            PieceType::Rook => 'R',
+            PieceType::Pawn => 'P',
+            PieceType::Fairy(f) => f.letter,
+        }
+    }
+
+    /// The standard piece type for a letter in either case, e.g. `n`
+    /// or `N`. Fairy pieces are looked up with [`PieceType::from_letter_in`].
+    ///
+    pub fn from_letter(c: char) -> Option<PieceType> {
+        match c.to_ascii_uppercase() {
+            'K' => Some(PieceType::King),
+            'Q' => Some(PieceType::Queen),
+            'B' => Some(PieceType::Bishop),
+            'N' => Some(PieceType::Knight),
+            'R' => Some(PieceType::Rook),
+            'P' => Some(PieceType::Pawn),
+            _ => None,
+        }
+    }
+
+    /// The piece type for a letter, including the `fairy` pieces.
+    ///
+    pub fn from_letter_in(c: char, fairy: &'static [FairyPiece]) -> Option<PieceType> {
+        PieceType::from_letter(c).or_else(|| {
+            fairy
+                .iter()
+                .find(|f| f.letter == c.to_ascii_uppercase())
+                .map(PieceType::Fairy)
+        })
+    }
+}
+
+impl Player {
+    pub fn opponent(&self) -> Player {
+        match self {
+            Player::White => Player::Black,
+            Player::Black => Player::White,
+        }
+    }
+}
+
+impl Display for Player {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            Player::White => "White",
+            Player::Black => "Black",
+        })
+    }
 }
 
 impl Display for Piece {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self.ty)
+        f.write_str(self.ty.name())
     }
 }
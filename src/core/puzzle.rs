@@ -0,0 +1,490 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{hash::position_hash, notation, Chess, Mode, Move, Outcome, Player, Vec2};
+
+/// The rating a new solver starts at, and that puzzles without one are
+/// taken to have.
+///
+const START_RATING: f64 = 1500.0;
+
+/// How far one puzzle moves the solver's rating, at most.
+///
+const K_FACTOR: f64 = 32.0;
+
+/// A tactics puzzle: a position and the moves that solve it. As in the
+/// Lichess puzzle database, the first move is the opponent's, played to
+/// reach the puzzle, and the solver's and opponent's moves alternate
+/// from there.
+///
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    pub id: String,
+    pub fen: String,
+    /// The moves in coordinate notation or SAN.
+    pub moves: Vec<String>,
+    pub rating: Option<u32>,
+    pub themes: Vec<String>,
+}
+
+impl Puzzle {
+    /// Read a puzzle from a line of CSV: either the Lichess columns
+    /// `PuzzleId,FEN,Moves,Rating,...,Themes,...` or just `FEN,Moves`.
+    /// Header lines and anything else without a FEN are skipped.
+    ///
+    pub fn parse(line: &str, number: usize) -> Option<Puzzle> {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+
+        // the FEN is the only field with slashes in it
+        //
+        let at = fields.iter().position(|f| f.contains('/'))?;
+        let moves: Vec<String> = fields
+            .get(at + 1)?
+            .split_whitespace()
+            .map(String::from)
+            .collect();
+        if moves.len() < 2 {
+            return None;
+        }
+
+        let id = match at {
+            0 => number.to_string(),
+            _ => fields[0].to_string(),
+        };
+
+        Some(Puzzle {
+            id,
+            fen: fields[at].to_string(),
+            moves,
+            rating: fields.get(at + 2).and_then(|r| r.parse().ok()),
+            themes: fields
+                .get(at + 6)
+                .map(|t| t.split_whitespace().map(String::from).collect())
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// The solver's record, kept beside the puzzle file.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PuzzleStats {
+    pub rating: f64,
+    /// Puzzles solved in a row without a mistake.
+    pub streak: u32,
+    pub best_streak: u32,
+    pub solved: u32,
+    pub failed: u32,
+    /// The puzzle to carry on from.
+    pub next: usize,
+}
+
+impl Default for PuzzleStats {
+    fn default() -> Self {
+        Self {
+            rating: START_RATING,
+            streak: 0,
+            best_streak: 0,
+            solved: 0,
+            failed: 0,
+            next: 0,
+        }
+    }
+}
+
+impl PuzzleStats {
+    /// Rate the solver on a puzzle of `rating`, as an Elo game won when
+    /// it was solved and lost when it wasn't.
+    ///
+    fn record(&mut self, rating: Option<u32>, solved: bool) {
+        let rating = rating.map_or(START_RATING, |r| r as f64);
+        let expected = 1.0 / (1.0 + 10f64.powf((rating - self.rating) / 400.0));
+        let score = if solved { 1.0 } else { 0.0 };
+
+        self.rating += K_FACTOR * (score - expected);
+
+        if solved {
+            self.solved += 1;
+            self.streak += 1;
+            self.best_streak = self.best_streak.max(self.streak);
+        } else {
+            self.failed += 1;
+            self.streak = 0;
+        }
+    }
+}
+
+/// A file of puzzles being worked through, and where the solver is in
+/// the current one.
+///
+pub struct Puzzles {
+    puzzles: Vec<Puzzle>,
+    /// Where the stats are saved, if anywhere.
+    stats_path: Option<PathBuf>,
+    pub stats: PuzzleStats,
+    /// The puzzle on the board.
+    pub index: usize,
+    /// The side the solver plays.
+    pub player: Player,
+    /// The number of the puzzle's moves played so far.
+    ply: usize,
+    /// Set once the solver has gone wrong, which counts against them
+    /// even if they go on to find the solution.
+    pub failed: bool,
+    pub solved: bool,
+}
+
+impl Puzzles {
+    /// Read the puzzles in a CSV file, and the solver's stats from the
+    /// file beside it, if there is one yet.
+    ///
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Puzzles> {
+        let text = std::fs::read_to_string(path.as_ref())?;
+        let puzzles: Vec<Puzzle> = text
+            .lines()
+            .enumerate()
+            .filter_map(|(i, line)| Puzzle::parse(line, i + 1))
+            .collect();
+
+        if puzzles.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "no puzzles found",
+            ));
+        }
+
+        let mut stats_path = path.as_ref().as_os_str().to_owned();
+        stats_path.push(".stats");
+        let stats_path = PathBuf::from(stats_path);
+
+        let stats = std::fs::read_to_string(&stats_path)
+            .ok()
+            .and_then(|data| toml::from_str(&data).ok())
+            .unwrap_or_default();
+
+        Ok(Puzzles::new(puzzles, stats, Some(stats_path)))
+    }
+
+    pub fn new(puzzles: Vec<Puzzle>, stats: PuzzleStats, stats_path: Option<PathBuf>) -> Self {
+        Self {
+            index: stats.next % puzzles.len().max(1),
+            puzzles,
+            stats_path,
+            stats,
+            player: Player::White,
+            ply: 0,
+            failed: false,
+            solved: false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.puzzles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.puzzles.is_empty()
+    }
+
+    /// The puzzle on the board.
+    ///
+    pub fn current(&self) -> &Puzzle {
+        &self.puzzles[self.index]
+    }
+
+    /// The move of the solution to be played next, if there is one.
+    ///
+    fn expected(&self) -> Option<&str> {
+        self.current().moves.get(self.ply).map(String::as_str)
+    }
+
+    /// Count the current puzzle as solved or failed, unless it already
+    /// has been.
+    ///
+    fn record(&mut self, solved: bool) {
+        if self.failed {
+            return;
+        }
+
+        let rating = self.current().rating;
+        self.stats.record(rating, solved);
+        self.failed = !solved;
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.stats_path else {
+            return Ok(());
+        };
+
+        let data = toml::to_string(&self.stats).map_err(|err| err.to_string())?;
+        std::fs::write(path, data)
+            .map_err(|err| format!("Unable to save puzzle stats to {}: {}", path.display(), err))
+    }
+}
+
+/// Solving tactics puzzles: the solver's moves are checked against the
+/// solution as they are played, and the opponent's replies are played
+/// for them.
+///
+impl Chess {
+    /// Work through the puzzles in a file, starting from the one after
+    /// the last puzzle tried.
+    ///
+    pub fn open_puzzles(&mut self, path: &str) -> Result<(), String> {
+        let puzzles = Puzzles::open(path)
+            .map_err(|err| format!("Unable to load puzzles from {}: {}", path, err))?;
+
+        self.puzzles = Some(puzzles);
+        self.load_puzzle()
+    }
+
+    pub fn is_puzzle(&self) -> bool {
+        self.puzzles.is_some()
+    }
+
+    /// Move on to the next puzzle, a puzzle given up on counting as failed.
+    ///
+    pub fn next_puzzle(&mut self) {
+        let Some(puzzles) = &mut self.puzzles else {
+            return;
+        };
+
+        if !puzzles.solved {
+            puzzles.record(false);
+        }
+        puzzles.index = (puzzles.index + 1) % puzzles.len();
+
+        if let Err(msg) = self.load_puzzle() {
+            self.view.message = Some(msg);
+        }
+    }
+
+    /// Set up the current puzzle and play the opponent's first move.
+    /// Puzzles that can't be set up are skipped.
+    ///
+    fn load_puzzle(&mut self) -> Result<(), String> {
+        let Some(mut puzzles) = self.puzzles.take() else {
+            return Ok(());
+        };
+
+        for _ in 0..puzzles.len() {
+            let puzzle = puzzles.current();
+            if let Ok(mut game) = Chess::from_fen_variant(&puzzle.fen, self.board.variant.clone()) {
+                let first = puzzle.moves[0].clone();
+
+                puzzles.player = game.turn.opponent();
+                puzzles.ply = 1;
+                puzzles.failed = false;
+                puzzles.solved = false;
+                puzzles.stats.next = (puzzles.index + 1) % puzzles.len();
+
+                // the puzzle's game takes over everything that isn't
+                // the position
+                //
+                game.view = std::mem::take(&mut self.view);
+                game.extensions = self.extensions.fork();
+                game.view.perspective = puzzles.player;
+                game.view.follow_turn = false;
+                game.view.hint = None;
+                *self = game;
+
+                if self.play_notation(&first) {
+                    self.view.message = Some(format!("{} to play and win", puzzles.player));
+                    let saved = puzzles.save();
+                    self.puzzles = Some(puzzles);
+                    return saved;
+                }
+            }
+
+            puzzles.index = (puzzles.index + 1) % puzzles.len();
+        }
+
+        Err("None of the puzzles could be set up".into())
+    }
+
+    /// Whether a move may be played: in a puzzle the solver's move has to
+    /// be the one in the solution, or any move that mates. A wrong move
+    /// isn't played, and fails the puzzle.
+    ///
+    pub(super) fn puzzle_allows(&mut self, from: Vec2, m: &Move) -> bool {
+        let Some(puzzles) = &mut self.puzzles else {
+            return true;
+        };
+
+        if self.turn != puzzles.player {
+            return true;
+        }
+
+        if puzzles.solved {
+            self.view.message = Some("Solved, on to the next puzzle".into());
+            return false;
+        }
+
+        let expected = puzzles
+            .expected()
+            .and_then(|text| notation::parse_move(&self.board, self.turn, text));
+        let right =
+            expected.is_some_and(|(f, e)| f == from && e.pos == m.pos && e.result == m.result);
+
+        let mut board = self.board.clone();
+        board.make_move(from, m);
+        let mates = matches!(
+            board.variant.outcome(&board, self.turn.opponent()),
+            Some(Outcome::Win(player, _)) if player == self.turn
+        );
+
+        if right || mates {
+            puzzles.ply += 1;
+            return true;
+        }
+
+        puzzles.record(false);
+        self.view.message = Some(match puzzles.save() {
+            Ok(()) => "That's not it, try again".into(),
+            Err(msg) => msg,
+        });
+        self.mode = Mode::Selecting;
+
+        false
+    }
+
+    /// After a move of the puzzle, play the opponent's reply, or finish
+    /// the puzzle when there are no moves left.
+    ///
+    pub(super) fn puzzle_reply(&mut self) {
+        let Some(puzzles) = &mut self.puzzles else {
+            return;
+        };
+
+        if puzzles.solved || self.turn == puzzles.player {
+            return;
+        }
+
+        match puzzles.expected().map(String::from) {
+            Some(reply) if self.outcome.is_none() => {
+                puzzles.ply += 1;
+                self.play_notation(&reply);
+            }
+            _ => {
+                puzzles.record(true);
+                puzzles.solved = true;
+
+                let stats = &puzzles.stats;
+                let message = match puzzles.failed {
+                    true => "Solved, but not at the first try".to_string(),
+                    false => format!(
+                        "Solved! Rating {:.0}, streak {}",
+                        stats.rating, stats.streak
+                    ),
+                };
+                self.view.message = Some(match puzzles.save() {
+                    Ok(()) => message,
+                    Err(msg) => msg,
+                });
+            }
+        }
+    }
+
+    /// Show the next move of the solution as a hint, which fails the
+    /// puzzle. Returns false outside a puzzle.
+    ///
+    pub(crate) fn puzzle_hint(&mut self) -> bool {
+        let Some(puzzles) = &mut self.puzzles else {
+            return false;
+        };
+
+        let expected = puzzles
+            .expected()
+            .and_then(|text| notation::parse_move(&self.board, self.turn, text));
+
+        let Some((from, m)) = expected.filter(|_| !puzzles.solved && self.turn == puzzles.player)
+        else {
+            return true;
+        };
+
+        puzzles.record(false);
+        if let Err(msg) = puzzles.save() {
+            self.view.message = Some(msg);
+            return true;
+        }
+
+        self.view.hint = Some((position_hash(&self.board, self.turn), from, m.pos));
+        self.view.message = Some(format!("Hint: {}", notation::san(&self.board, from, &m)));
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lichess_lines() {
+        let line = "00sHx,q3k1nr/1pp1nQpp/3p4/1P2p3/4P3/B1PP1b2/B5PP/5K2 b k - 0 17,\
+            e8d7 a2e6 d7d8 f7f8,1760,80,83,72,mate mateIn2 middlegame short,\
+            https://lichess.org/yyznGmXs/black#34,Italian_Game Italian_Game_Classical_Variation";
+        let puzzle = Puzzle::parse(line, 7).unwrap();
+
+        assert_eq!(puzzle.id, "00sHx");
+        assert_eq!(
+            puzzle.fen,
+            "q3k1nr/1pp1nQpp/3p4/1P2p3/4P3/B1PP1b2/B5PP/5K2 b k - 0 17"
+        );
+        assert_eq!(puzzle.moves, ["e8d7", "a2e6", "d7d8", "f7f8"]);
+        assert_eq!(puzzle.rating, Some(1760));
+        assert_eq!(puzzle.themes, ["mate", "mateIn2", "middlegame", "short"]);
+    }
+
+    #[test]
+    fn bare_lines() {
+        let line = "4k3/8/8/8/8/8/4R3/4K3 b - - 0 1, e8d8 e2e8";
+        let puzzle = Puzzle::parse(line, 3).unwrap();
+
+        assert_eq!(puzzle.id, "3");
+        assert_eq!(puzzle.fen, "4k3/8/8/8/8/8/4R3/4K3 b - - 0 1");
+        assert_eq!(puzzle.moves, ["e8d8", "e2e8"]);
+        assert_eq!(puzzle.rating, None);
+        assert!(puzzle.themes.is_empty());
+    }
+
+    #[test]
+    fn skipped_lines() {
+        let header = "PuzzleId,FEN,Moves,Rating,RatingDeviation,Popularity,NbPlays,\
+            Themes,GameUrl,OpeningTags";
+        assert!(Puzzle::parse(header, 1).is_none());
+        assert!(Puzzle::parse("", 2).is_none());
+
+        // a puzzle needs the opponent's move and the solver's answer
+        //
+        assert!(Puzzle::parse("4k3/8/8/8/8/8/4R3/4K3 b - - 0 1,e8d8", 3).is_none());
+    }
+
+    #[test]
+    fn ratings_and_streaks() {
+        let mut stats = PuzzleStats::default();
+
+        // against an equal rating, a solve and a miss are each worth half
+        // the K factor
+        //
+        stats.record(Some(1500), true);
+        assert_eq!(stats.rating, START_RATING + K_FACTOR / 2.0);
+        stats.record(None, true);
+        assert!(stats.rating > START_RATING + K_FACTOR / 2.0);
+        assert_eq!((stats.streak, stats.best_streak), (2, 2));
+
+        let before = stats.rating;
+        stats.record(Some(before as u32), false);
+        assert!((stats.rating - (before - K_FACTOR / 2.0)).abs() < 1.0);
+        assert_eq!((stats.streak, stats.best_streak), (0, 2));
+
+        stats.record(Some(3000), true);
+        assert_eq!((stats.solved, stats.failed), (3, 1));
+        assert_eq!((stats.streak, stats.best_streak), (1, 2));
+    }
+}
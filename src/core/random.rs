@@ -0,0 +1,14 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// A random number in `0..bound`. Each call draws on a freshly keyed
+/// `RandomState`, which is plenty for picking start positions or book
+/// moves without pulling in a random number crate.
+///
+pub fn below(bound: u64) -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(bound);
+    hasher.finish() % bound.max(1)
+}
@@ -1,16 +1,95 @@
-use super::{PieceType, Vec2};
+use serde::{Deserialize, Serialize};
+
+use super::{Board, PieceType, Player, Vec2};
 
 #[allow(dead_code)]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum MoveResult {
     Cancel,
     Nothing,
     Capture(Vec2),
-    Castle,
+    /// Castle with the rook at the given position.
+    Castle(Vec2),
     Promotion(PieceType),
+    /// Drop a piece of the player's from their pocket onto an empty
+    /// square, as in Crazyhouse. The move's origin is its destination.
+    Drop(Player, PieceType),
     Invalid,
 }
 
+/// A move that has been played, as kept in the game history.
+///
+pub struct MoveRecord {
+    pub player: Player,
+    pub piece: PieceType,
+    pub from: Vec2,
+    pub to: Vec2,
+    pub result: MoveResult,
+    pub captured: Option<PieceType>,
+    pub san: String,
+    /// The position before the move was played.
+    pub board: Board,
+    /// Whether the player asked for a hint before playing the move.
+    pub hinted: bool,
+}
+
+/// Why a game came to an end.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Reason {
+    Checkmate,
+    Resignation,
+    Stalemate,
+    Agreement,
+    KingOfTheHill,
+    ThreeChecks,
+    Race,
+    NoPieces,
+    Explosion,
+    Timeout,
+    /// The game on the other bughouse board was decided first.
+    OtherBoard,
+}
+
+impl std::fmt::Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Reason::Checkmate => "Checkmate",
+            Reason::Resignation => "Resignation",
+            Reason::Stalemate => "Stalemate",
+            Reason::Agreement => "Agreement",
+            Reason::KingOfTheHill => "King of the hill",
+            Reason::ThreeChecks => "Three checks",
+            Reason::Race => "Race won",
+            Reason::NoPieces => "No pieces left",
+            Reason::Explosion => "King exploded",
+            Reason::Timeout => "Out of time",
+            Reason::OtherBoard => "Decided on the other board",
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Outcome {
+    Win(Player, Reason),
+    Draw(Reason),
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Win(player, Reason::Resignation) => {
+                write!(f, "{} resigns, {} wins", player.opponent(), player)
+            }
+            Outcome::Win(player, reason) => write!(f, "{}, {} wins", reason, player),
+            Outcome::Draw(Reason::Agreement) => write!(f, "Draw agreed"),
+            Outcome::Draw(Reason::Race) => write!(f, "Both kings home, draw"),
+            Outcome::Draw(reason) => write!(f, "{}, draw", reason),
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
 pub struct Move {
     pub pos: Vec2,
     pub result: MoveResult,
@@ -35,6 +114,13 @@ impl Move {
         }
     }
 
+    pub fn drop(pos: Vec2, player: Player, ty: PieceType) -> Self {
+        Self {
+            pos,
+            result: MoveResult::Drop(player, ty),
+        }
+    }
+
     pub fn null(pos: Vec2) -> Self {
         Self {
             pos,
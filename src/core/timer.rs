@@ -1,4 +1,7 @@
-use std::{fmt::Display, time::Instant};
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
 
 pub struct CountdownTimer {
     remaining: usize,
@@ -6,7 +9,7 @@ pub struct CountdownTimer {
 }
 
 impl CountdownTimer {
-    pub fn new(time: std::time::Duration) -> Self {
+    pub fn new(time: Duration) -> Self {
         Self {
             remaining: time.as_millis() as usize,
             start: None,
@@ -18,14 +21,26 @@ impl CountdownTimer {
     }
 
     pub fn stop(&mut self) {
-        let elapsed = self.start.unwrap().elapsed();
-        self.remaining = self.remaining.saturating_sub(elapsed.as_millis() as usize);
-        self.start = None;
+        if let Some(start) = self.start.take() {
+            let elapsed = start.elapsed();
+            self.remaining = self.remaining.saturating_sub(elapsed.as_millis() as usize);
+        }
+    }
+
+    /// Set the time left, as when the clock is kept elsewhere. A running
+    /// timer keeps running from the new time.
+    ///
+    pub fn set_remaining(&mut self, time: Duration) {
+        self.remaining = time.as_millis() as usize;
+        if self.start.is_some() {
+            self.start = Some(Instant::now());
+        }
     }
 
     pub fn remaining(&self) -> usize {
         if let Some(timer) = self.start {
-            self.remaining - (timer.elapsed().as_millis() as usize)
+            self.remaining
+                .saturating_sub(timer.elapsed().as_millis() as usize)
         } else {
             self.remaining
         }
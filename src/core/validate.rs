@@ -0,0 +1,249 @@
+use std::fmt::Display;
+
+use super::board::pawn_direction;
+use super::{notation, Board, PieceType, Player};
+
+/// Something that makes a position impossible to have reached, or to
+/// play on from.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum Problem {
+    MissingKing(Player),
+    ExtraKings(Player, usize),
+    /// A pawn on a rank it can never stand on, on the square named.
+    PawnOnBackRank(Player, String),
+    TooManyPawns(Player, usize),
+    /// More pieces beyond the starting set than pawns gone to promote.
+    TooManyPromotions(Player),
+    /// The player is in check, but it is the other player's move.
+    WaitingInCheck(Player),
+    /// The player is in check from more pieces than one move can give.
+    TooManyCheckers(Player, usize),
+    /// The player's last move could not have left the position, under
+    /// the rules of the variant.
+    Unreachable(Player),
+    /// No pawn can just have double stepped past the named square.
+    EnPassant(String),
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::MissingKing(p) => write!(f, "{} has no king", p),
+            Problem::ExtraKings(p, n) => write!(f, "{} has {} kings", p, n),
+            Problem::PawnOnBackRank(p, sq) => write!(f, "{} has a pawn on {}", p, sq),
+            Problem::TooManyPawns(p, n) => write!(f, "{} has {} pawns", p, n),
+            Problem::TooManyPromotions(p) => {
+                write!(f, "{} has more promoted pieces than missing pawns", p)
+            }
+            Problem::WaitingInCheck(p) => {
+                write!(f, "{} is in check but it is not their move", p)
+            }
+            Problem::TooManyCheckers(p, n) => write!(f, "{} is in check from {} pieces", p, n),
+            Problem::Unreachable(p) => write!(f, "{}'s last move can't have led here", p),
+            Problem::EnPassant(sq) => write!(f, "no pawn has just passed {}", sq),
+        }
+    }
+}
+
+/// Everything wrong with the position on `board` with `turn` to move,
+/// judged by the rules of its variant and against the variant's start
+/// position. A position with no problems can be played on.
+///
+pub fn validate(board: &Board, turn: Player) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    let variant = board.variant.clone();
+    let start = variant.start_position();
+
+    for player in [Player::White, Player::Black] {
+        let count =
+            |board: &Board, ty: PieceType| board.pieces(player).filter(|(_, p)| p.ty == ty).count();
+
+        // a side that starts without a king, as in Horde, needs none
+        //
+        if variant.royal_king() && start.find_king(player).is_some() {
+            match count(board, PieceType::King) {
+                0 => problems.push(Problem::MissingKing(player)),
+                1 => {}
+                n => problems.push(Problem::ExtraKings(player, n)),
+            }
+        }
+
+        // pawns promote on the far rank, and only stand on their own
+        // back rank if they start there
+        //
+        let starts_on_home_rank = start
+            .pieces(player)
+            .any(|(pos, p)| p.ty == PieceType::Pawn && pos.y == start.home_rank(player));
+
+        for (pos, piece) in board.pieces(player) {
+            if piece.ty == PieceType::Pawn
+                && (pos.y == board.home_rank(player.opponent())
+                    || (pos.y == board.home_rank(player) && !starts_on_home_rank))
+            {
+                let square = notation::square_name(board, pos);
+                problems.push(Problem::PawnOnBackRank(player, square));
+            }
+        }
+
+        // with pockets, captured pieces change sides, so there is no
+        // telling how many each player may have
+        //
+        if variant.has_pockets() {
+            continue;
+        }
+
+        let start_pawns = count(&start, PieceType::Pawn);
+        let pawns = count(board, PieceType::Pawn);
+
+        if pawns > start_pawns {
+            problems.push(Problem::TooManyPawns(player, pawns));
+            continue;
+        }
+
+        let mut types: Vec<PieceType> = Vec::new();
+        for (_, piece) in start.pieces(player).chain(board.pieces(player)) {
+            if piece.ty != PieceType::Pawn && !types.contains(&piece.ty) {
+                types.push(piece.ty);
+            }
+        }
+
+        let promoted: usize = types
+            .into_iter()
+            .filter(|ty| *ty != PieceType::King || !variant.royal_king())
+            .map(|ty| count(board, ty).saturating_sub(count(&start, ty)))
+            .sum();
+
+        if promoted > start_pawns - pawns {
+            problems.push(Problem::TooManyPromotions(player));
+        }
+    }
+
+    // checks only make sense once each side has its one king
+    //
+    if !problems.is_empty() {
+        return problems;
+    }
+
+    let mover = turn.opponent();
+
+    if variant.in_check(board, mover) {
+        problems.push(Problem::WaitingInCheck(mover));
+    } else if !variant.is_legal(board, mover) {
+        problems.push(Problem::Unreachable(mover));
+    }
+
+    if let Some(king) = board.find_king(turn) {
+        if variant.in_check(board, turn) {
+            let checkers = board
+                .pieces(mover)
+                .filter(|(pos, _)| board.attacks(*pos).contains(&king))
+                .count();
+
+            if checkers > 2 {
+                problems.push(Problem::TooManyCheckers(turn, checkers));
+            }
+        }
+    }
+
+    if let Some(pos) = board.en_passant {
+        let forward = pawn_direction(mover);
+
+        let passed = board.get(pos).is_none()
+            && board.get(pos + forward * -1).is_none()
+            && matches!(board.get(pos + forward),
+                Some(p) if p.ty == PieceType::Pawn && p.player == mover);
+
+        if !passed {
+            problems.push(Problem::EnPassant(notation::square_name(board, pos)));
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::fen;
+
+    fn problems(text: &str) -> Vec<Problem> {
+        let fen = fen::parse(text).unwrap();
+        validate(&fen.board, fen.turn)
+    }
+
+    #[test]
+    fn legal_positions() {
+        assert!(problems("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_empty());
+        assert!(problems("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").is_empty());
+        assert!(problems("4k3/8/3N4/8/8/8/8/4RK2 b - - 0 1").is_empty());
+    }
+
+    #[test]
+    fn kings() {
+        assert_eq!(
+            problems("8/8/8/8/8/8/8/4K3 w - - 0 1"),
+            [Problem::MissingKing(Player::Black)]
+        );
+        assert_eq!(
+            problems("4k3/8/8/8/8/8/8/2K1K3 w - - 0 1"),
+            [Problem::ExtraKings(Player::White, 2)]
+        );
+    }
+
+    #[test]
+    fn pawns_on_the_back_ranks() {
+        assert_eq!(
+            problems("P3k3/8/8/8/8/8/8/4K3 w - - 0 1"),
+            [Problem::PawnOnBackRank(Player::White, "a8".into())]
+        );
+        assert_eq!(
+            problems("4k3/8/8/8/8/8/8/4K2p w - - 0 1"),
+            [Problem::PawnOnBackRank(Player::Black, "h1".into())]
+        );
+    }
+
+    #[test]
+    fn material() {
+        assert_eq!(
+            problems("4k3/8/P7/PPPPPPPP/8/8/8/4K3 w - - 0 1"),
+            [Problem::TooManyPawns(Player::White, 9)]
+        );
+
+        // a second queen with all eight pawns still on the board
+        //
+        assert_eq!(
+            problems("qq2k3/pppppppp/8/8/8/8/8/4K3 w - - 0 1"),
+            [Problem::TooManyPromotions(Player::Black)]
+        );
+        assert!(problems("qq2k3/ppppppp1/8/8/8/8/8/4K3 w - - 0 1").is_empty());
+    }
+
+    #[test]
+    fn checks() {
+        assert_eq!(
+            problems("4k3/8/8/8/8/8/8/4RK2 w - - 0 1"),
+            [Problem::WaitingInCheck(Player::Black)]
+        );
+        assert_eq!(
+            problems("4k3/8/3N4/1B6/8/8/8/4RK2 b - - 0 1"),
+            [Problem::TooManyCheckers(Player::Black, 3)]
+        );
+    }
+
+    #[test]
+    fn en_passant() {
+        assert_eq!(
+            problems("4k3/8/8/8/8/8/8/4K3 b - e3 0 1"),
+            [Problem::EnPassant("e3".into())]
+        );
+
+        // the pawn is there, but it is the wrong side's move
+        //
+        assert_eq!(
+            problems("4k3/8/8/8/4P3/8/8/4K3 w - e3 0 1"),
+            [Problem::EnPassant("e3".into())]
+        );
+    }
+}
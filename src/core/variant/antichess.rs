@@ -0,0 +1,148 @@
+use std::sync::Arc;
+
+use super::{Standard, Variant};
+use crate::core::{Board, Move, MoveResult, Outcome, PieceType, Player, Reason, Vec2};
+
+/// Antichess, also known as giveaway or losing chess: captures are
+/// compulsory, the king is an ordinary piece that can be captured, and
+/// a player wins by losing all of their pieces or by being stalemated.
+///
+pub struct Antichess;
+
+impl Variant for Antichess {
+    fn name(&self) -> &'static str {
+        "antichess"
+    }
+
+    fn start_position(&self) -> Board {
+        let mut board = Standard.start_position();
+        board.variant = Arc::new(Antichess);
+
+        // there is no castling, so take the right away from the
+        // kings and rooks.
+        //
+        let castlers: Vec<Vec2> = [Player::White, Player::Black]
+            .into_iter()
+            .flat_map(|player| board.pieces(player))
+            .filter(|(_, p)| matches!(p.ty, PieceType::King | PieceType::Rook))
+            .map(|(pos, _)| pos)
+            .collect();
+
+        for pos in castlers {
+            if let Some(piece) = board.get_mut(pos) {
+                piece.move_count = 1;
+            }
+        }
+
+        board
+    }
+
+    fn in_check(&self, _board: &Board, _player: Player) -> bool {
+        false
+    }
+
+    fn royal_king(&self) -> bool {
+        false
+    }
+
+    fn valid_moves(&self, board: &Board, pos: Vec2, moves: &mut Vec<Move>) {
+        let player = match board.get(pos) {
+            Some(piece) => piece.player,
+            None => return,
+        };
+
+        moves.retain(|m| !matches!(m.result, MoveResult::Castle(_)));
+
+        // pawns may also promote to a king
+        //
+        let kings: Vec<Move> = moves
+            .iter()
+            .filter(|m| m.result == MoveResult::Promotion(PieceType::Queen))
+            .map(|m| Move::new(m.pos, MoveResult::Promotion(PieceType::King)))
+            .collect();
+        moves.extend(kings);
+
+        // captures are compulsory
+        //
+        if board.has_capture(player) {
+            moves.retain(|m| m.result == MoveResult::Cancel || board.is_capture(m));
+        }
+    }
+
+    fn is_legal(&self, _after: &Board, _player: Player) -> bool {
+        true
+    }
+
+    fn outcome(&self, board: &Board, turn: Player) -> Option<Outcome> {
+        if board.pieces(turn).next().is_none() {
+            Some(Outcome::Win(turn, Reason::NoPieces))
+        } else if !board.has_legal_moves(turn) {
+            Some(Outcome::Win(turn, Reason::Stalemate))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::board::tests::assert_variant_perft;
+    use crate::core::fen;
+
+    fn parse(text: &str) -> (Board, Player) {
+        let fen = fen::parse_variant(text, Arc::new(Antichess)).unwrap();
+        (fen.board, fen.turn)
+    }
+
+    #[test]
+    fn perft() {
+        assert_variant_perft(
+            "antichess",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+            &[20, 400, 8067],
+        );
+    }
+
+    #[test]
+    fn captures_are_compulsory() {
+        let (board, turn) = parse("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1");
+        let moves = board.all_legal_moves(turn);
+
+        assert_eq!(moves.len(), 1);
+        assert_eq!(
+            (moves[0].0, moves[0].1.pos),
+            (Vec2::new(4, 4), Vec2::new(3, 3))
+        );
+    }
+
+    #[test]
+    fn pawns_promote_to_kings() {
+        let (board, turn) = parse("8/P7/8/8/8/8/8/k7 w - - 0 1");
+        let promotions: Vec<MoveResult> = board
+            .all_legal_moves(turn)
+            .into_iter()
+            .map(|(_, m)| m.result)
+            .collect();
+
+        assert_eq!(promotions.len(), 5);
+        assert!(promotions.contains(&MoveResult::Promotion(PieceType::King)));
+    }
+
+    #[test]
+    fn losing_everything_wins() {
+        let (board, turn) = parse("8/8/8/8/8/8/8/k7 w - - 0 1");
+        assert_eq!(
+            Antichess.outcome(&board, turn),
+            Some(Outcome::Win(Player::White, Reason::NoPieces))
+        );
+
+        // a blocked pawn has no moves, which wins too
+        //
+        let (board, turn) = parse("8/8/8/8/8/p7/P7/8 w - - 0 1");
+        assert_eq!(
+            Antichess.outcome(&board, turn),
+            Some(Outcome::Win(Player::White, Reason::Stalemate))
+        );
+    }
+}
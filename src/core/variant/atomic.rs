@@ -0,0 +1,183 @@
+use std::sync::Arc;
+
+use super::{standard_outcome, Standard, Variant};
+use crate::core::{Board, Move, MoveResult, Outcome, Piece, PieceType, Player, Reason, Vec2};
+
+/// Atomic: every capture sets off an explosion that removes the
+/// capturing piece and all pieces other than pawns next to the capture
+/// square. Kings may not capture, and blowing up the enemy king wins.
+///
+pub struct Atomic;
+
+impl Variant for Atomic {
+    fn name(&self) -> &'static str {
+        "atomic"
+    }
+
+    fn start_position(&self) -> Board {
+        let mut board = Standard.start_position();
+        board.variant = Arc::new(Atomic);
+        board
+    }
+
+    /// Kings next to each other can not be captured, as the
+    /// capture would blow up both of them.
+    ///
+    fn in_check(&self, board: &Board, player: Player) -> bool {
+        match (board.find_king(player), board.find_king(player.opponent())) {
+            (Some(king), Some(enemy)) => {
+                !adjacent(king, enemy) && board.is_attacked(king, player.opponent())
+            }
+            _ => false,
+        }
+    }
+
+    fn valid_moves(&self, board: &Board, pos: Vec2, moves: &mut Vec<Move>) {
+        if matches!(board.get(pos), Some(p) if p.ty == PieceType::King) {
+            moves.retain(|m| !matches!(m.result, MoveResult::Capture(_)));
+        }
+    }
+
+    fn is_legal(&self, after: &Board, player: Player) -> bool {
+        // blowing up the enemy king wins, even when in check
+        //
+        match after.find_king(player) {
+            Some(_) if after.find_king(player.opponent()).is_none() => true,
+            Some(_) => !self.in_check(after, player),
+            None => false,
+        }
+    }
+
+    fn after_move(
+        &self,
+        board: &mut Board,
+        _player: Player,
+        _from: Vec2,
+        m: &Move,
+        taken: Option<&Piece>,
+    ) {
+        if taken.is_none() {
+            return;
+        }
+
+        for pos in blast_area(board, m.pos) {
+            let survives =
+                pos != m.pos && matches!(board.get(pos), Some(p) if p.ty == PieceType::Pawn);
+
+            if !survives {
+                board.take_piece(pos);
+            }
+        }
+    }
+
+    fn blast(&self, board: &Board, _from: Vec2, m: &Move) -> Vec<Vec2> {
+        if board.is_capture(m) {
+            blast_area(board, m.pos)
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn outcome(&self, board: &Board, turn: Player) -> Option<Outcome> {
+        if board.find_king(turn).is_none() {
+            return Some(Outcome::Win(turn.opponent(), Reason::Explosion));
+        }
+
+        standard_outcome(board, turn)
+    }
+}
+
+/// The capture square and the squares around it.
+///
+fn blast_area(board: &Board, pos: Vec2) -> Vec<Vec2> {
+    (-1..=1)
+        .flat_map(|dy| (-1..=1).map(move |dx| pos + Vec2::new(dx, dy)))
+        .filter(|p| board.contains(*p))
+        .collect()
+}
+
+fn adjacent(a: Vec2, b: Vec2) -> bool {
+    (a.x - b.x).abs() <= 1 && (a.y - b.y).abs() <= 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::board::tests::assert_variant_perft;
+    use crate::core::fen;
+
+    fn parse(text: &str) -> (Board, Player) {
+        let fen = fen::parse_variant(text, Arc::new(Atomic)).unwrap();
+        (fen.board, fen.turn)
+    }
+
+    #[test]
+    fn perft() {
+        assert_variant_perft(
+            "atomic",
+            "rn2kb1r/1pp1p2p/p2q1pp1/3P4/2P3b1/4PN2/PP3PPP/R2QKB1R b KQkq - 0 1",
+            &[40, 1238, 45237],
+        );
+        assert_variant_perft(
+            "atomic",
+            "rn1qkb1r/p5pp/2p5/3p4/N3P3/5P2/PPP4P/R1BQK3 w Qkq - 0 1",
+            &[28, 833, 23353],
+        );
+    }
+
+    #[test]
+    fn explosions_spare_pawns() {
+        let (mut board, _) = parse("4k3/8/2b5/3pp3/8/4N3/8/4K3 w - - 0 1");
+        let (d5, e3) = (Vec2::new(3, 3), Vec2::new(4, 5));
+
+        let m = board
+            .get_legal_moves(e3, false)
+            .into_iter()
+            .find(|m| m.pos == d5)
+            .unwrap();
+        board.make_move(e3, &m);
+
+        // the knight, the pawn it took and the bishop beside them are gone
+        //
+        assert!(board.get(d5).is_none());
+        assert!(board.get(Vec2::new(2, 2)).is_none());
+        assert!(board
+            .get(Vec2::new(4, 3))
+            .is_some_and(|p| p.ty == PieceType::Pawn));
+        assert_eq!(board.pieces(Player::White).count(), 1);
+    }
+
+    #[test]
+    fn kings_side_by_side() {
+        // the rook can't take the king without blowing up its own
+        //
+        let (board, _) = parse("4r3/8/8/8/3kK3/8/8/8 w - - 0 1");
+        assert!(!Atomic.in_check(&board, Player::White));
+
+        // and kings never capture
+        //
+        let (board, turn) = parse("4k3/8/8/8/4Kp2/8/8/8 w - - 0 1");
+        assert!(!board
+            .all_legal_moves(turn)
+            .iter()
+            .any(|(_, m)| board.is_capture(m)));
+    }
+
+    #[test]
+    fn blowing_up_the_king_wins() {
+        let (mut board, _) = parse("3qk3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        let (d1, d8) = (Vec2::new(3, 7), Vec2::new(3, 0));
+
+        let m = board
+            .get_legal_moves(d1, false)
+            .into_iter()
+            .find(|m| m.pos == d8)
+            .unwrap();
+        board.make_move(d1, &m);
+
+        assert_eq!(
+            Atomic.outcome(&board, Player::Black),
+            Some(Outcome::Win(Player::White, Reason::Explosion))
+        );
+    }
+}
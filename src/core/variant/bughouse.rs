@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use super::{Standard, Variant};
+use crate::core::Board;
+
+/// One board of a game of bughouse. Pieces are dropped from the pocket
+/// as in Crazyhouse, but the pocket is filled by the partner's captures
+/// on the other board, see [`crate::core::BughouseGame`].
+///
+pub struct Bughouse;
+
+impl Variant for Bughouse {
+    fn name(&self) -> &'static str {
+        "bughouse"
+    }
+
+    fn start_position(&self) -> Board {
+        let mut board = Standard.start_position();
+        board.variant = Arc::new(Bughouse);
+        board
+    }
+
+    fn has_pockets(&self) -> bool {
+        true
+    }
+
+    fn can_undo(&self) -> bool {
+        false
+    }
+}
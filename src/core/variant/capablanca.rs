@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use super::{from_fen, Variant};
+use crate::core::{Board, FairyPiece, Move, MoveResult, Movement, PieceType, Vec2};
+
+/// Capablanca chess: played on a 10x8 board with two extra pieces, the
+/// archbishop (bishop and knight) and the chancellor (rook and knight).
+///
+pub struct Capablanca;
+
+const START: &str = "rnabqkbcnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNABQKBCNR w KQkq - 0 1";
+
+/// The archbishop and the chancellor.
+///
+static PIECES: [FairyPiece; 2] = [
+    FairyPiece {
+        name: "Archbishop",
+        letter: 'A',
+        glyph: "A",
+        value: 7,
+        moves: &[
+            Movement {
+                steps: &Vec2::DIAG,
+                limit: Movement::RIDE,
+            },
+            Movement {
+                steps: &Vec2::KNIGHT,
+                limit: 1,
+            },
+        ],
+    },
+    FairyPiece {
+        name: "Chancellor",
+        letter: 'C',
+        glyph: "C",
+        value: 8,
+        moves: &[
+            Movement {
+                steps: &Vec2::AXIS,
+                limit: Movement::RIDE,
+            },
+            Movement {
+                steps: &Vec2::KNIGHT,
+                limit: 1,
+            },
+        ],
+    },
+];
+
+impl Variant for Capablanca {
+    fn name(&self) -> &'static str {
+        "capablanca"
+    }
+
+    fn start_position(&self) -> Board {
+        from_fen(Arc::new(Capablanca), START)
+    }
+
+    fn fairy_pieces(&self) -> &'static [FairyPiece] {
+        &PIECES
+    }
+
+    fn valid_moves(&self, _board: &Board, _pos: Vec2, moves: &mut Vec<Move>) {
+        // pawns may also promote to either of the new pieces
+        //
+        let promotions: Vec<Move> = moves
+            .iter()
+            .filter(|m| m.result == MoveResult::Promotion(PieceType::Queen))
+            .flat_map(|m| {
+                PIECES
+                    .iter()
+                    .map(|f| Move::new(m.pos, MoveResult::Promotion(PieceType::Fairy(f))))
+            })
+            .collect();
+
+        moves.extend(promotions);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::board::tests::assert_variant_perft;
+    use crate::core::fen;
+
+    #[test]
+    fn perft() {
+        assert_variant_perft("capablanca", START, &[28, 784, 25228]);
+    }
+
+    #[test]
+    fn fairy_promotions() {
+        let fen = fen::parse_variant("4k5/P9/10/10/10/10/10/4K5 w - - 0 1", Arc::new(Capablanca))
+            .unwrap();
+        let a7 = Vec2::new(0, 1);
+
+        let promotions: Vec<String> = fen
+            .board
+            .get_legal_moves(a7, false)
+            .into_iter()
+            .filter_map(|m| match m.result {
+                MoveResult::Promotion(ty) => Some(ty.letter().to_string()),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(promotions.len(), 6);
+        for letter in ["Q", "R", "B", "N", "A", "C"] {
+            assert!(promotions.iter().any(|p| p.eq_ignore_ascii_case(letter)));
+        }
+        assert_eq!(fen.board.width(), 10);
+    }
+}
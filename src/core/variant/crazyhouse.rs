@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use super::{Standard, Variant};
+use crate::core::{Board, Move, Piece, PieceType, Player, Vec2};
+
+/// Crazyhouse: captured pieces change sides and go into the capturer's
+/// pocket, from where they can be dropped onto any empty square in
+/// place of a move.
+///
+pub struct Crazyhouse;
+
+impl Variant for Crazyhouse {
+    fn name(&self) -> &'static str {
+        "crazyhouse"
+    }
+
+    fn start_position(&self) -> Board {
+        let mut board = Standard.start_position();
+        board.variant = Arc::new(Crazyhouse);
+        board
+    }
+
+    fn has_pockets(&self) -> bool {
+        true
+    }
+
+    fn after_move(
+        &self,
+        board: &mut Board,
+        player: Player,
+        _from: Vec2,
+        _m: &Move,
+        taken: Option<&Piece>,
+    ) {
+        if let Some(piece) = taken {
+            let ty = if piece.promoted {
+                PieceType::Pawn
+            } else {
+                piece.ty
+            };
+            board.add_to_pocket(player, ty);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use super::{from_fen, standard_outcome, Variant};
+use crate::core::{Board, Outcome, PieceType, Player, Reason};
+
+/// Horde: White has a horde of pawns and no king, and wins by
+/// checkmate. Black wins by capturing every one of White's pieces.
+///
+pub struct Horde;
+
+const START: &str = "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1";
+
+impl Variant for Horde {
+    fn name(&self) -> &'static str {
+        "horde"
+    }
+
+    fn start_position(&self) -> Board {
+        let mut board = from_fen(Arc::new(Horde), START);
+
+        // pawns on the first rank may also advance two squares
+        //
+        let first_rank: Vec<_> = board
+            .pieces(Player::White)
+            .filter(|(pos, p)| p.ty == PieceType::Pawn && pos.y == board.home_rank(Player::White))
+            .map(|(pos, _)| pos)
+            .collect();
+
+        for pos in first_rank {
+            if let Some(pawn) = board.get_mut(pos) {
+                pawn.move_count = 0;
+            }
+        }
+
+        board
+    }
+
+    fn outcome(&self, board: &Board, turn: Player) -> Option<Outcome> {
+        if board.pieces(Player::White).next().is_none() {
+            return Some(Outcome::Win(Player::Black, Reason::NoPieces));
+        }
+
+        standard_outcome(board, turn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{fen, Vec2};
+
+    #[test]
+    fn outcome() {
+        let fen = fen::parse_variant("4k3/8/8/8/8/8/8/8 w - - 0 1", Arc::new(Horde)).unwrap();
+        assert_eq!(
+            Horde.outcome(&fen.board, fen.turn),
+            Some(Outcome::Win(Player::Black, Reason::NoPieces))
+        );
+
+        let fen = fen::parse_variant("4k3/8/8/8/8/8/8/P7 w - - 0 1", Arc::new(Horde)).unwrap();
+        assert_eq!(Horde.outcome(&fen.board, fen.turn), None);
+    }
+
+    #[test]
+    fn first_rank_pawns_double_step() {
+        let mut board = Horde.start_position();
+        let (a1, a2, a3) = (Vec2::new(0, 7), Vec2::new(0, 6), Vec2::new(0, 5));
+
+        board.take_piece(a2);
+        board.take_piece(a3);
+
+        let moves: Vec<Vec2> = board
+            .get_legal_moves(a1, false)
+            .into_iter()
+            .map(|m| m.pos)
+            .collect();
+        assert_eq!(moves, [a2, a3]);
+    }
+}
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use super::{standard_outcome, Standard, Variant};
+use crate::core::{Board, Outcome, Player, Reason, Vec2};
+
+/// King of the Hill: as standard chess, but a player also wins by
+/// bringing their king to one of the four centre squares.
+///
+pub struct KingOfTheHill;
+
+const HILL: [Vec2; 4] = [
+    Vec2::new(3, 3),
+    Vec2::new(4, 3),
+    Vec2::new(3, 4),
+    Vec2::new(4, 4),
+];
+
+impl Variant for KingOfTheHill {
+    fn name(&self) -> &'static str {
+        "koth"
+    }
+
+    fn start_position(&self) -> Board {
+        let mut board = Standard.start_position();
+        board.variant = Arc::new(KingOfTheHill);
+        board
+    }
+
+    fn outcome(&self, board: &Board, turn: Player) -> Option<Outcome> {
+        let mover = turn.opponent();
+
+        match board.find_king(mover) {
+            Some(king) if HILL.contains(&king) => Some(Outcome::Win(mover, Reason::KingOfTheHill)),
+            _ => standard_outcome(board, turn),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::fen;
+
+    #[test]
+    fn outcome() {
+        let parse = |text| fen::parse_variant(text, Arc::new(KingOfTheHill)).unwrap();
+
+        // white's king has just reached d4
+        //
+        let fen = parse("4k3/8/8/8/3K4/8/8/8 b - - 0 1");
+        assert_eq!(
+            KingOfTheHill.outcome(&fen.board, fen.turn),
+            Some(Outcome::Win(Player::White, Reason::KingOfTheHill))
+        );
+
+        let fen = parse("4k3/8/8/8/2K5/8/8/8 b - - 0 1");
+        assert_eq!(KingOfTheHill.outcome(&fen.board, fen.turn), None);
+    }
+}
@@ -0,0 +1,185 @@
+mod antichess;
+mod atomic;
+mod bughouse;
+mod capablanca;
+mod crazyhouse;
+mod horde;
+mod koth;
+mod racing_kings;
+mod three_check;
+
+use std::sync::Arc;
+
+use super::{fen, Board, FairyPiece, Move, Outcome, Piece, Player, Reason, Vec2};
+
+pub use antichess::Antichess;
+pub use atomic::Atomic;
+pub use bughouse::Bughouse;
+pub use capablanca::Capablanca;
+pub use crazyhouse::Crazyhouse;
+pub use horde::Horde;
+pub use koth::KingOfTheHill;
+pub use racing_kings::RacingKings;
+pub use three_check::ThreeCheck;
+
+/// The rules that set one kind of chess apart from another. Every hook
+/// has a default giving the standard rules, so a variant only overrides
+/// what it changes.
+///
+pub trait Variant: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// The position a new game starts from.
+    ///
+    fn start_position(&self) -> Board;
+
+    /// Adjust the moves generated for the piece at `pos`, before
+    /// moves that are illegal under [`Variant::is_legal`] are removed.
+    ///
+    fn valid_moves(&self, _board: &Board, _pos: Vec2, _moves: &mut Vec<Move>) {}
+
+    /// Whether `player`'s king is in check.
+    ///
+    fn in_check(&self, board: &Board, player: Player) -> bool {
+        board.in_check(player)
+    }
+
+    /// Whether the position reached after `player` moved is allowed.
+    ///
+    fn is_legal(&self, after: &Board, player: Player) -> bool {
+        !self.in_check(after, player)
+    }
+
+    /// Called once a move has been made on the board, to update any
+    /// state the variant keeps. `taken` is the piece captured, if any.
+    ///
+    fn after_move(
+        &self,
+        _board: &mut Board,
+        _player: Player,
+        _from: Vec2,
+        _m: &Move,
+        _taken: Option<&Piece>,
+    ) {
+    }
+
+    /// The result of the game if it has ended, `turn` being the
+    /// player who would move next.
+    ///
+    fn outcome(&self, board: &Board, turn: Player) -> Option<Outcome> {
+        standard_outcome(board, turn)
+    }
+
+    /// The squares caught up in an explosion set off by the move
+    /// `m` of the piece at `from`, for drawing the explosion effect.
+    /// `board` is the position before the move is made.
+    ///
+    fn blast(&self, _board: &Board, _from: Vec2, _m: &Move) -> Vec<Vec2> {
+        Vec::new()
+    }
+
+    /// The pieces the variant adds to the standard ones.
+    ///
+    fn fairy_pieces(&self) -> &'static [FairyPiece] {
+        &[]
+    }
+
+    /// Whether players hold captured pieces in a pocket to drop.
+    ///
+    fn has_pockets(&self) -> bool {
+        false
+    }
+
+    /// A line of variant specific information for the info panel.
+    ///
+    fn info(&self, _board: &Board) -> Option<String> {
+        None
+    }
+
+    /// Whether moves may be taken back.
+    ///
+    fn can_undo(&self) -> bool {
+        true
+    }
+
+    /// Whether each player must have exactly one king, which may not
+    /// be left in check.
+    ///
+    fn royal_king(&self) -> bool {
+        true
+    }
+}
+
+/// Checkmate wins and stalemate draws.
+///
+pub fn standard_outcome(board: &Board, turn: Player) -> Option<Outcome> {
+    if board.has_legal_moves(turn) {
+        None
+    } else if board.variant.in_check(board, turn) {
+        Some(Outcome::Win(turn.opponent(), Reason::Checkmate))
+    } else {
+        Some(Outcome::Draw(Reason::Stalemate))
+    }
+}
+
+pub struct Standard;
+
+impl Variant for Standard {
+    fn name(&self) -> &'static str {
+        "standard"
+    }
+
+    fn start_position(&self) -> Board {
+        let data = "RNBQKBNR\
+                    PPPPPPPP\
+                    ........\
+                    ........\
+                    ........\
+                    ........\
+                    pppppppp\
+                    rnbqkbnr";
+
+        Board::from_string(data)
+    }
+}
+
+/// The names accepted by [`by_name`].
+///
+pub const NAMES: [&str; 10] = [
+    "standard",
+    "koth",
+    "three-check",
+    "racing-kings",
+    "horde",
+    "crazyhouse",
+    "atomic",
+    "antichess",
+    "capablanca",
+    "bughouse",
+];
+
+pub fn by_name(name: &str) -> Option<Arc<dyn Variant>> {
+    let variant: Arc<dyn Variant> = match name {
+        "standard" => Arc::new(Standard),
+        "koth" => Arc::new(KingOfTheHill),
+        "three-check" => Arc::new(ThreeCheck),
+        "racing-kings" => Arc::new(RacingKings),
+        "horde" => Arc::new(Horde),
+        "crazyhouse" => Arc::new(Crazyhouse),
+        "atomic" => Arc::new(Atomic),
+        "antichess" => Arc::new(Antichess),
+        "capablanca" => Arc::new(Capablanca),
+        "bughouse" => Arc::new(Bughouse),
+        _ => return None,
+    };
+    Some(variant)
+}
+
+/// Build a variant's start position from a FEN string, with the
+/// board using that variant's rules.
+///
+fn from_fen(variant: Arc<dyn Variant>, data: &str) -> Board {
+    fen::parse_variant(data, variant)
+        .expect("valid variant FEN")
+        .board
+}
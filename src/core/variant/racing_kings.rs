@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use super::{from_fen, Variant};
+use crate::core::{Board, Outcome, Player, Reason};
+
+/// Racing Kings: the first king to reach the eighth rank wins. Checks
+/// are not allowed, and if White gets there first Black has one move
+/// left to draw by reaching it too.
+///
+pub struct RacingKings;
+
+const START: &str = "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1";
+
+impl RacingKings {
+    fn on_goal(board: &Board, player: Player) -> bool {
+        matches!(board.find_king(player), Some(king) if king.y == 0)
+    }
+}
+
+impl Variant for RacingKings {
+    fn name(&self) -> &'static str {
+        "racing-kings"
+    }
+
+    fn start_position(&self) -> Board {
+        from_fen(Arc::new(RacingKings), START)
+    }
+
+    fn is_legal(&self, after: &Board, player: Player) -> bool {
+        !self.in_check(after, player) && !self.in_check(after, player.opponent())
+    }
+
+    fn outcome(&self, board: &Board, turn: Player) -> Option<Outcome> {
+        let white = Self::on_goal(board, Player::White);
+        let black = Self::on_goal(board, Player::Black);
+
+        match turn {
+            // black has just moved, so both sides have had the same
+            // number of moves to reach the goal.
+            //
+            Player::White if white && black => return Some(Outcome::Draw(Reason::Race)),
+            Player::White if white => return Some(Outcome::Win(Player::White, Reason::Race)),
+            Player::White if black => return Some(Outcome::Win(Player::Black, Reason::Race)),
+
+            // white got there first, black may still catch up.
+            //
+            Player::Black if white => {
+                let king = board.find_king(Player::Black);
+                let can_draw = board
+                    .all_legal_moves(Player::Black)
+                    .iter()
+                    .any(|(from, m)| Some(*from) == king && m.pos.y == 0);
+
+                if !can_draw {
+                    return Some(Outcome::Win(Player::White, Reason::Race));
+                }
+            }
+            _ => {}
+        }
+
+        if board.has_legal_moves(turn) {
+            None
+        } else {
+            Some(Outcome::Draw(Reason::Stalemate))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::fen;
+
+    #[test]
+    fn outcome() {
+        let outcome = |text| {
+            let fen = fen::parse_variant(text, Arc::new(RacingKings)).unwrap();
+            RacingKings.outcome(&fen.board, fen.turn)
+        };
+
+        // black reached the last rank straight after white
+        //
+        assert_eq!(
+            outcome("K6k/8/8/8/8/8/8/8 w - - 0 1"),
+            Some(Outcome::Draw(Reason::Race))
+        );
+
+        // white is there, and black is a move away
+        //
+        assert_eq!(outcome("K7/7k/8/8/8/8/8/8 b - - 0 1"), None);
+
+        // white is there, and black is too far behind
+        //
+        assert_eq!(
+            outcome("K7/8/7k/8/8/8/8/8 b - - 0 1"),
+            Some(Outcome::Win(Player::White, Reason::Race))
+        );
+    }
+}
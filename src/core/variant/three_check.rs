@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use super::{standard_outcome, Standard, Variant};
+use crate::core::{Board, Move, Outcome, Piece, Player, Reason, Vec2};
+
+/// Three-check: as standard chess, but giving check for
+/// the third time also wins the game.
+///
+pub struct ThreeCheck;
+
+const CHECKS: u32 = 3;
+
+impl Variant for ThreeCheck {
+    fn name(&self) -> &'static str {
+        "three-check"
+    }
+
+    fn start_position(&self) -> Board {
+        let mut board = Standard.start_position();
+        board.variant = Arc::new(ThreeCheck);
+        board
+    }
+
+    fn after_move(
+        &self,
+        board: &mut Board,
+        player: Player,
+        _from: Vec2,
+        _m: &Move,
+        _taken: Option<&Piece>,
+    ) {
+        if self.in_check(board, player.opponent()) {
+            board.checks[player as usize] += 1;
+        }
+    }
+
+    fn outcome(&self, board: &Board, turn: Player) -> Option<Outcome> {
+        let mover = turn.opponent();
+
+        if board.checks[mover as usize] >= CHECKS {
+            return Some(Outcome::Win(mover, Reason::ThreeChecks));
+        }
+
+        standard_outcome(board, turn)
+    }
+
+    fn info(&self, board: &Board) -> Option<String> {
+        Some(format!(
+            "Checks: White {}, Black {}",
+            board.checks[Player::White as usize],
+            board.checks[Player::Black as usize]
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::fen;
+
+    #[test]
+    fn outcome() {
+        let fen =
+            fen::parse_variant("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", Arc::new(ThreeCheck)).unwrap();
+        let mut board = fen.board;
+        board.checks = [2, 0];
+
+        let (a1, a8) = (Vec2::new(0, 7), Vec2::new(0, 0));
+        let check = board
+            .get_legal_moves(a1, false)
+            .into_iter()
+            .find(|m| m.pos == a8)
+            .unwrap();
+
+        assert_eq!(ThreeCheck.outcome(&board, Player::White), None);
+        board.make_move(a1, &check);
+
+        assert_eq!(board.checks, [3, 0]);
+        assert_eq!(
+            ThreeCheck.outcome(&board, Player::Black),
+            Some(Outcome::Win(Player::White, Reason::ThreeChecks))
+        );
+    }
+}
@@ -1,8 +1,6 @@
-use std::cmp::{Eq, PartialEq};
-use std::hash::Hash;
 use std::ops::{Add, Mul};
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Vec2 {
     pub x: i16,
     pub y: i16,
@@ -30,6 +28,16 @@ impl Vec2 {
         Vec2::DOWN_LEFT,
         Vec2::DOWN_RIGHT,
     ];
+    pub const KNIGHT: [Vec2; 8] = [
+        Vec2::new(-1, -2),
+        Vec2::new(1, -2),
+        Vec2::new(-2, -1),
+        Vec2::new(2, -1),
+        Vec2::new(-2, 1),
+        Vec2::new(2, 1),
+        Vec2::new(-1, 2),
+        Vec2::new(1, 2),
+    ];
 }
 
 impl Mul<i16> for Vec2 {
@@ -54,22 +62,6 @@ impl Add for Vec2 {
     }
 }
 
-impl PartialEq for Vec2 {
-    fn eq(&self, other: &Self) -> bool {
-        self.x == other.x && self.y == other.y
-    }
-}
-
-impl Eq for Vec2 {
-    fn assert_receiver_is_total_eq(&self) {}
-}
-
-impl Hash for Vec2 {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        (self.x, self.y).hash(state)
-    }
-}
-
 impl From<(i16, i16)> for Vec2 {
     fn from(t: (i16, i16)) -> Self {
         Vec2 { x: t.0, y: t.1 }
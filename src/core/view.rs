@@ -0,0 +1,67 @@
+use std::time::Instant;
+
+use super::{Player, Vec2};
+
+/// Presentation state that the inputs may change and the
+/// renderer reads back, such as the active colour theme.
+///
+pub struct View {
+    pub theme: usize,
+    /// The player whose pieces are drawn at the bottom of the board.
+    pub perspective: Player,
+    /// Turn the board to face whoever is to move. Disabled when only
+    /// one of the players is sat at this terminal, e.g. network play.
+    pub follow_turn: bool,
+    /// Shade the squares attacked by the opponent of the side to move.
+    pub show_attacks: bool,
+    /// The text typed so far while the command line is open.
+    pub command: Option<String>,
+    /// Feedback for the player, shown on the status line.
+    pub message: Option<String>,
+    /// The squares caught in the last explosion and when it went off,
+    /// so the renderer can flash them for a moment.
+    pub explosion: Option<(Vec<Vec2>, Instant)>,
+    /// Draw the board as this player may see it, hiding the opponent's
+    /// pieces and anything that would give them away, as in Kriegspiel.
+    pub visible_to: Option<Player>,
+    /// What the referee has announced to both players, oldest first.
+    pub announcements: Vec<String>,
+    /// Set to switch to the other board in bughouse, where the same
+    /// keys play on either board.
+    pub other_board: bool,
+    /// Show what the tablebases say about the position and its moves.
+    pub show_oracle: bool,
+    /// The squares a hint suggests moving from and to, and the hash of
+    /// the position it is for. It is shown until the position changes.
+    pub hint: Option<(u64, Vec2, Vec2)>,
+    /// The move selected on the review screen, while it is open.
+    pub review: Option<usize>,
+}
+
+impl View {
+    /// Whether the pieces of `player` may be shown.
+    ///
+    pub fn shows(&self, player: Player) -> bool {
+        self.visible_to.is_none_or(|p| p == player)
+    }
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            theme: 0,
+            perspective: Player::White,
+            follow_turn: true,
+            show_attacks: false,
+            command: None,
+            message: None,
+            explosion: None,
+            visible_to: None,
+            announcements: Vec::new(),
+            other_board: false,
+            show_oracle: false,
+            hint: None,
+            review: None,
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    ai::{Limits, Search, SearchInfo},
+    core::{hash::position_hash, Chess},
+    input::{CrosstermInput, Error, Input, Keymap},
+};
+
+/// The longest the computer thinks about a move.
+///
+const MOVE_TIME: Duration = Duration::from_secs(2);
+
+/// The share of its remaining clock time the computer spends on a move.
+///
+const CLOCK_SHARE: u32 = 30;
+
+/// A search running in the background, and the position it is for.
+///
+struct Thinking {
+    key: u64,
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<(Search, Option<SearchInfo>)>,
+}
+
+/// Plays a side of the board with the search, playing from the opening
+/// book while it has moves. Keys are still read from the terminal as with
+/// [`CrosstermInput`], so the game can be quit or the board flipped while
+/// the computer thinks, but they can't move for it.
+///
+pub struct AiInput {
+    local: CrosstermInput,
+    /// The searcher, while it isn't off thinking in another thread.
+    search: Option<Search>,
+    thinking: Option<Thinking>,
+}
+
+impl AiInput {
+    pub fn new(keymap: Keymap) -> Self {
+        Self {
+            local: CrosstermInput::new(keymap),
+            search: Some(Search::new(64)),
+            thinking: None,
+        }
+    }
+
+    /// Search on `threads` threads.
+    ///
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        if let Some(search) = &mut self.search {
+            search.set_threads(threads);
+        }
+        self
+    }
+
+    /// Start searching the game's position in the background.
+    ///
+    fn think(&mut self, game: &Chess) {
+        let Some(mut search) = self.search.take() else {
+            return;
+        };
+
+        let clock = Duration::from_millis(game.timers[game.turn as usize].remaining() as u64);
+        let limits = Limits::time((clock / CLOCK_SHARE).min(MOVE_TIME));
+
+        search.tablebase = game.tablebase();
+
+        let board = game.board.clone();
+        let turn = game.turn;
+
+        self.thinking = Some(Thinking {
+            key: position_hash(&board, turn),
+            stop: search.stop_flag(),
+            handle: thread::spawn(move || {
+                let info = search.run(&board, turn, limits, |_| {});
+                (search, info)
+            }),
+        });
+    }
+
+    /// Wait for the background search to finish, taking back the searcher.
+    ///
+    fn finish(&mut self, thinking: Thinking) -> Result<Option<SearchInfo>, Error> {
+        let (search, info) = thinking.handle.join().map_err(|_| Error {})?;
+
+        // a stop that came after the search was over would end the next one
+        //
+        thinking.stop.store(false, Ordering::Relaxed);
+        self.search = Some(search);
+
+        Ok(info)
+    }
+}
+
+/// Whether the computer sits the position out: the game is over, or
+/// the player is setting up or analysing a position.
+///
+fn idle(game: &Chess) -> bool {
+    game.outcome.is_some() || game.is_editing() || game.is_analysing() || game.refereed
+}
+
+impl Input for AiInput {
+    fn update(&mut self, game: &mut Chess) -> Result<(), Error> {
+        // the keys can't play for the computer, only for the player
+        // moving for both sides while it sits out
+        //
+        self.local.allow_moves(idle(game));
+        self.local.update(game)?;
+
+        let idle = idle(game);
+        let key = position_hash(&game.board, game.turn);
+
+        match self.thinking.take() {
+            // the position changed under the search, by an undo or a move
+            // made from the keyboard
+            //
+            Some(thinking) if thinking.key != key || idle => {
+                thinking.stop.store(true, Ordering::Relaxed);
+                self.finish(thinking)?;
+            }
+            Some(thinking) if !thinking.handle.is_finished() => self.thinking = Some(thinking),
+            Some(thinking) => {
+                if let Some(best) = self.finish(thinking)?.and_then(|info| info.best()) {
+                    game.play(best.0, best.1);
+                }
+            }
+            None if idle => {}
+            None => {
+                if !game.play_book_move() {
+                    self.think(game);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+use crate::core::{notation, Chess, Player};
+
+/// Why a command that plays for the side to move was turned down.
+///
+pub const WAITING: &str = "Wait for the computer to move";
+
+/// Run a line typed at the command prompt: either one of the named
+/// commands, or a move in SAN (`Nf3`) or coordinate notation (`g1f3`).
+///
+pub fn execute(game: &mut Chess, line: &str) -> Result<(), String> {
+    run(game, line, true)
+}
+
+/// Run a line typed at the command prompt while the computer is to
+/// move. Anything that would play for it, such as a move, resigning or
+/// offering a draw, is turned down.
+///
+pub fn execute_waiting(game: &mut Chess, line: &str) -> Result<(), String> {
+    run(game, line, false)
+}
+
+fn run(game: &mut Chess, line: &str, moves: bool) -> Result<(), String> {
+    let line = line.trim();
+
+    let (name, arg) = match line.split_once(' ') {
+        Some((name, arg)) => (name, Some(arg.trim())),
+        None => (line, None),
+    };
+
+    match (name, arg) {
+        ("", _) => {}
+        ("resign" | "draw" | "hint", None) | ("book", Some("play")) if !moves => {
+            return Err(WAITING.into())
+        }
+        ("resign", None) => game.resign(),
+        ("draw", None) => game.offer_draw(),
+        ("flip", None) => game.flip(),
+        ("undo", None) => game.undo(),
+        ("theme", None) => game.next_theme(),
+        ("quit" | "q", None) => game.quit(),
+        ("edit", None) => game.edit(),
+        ("fen", None) => game.view.message = Some(game.to_fen()),
+        ("fen", Some(path)) => {
+            std::fs::write(path, game.to_fen() + "\n")
+                .map_err(|err| format!("Unable to write {}: {}", path, err))?;
+            game.view.message = Some(format!("Saved the position to {}", path));
+        }
+        ("oracle", None) => game.toggle_oracle(),
+        ("hint", None) => game.hint(),
+        ("analyse" | "analyze", None) => game.toggle_analysis(),
+        ("review", None) => game.toggle_review(),
+        ("review", Some(path)) => {
+            game.save_review(path)?;
+            game.view.message = Some(format!("Saved the review to {}", path));
+        }
+        ("puzzles", Some(path)) => game.open_puzzles(path)?,
+        ("next", None) => game.next_puzzle(),
+        ("book", None) => game.view.message = Some(book_moves(game)?),
+        ("book", Some("play")) if !game.is_editing() => {
+            if !game.play_book_move() {
+                return Err("No book move to play".into());
+            }
+        }
+        _ if game.is_editing() => edit(game, name, arg)?,
+        _ => {
+            if game.outcome.is_some() {
+                return Err("The game is over".into());
+            }
+            if !moves {
+                return Err(WAITING.into());
+            }
+            if !game.play_notation(line) {
+                return Err(format!("Illegal move: {}", line));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The commands of the board editor.
+///
+fn edit(game: &mut Chess, name: &str, arg: Option<&str>) -> Result<(), String> {
+    match (name, arg) {
+        ("piece", Some(letter)) => game.set_brush(letter)?,
+        ("turn", None) => game.set_turn(None),
+        ("turn", Some("white" | "w")) => game.set_turn(Some(Player::White)),
+        ("turn", Some("black" | "b")) => game.set_turn(Some(Player::Black)),
+        ("castling", Some(rights)) => game.set_castling(rights)?,
+        ("clear", None) => game.clear_board(),
+        ("start", None) => game.reset_board(),
+        ("play" | "done", None) => game.play_position()?,
+        ("solve", Some(arg)) => match arg.split_once(' ') {
+            Some((stipulation, path)) => game.solve_problem(stipulation, Some(path.trim()))?,
+            None => game.solve_problem(arg, None)?,
+        },
+        _ => return Err(format!("Unknown editor command: {}", name)),
+    }
+
+    Ok(())
+}
+
+/// The book moves for the position, with the share of games each was
+/// chosen in.
+///
+fn book_moves(game: &Chess) -> Result<String, String> {
+    if game.book().is_none() {
+        return Err("No opening book loaded".into());
+    }
+
+    let moves = game.book_moves();
+    if moves.is_empty() {
+        return Ok("Out of book".into());
+    }
+
+    let total: u32 = moves.iter().map(|m| m.weight as u32).sum::<u32>().max(1);
+    let moves: Vec<String> = moves
+        .iter()
+        .map(|m| {
+            let san = notation::san(&game.board, m.from, &m.m);
+            format!("{} {}%", san, m.weight as u32 * 100 / total)
+        })
+        .collect();
+
+    Ok(format!("Book: {}", moves.join(", ")))
+}
@@ -3,8 +3,8 @@ use std::time::Duration;
 use crossterm::event::{poll, read, Event, KeyCode, KeyEvent, KeyEventKind};
 
 use crate::{
-    core::Vec2,
-    input::{Error, Input},
+    core::{Chess, Vec2},
+    input::{command, Action, Error, Input, Keymap},
 };
 
 impl From<std::io::Error> for Error {
@@ -13,10 +13,75 @@ impl From<std::io::Error> for Error {
     }
 }
 
-pub struct CrosstermInput;
+#[derive(Clone)]
+pub struct CrosstermInput {
+    keymap: Keymap,
+    /// Whether the keys may play for the side to move. When they may
+    /// not, as while the computer thinks, only keys that change how the
+    /// game is shown or leave it still work.
+    moves: bool,
+}
+
+impl Default for CrosstermInput {
+    fn default() -> Self {
+        Self::new(Keymap::default())
+    }
+}
+
+impl CrosstermInput {
+    pub fn new(keymap: Keymap) -> Self {
+        Self {
+            keymap,
+            moves: true,
+        }
+    }
+
+    /// Let the keys play for the side to move, or stop them.
+    ///
+    pub fn allow_moves(&mut self, moves: bool) {
+        self.moves = moves;
+    }
+
+    /// Edit the command line while it is open.
+    ///
+    fn update_command(&mut self, game: &mut Chess, code: KeyCode) {
+        let Some(line) = game.view.command.as_mut() else {
+            return;
+        };
+
+        match code {
+            KeyCode::Esc => game.view.command = None,
+            KeyCode::Backspace if line.pop().is_none() => game.view.command = None,
+            KeyCode::Enter => {
+                let line = game.view.command.take().unwrap_or_default();
+                let result = match self.moves {
+                    true => command::execute(game, &line),
+                    false => command::execute_waiting(game, &line),
+                };
+                if let Err(msg) = result {
+                    game.view.message = Some(msg);
+                }
+            }
+            KeyCode::Char(c) => line.push(c),
+            _ => {}
+        }
+    }
+
+    /// Move through the reviewed moves while the review screen is open.
+    ///
+    fn update_review(&mut self, game: &mut Chess, code: KeyCode) {
+        match self.keymap.get(code) {
+            Some(Action::Up) => game.select_review(-1),
+            Some(Action::Down) => game.select_review(1),
+            Some(Action::Quit | Action::Select | Action::Review) => game.toggle_review(),
+            Some(Action::Command) => game.view.command = Some(String::new()),
+            _ => {}
+        }
+    }
+}
 
 impl Input for CrosstermInput {
-    fn update(&mut self, game: &mut crate::core::Chess) -> Result<(), Error> {
+    fn update(&mut self, game: &mut Chess) -> Result<(), Error> {
         if poll(Duration::from_millis(100))? {
             if let Event::Key(KeyEvent {
                 code,
@@ -25,18 +90,69 @@ impl Input for CrosstermInput {
                 state: _,
             }) = read()?
             {
-                match code {
-                    KeyCode::Esc => game.quit(),
-                    KeyCode::Char('h') => game.move_cursor(Vec2::LEFT),
-                    KeyCode::Char('j') => game.move_cursor(Vec2::DOWN),
-                    KeyCode::Char('k') => game.move_cursor(Vec2::UP),
-                    KeyCode::Char('l') => game.move_cursor(Vec2::RIGHT),
-                    KeyCode::Char(' ') => game.action(),
-                    _ => {}
+                game.view.message = None;
+
+                if game.view.command.is_some() {
+                    self.update_command(game, code);
+                    return Ok(());
+                }
+
+                if game.view.review.is_some() {
+                    self.update_review(game, code);
+                    return Ok(());
+                }
+
+                let action = self.keymap.get(code);
+
+                if !self.moves
+                    && matches!(
+                        action,
+                        Some(
+                            Action::Select
+                                | Action::Pocket
+                                | Action::Hint
+                                | Action::Resign
+                                | Action::Draw
+                        )
+                    )
+                {
+                    game.view.message = Some(command::WAITING.into());
+                    return Ok(());
+                }
+
+                match action {
+                    Some(Action::Quit) => game.quit(),
+                    Some(Action::Left) => game.move_cursor(Vec2::LEFT),
+                    Some(Action::Down) => game.move_cursor(Vec2::DOWN),
+                    Some(Action::Up) => game.move_cursor(Vec2::UP),
+                    Some(Action::Right) => game.move_cursor(Vec2::RIGHT),
+                    Some(Action::Select) => game.action(),
+                    Some(Action::Command) => game.view.command = Some(String::new()),
+                    Some(Action::Theme) => game.next_theme(),
+                    Some(Action::Attacks) => game.toggle_attacks(),
+                    Some(Action::Flip) => game.flip(),
+                    Some(Action::Undo) => game.undo(),
+                    Some(Action::Resign) => game.resign(),
+                    Some(Action::Draw) => game.offer_draw(),
+                    Some(Action::Pocket) => game.cycle_pocket(),
+                    Some(Action::Board) => game.other_board(),
+                    Some(Action::Edit) => game.edit(),
+                    Some(Action::Oracle) => game.toggle_oracle(),
+                    Some(Action::Hint) => game.hint(),
+                    Some(Action::Analysis) => game.toggle_analysis(),
+                    Some(Action::Review) => game.toggle_review(),
+                    Some(Action::Next) => game.next_puzzle(),
+                    None => {}
                 }
             }
         }
 
+        // a hint asked for with a key shows once its search is over, and
+        // the analysis moves on with the position
+        //
+        game.update_hint();
+        game.follow_analysis();
+
         Ok(())
     }
 }
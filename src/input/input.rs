@@ -1,8 +1,27 @@
-use crate::core::Chess;
+use crate::core::{BughouseGame, Chess};
 
 #[derive(Debug)]
 pub struct Error {}
 
 pub trait Input {
     fn update(&mut self, game: &mut Chess) -> Result<(), Error>;
+
+    /// Play a game of bughouse. By default keys go to whichever board is
+    /// active.
+    ///
+    fn update_bughouse(&mut self, game: &mut BughouseGame) -> Result<(), Error> {
+        self.update(&mut game.boards[game.active])
+    }
+}
+
+/// Lets the inputs for the two sides be chosen at runtime.
+///
+impl<T: Input + ?Sized> Input for Box<T> {
+    fn update(&mut self, game: &mut Chess) -> Result<(), Error> {
+        (**self).update(game)
+    }
+
+    fn update_bughouse(&mut self, game: &mut BughouseGame) -> Result<(), Error> {
+        (**self).update_bughouse(game)
+    }
 }
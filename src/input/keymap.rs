@@ -0,0 +1,173 @@
+use std::{collections::HashMap, path::Path};
+
+use crossterm::event::KeyCode;
+use serde::Deserialize;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    UnknownKey(String),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(err: toml::de::Error) -> Self {
+        Self::Parse(err)
+    }
+}
+
+/// Something a key can be bound to.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    Left,
+    Right,
+    Up,
+    Down,
+    Select,
+    /// Open the command line to type a move or command.
+    Command,
+    Theme,
+    Attacks,
+    Flip,
+    Undo,
+    Resign,
+    Draw,
+    /// Choose the next piece in the pocket to drop, in Crazyhouse.
+    Pocket,
+    /// Switch to the other board, in bughouse.
+    Board,
+    /// Set up a position in the board editor.
+    Edit,
+    /// Show or hide what the endgame tablebases say.
+    Oracle,
+    /// Suggest a move for the player to move.
+    Hint,
+    /// Start or stop analysing the position.
+    Analysis,
+    /// Open or close the review of the game's moves.
+    Review,
+    /// Go on to the next puzzle, in puzzle mode.
+    Next,
+}
+
+#[derive(Clone)]
+pub struct Keymap {
+    keys: HashMap<KeyCode, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Self {
+            keys: HashMap::new(),
+        };
+
+        let bindings = [
+            (KeyCode::Esc, Action::Quit),
+            (KeyCode::Char('h'), Action::Left),
+            (KeyCode::Char('j'), Action::Down),
+            (KeyCode::Char('k'), Action::Up),
+            (KeyCode::Char('l'), Action::Right),
+            (KeyCode::Left, Action::Left),
+            (KeyCode::Down, Action::Down),
+            (KeyCode::Up, Action::Up),
+            (KeyCode::Right, Action::Right),
+            (KeyCode::Char(' '), Action::Select),
+            (KeyCode::Enter, Action::Select),
+            (KeyCode::Char(':'), Action::Command),
+            (KeyCode::Char('/'), Action::Command),
+            (KeyCode::Char('t'), Action::Theme),
+            (KeyCode::Char('a'), Action::Attacks),
+            (KeyCode::Char('f'), Action::Flip),
+            (KeyCode::Char('u'), Action::Undo),
+            (KeyCode::Char('p'), Action::Pocket),
+            (KeyCode::Tab, Action::Board),
+            (KeyCode::Char('e'), Action::Edit),
+            (KeyCode::Char('o'), Action::Oracle),
+            (KeyCode::Char('?'), Action::Hint),
+            (KeyCode::Char('z'), Action::Analysis),
+            (KeyCode::Char('v'), Action::Review),
+            (KeyCode::Char('n'), Action::Next),
+        ];
+
+        for (key, action) in bindings {
+            keymap.bind(key, action);
+        }
+
+        keymap
+    }
+}
+
+impl Keymap {
+    pub fn get(&self, key: KeyCode) -> Option<Action> {
+        self.keys.get(&key).copied()
+    }
+
+    pub fn bind(&mut self, key: KeyCode, action: Action) {
+        self.keys.insert(key, action);
+    }
+
+    /// Load key bindings from a TOML file of `action = ["key", ...]`
+    /// entries, e.g. `left = ["h", "left"]`. Actions listed in the file
+    /// replace their default bindings, others keep them.
+    ///
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Keymap, Error> {
+        let data = std::fs::read_to_string(path)?;
+        let table: HashMap<Action, Vec<String>> = toml::from_str(&data)?;
+
+        let mut keymap = Keymap::default();
+
+        for (action, names) in table {
+            keymap.keys.retain(|_, a| *a != action);
+
+            for name in names {
+                let key = parse_key(&name).ok_or(Error::UnknownKey(name))?;
+                keymap.bind(key, action);
+            }
+        }
+
+        Ok(keymap)
+    }
+}
+
+/// Parse a key name such as `"x"`, `"space"`, `"left"` or `"f1"`.
+///
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+
+    let key = match name.to_lowercase().as_str() {
+        "space" => KeyCode::Char(' '),
+        "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        other => {
+            let n = other.strip_prefix('f')?.parse().ok()?;
+            KeyCode::F(n)
+        }
+    };
+
+    Some(key)
+}
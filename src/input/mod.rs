@@ -1,6 +1,12 @@
+mod ai;
+pub mod command;
 mod crossterm;
+#[allow(clippy::module_inception)]
 mod input;
+pub mod keymap;
 
 pub use self::crossterm::CrosstermInput;
+pub use ai::AiInput;
 pub use input::Error;
 pub use input::Input;
+pub use keymap::{Action, Keymap};
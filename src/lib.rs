@@ -1,3 +1,5 @@
-mod core;
-mod input;
-mod render;
+pub mod ai;
+pub mod core;
+pub mod input;
+pub mod net;
+pub mod render;
@@ -1,27 +1,312 @@
-mod core;
-mod input;
-mod render;
+use std::{sync::Arc, thread};
 
-use crate::core::{engine::Engine, Chess};
-use crate::input::CrosstermInput;
-use crate::render::tui::TuiRenderer;
-use crate::render::Theme;
+use tui_test::ai::book::{Book, BookBuilder};
+use tui_test::ai::syzygy::Tablebase;
+use tui_test::ai::{uci, Solver, Stipulation};
+use tui_test::core::engine::{self, BughouseEngine, Engine};
+use tui_test::core::{variant, variant::Standard, variant::Variant, BughouseGame, Chess};
+use tui_test::input::{AiInput, CrosstermInput, Input, Keymap};
+use tui_test::net::{self, BughouseHost, NetworkInput, Referee};
+use tui_test::render::tui::TuiRenderer;
+use tui_test::render::Theme;
 
-fn main() -> Result<(), core::engine::Error> {
-    let game = Chess::default();
+/// Settings taken from the command line.
+///
+#[derive(Default)]
+struct Options {
+    theme: Option<String>,
+    keys: Option<String>,
+    fen: Option<String>,
+    /// A Chess960 position number, or `random`.
+    chess960: Option<String>,
+    /// One of [`variant::NAMES`].
+    variant: Option<String>,
+    /// Referee a game of Kriegspiel, or of bughouse, on this address
+    /// instead of playing.
+    serve: Option<String>,
+    /// Play a game run by the referee at this address.
+    connect: Option<String>,
+    /// A Polyglot opening book to consult, or to write with `build_book`.
+    book: Option<String>,
+    /// Build the opening book from the games in this PGN file.
+    build_book: Option<String>,
+    /// A directory of Syzygy endgame tablebases.
+    syzygy: Option<String>,
+    /// The side the computer plays, `white`, `black` or `both`.
+    ai: Option<String>,
+    /// The number of threads the computer searches on.
+    threads: Option<String>,
+    /// Speak UCI on standard input and output instead of showing a board.
+    uci: bool,
+    /// A CSV file of tactics puzzles to solve instead of playing a game.
+    puzzles: Option<String>,
+    /// Solve the position as a problem, e.g. `#2`, `s#3` or `h#2`.
+    solve: Option<String>,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options::default();
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{} expects a value", arg));
+
+            match arg.as_str() {
+                "--theme" => options.theme = Some(value()?),
+                "--keys" => options.keys = Some(value()?),
+                "--fen" => options.fen = Some(value()?),
+                "--chess960" => options.chess960 = Some(value()?),
+                "--variant" => options.variant = Some(value()?),
+                "--serve" => options.serve = Some(value()?),
+                "--connect" => options.connect = Some(value()?),
+                "--book" => options.book = Some(value()?),
+                "--build-book" => options.build_book = Some(value()?),
+                "--syzygy" => options.syzygy = Some(value()?),
+                "--ai" => options.ai = Some(value()?),
+                "--threads" => options.threads = Some(value()?),
+                "--uci" => options.uci = true,
+                "--puzzles" => options.puzzles = Some(value()?),
+                "--solve" => options.solve = Some(value()?),
+                _ => return Err(format!("unknown argument '{}'", arg)),
+            }
+        }
+
+        Ok(options)
+    }
+}
 
-    let theme = Theme::default();
-    let renderer = TuiRenderer::new(theme);
+fn main() -> Result<(), engine::Error> {
+    let (options, game, themes, keymap) = match setup() {
+        Ok(setup) => setup,
+        Err(msg) => exit(&msg),
+    };
 
-    // TODO: when playing over the network, the second player
-    // will use a NetworkInput type.
+    if let Some(pgn) = &options.build_book {
+        if let Err(msg) = build_book(pgn, options.book.as_deref()) {
+            exit(&msg);
+        }
+        return Ok(());
+    }
+
+    if let Some(stipulation) = &options.solve {
+        match stipulation.parse::<Stipulation>() {
+            Ok(stipulation) => print!("{}", Solver::solve(&game.board, game.turn, stipulation)),
+            Err(msg) => exit(&msg),
+        }
+        return Ok(());
+    }
+
+    if options.uci {
+        if let Err(err) = uci::run(&game) {
+            exit(&format!("UCI input failed: {}", err));
+        }
+        return Ok(());
+    }
+
+    let bughouse = game.board.variant.name() == variant::Bughouse.name();
+
+    // the referee has no board of its own to show
     //
-    let p1 = CrosstermInput;
-    let p2 = CrosstermInput;
+    if let Some(addr) = &options.serve {
+        let served = match bughouse {
+            true => net::serve(BughouseHost::new(), addr),
+            false => net::serve(Referee::new(game), addr),
+        };
+        if let Err(err) = served {
+            exit(&format!("unable to listen on {}: {}", addr, err));
+        }
+        return Ok(());
+    }
+
+    let renderer = TuiRenderer::new(themes[0].clone());
+
+    match &options.connect {
+        Some(addr) => {
+            let input = match NetworkInput::connect(addr, keymap) {
+                Ok(input) => input,
+                Err(err) => exit(&format!("unable to connect to {}: {}", addr, err)),
+            };
+
+            if input.variant() == variant::Bughouse.name() {
+                BughouseEngine::new(input.bughouse(), renderer, input)
+                    .with_themes(themes)
+                    .run()?;
+                return Ok(());
+            }
+
+            let game = match input.game() {
+                Ok(game) => game,
+                Err(err) => exit(&format!("unable to play on {}: {}", addr, err)),
+            };
+
+            // the referee sends the position once everyone is here,
+            // and this one input plays whichever side we are given
+            //
+            Engine::new(game, renderer, input.clone(), input)
+                .with_themes(themes)
+                .run()?;
+        }
+        None if bughouse => {
+            // one player at the keyboard moves for everybody, on
+            // whichever board is active
+            //
+            let input = CrosstermInput::new(keymap);
+
+            BughouseEngine::new(BughouseGame::new(), renderer, input)
+                .with_themes(themes)
+                .run()?;
+        }
+        None => {
+            let threads = match options.threads.as_deref().map(str::parse::<usize>) {
+                Some(Ok(threads)) if threads > 0 => threads,
+                Some(_) => exit("--threads expects a number of threads, at least 1"),
+                None => thread::available_parallelism().map_or(1, |n| n.get()),
+            };
+
+            let player =
+                |keymap: Keymap| -> Box<dyn Input> { Box::new(CrosstermInput::new(keymap)) };
+            let computer = |keymap: Keymap| -> Box<dyn Input> {
+                Box::new(AiInput::new(keymap).with_threads(threads))
+            };
+
+            let (p1, p2) = match options.ai.as_deref() {
+                None => (player(keymap.clone()), player(keymap)),
+                Some("white") => (computer(keymap.clone()), player(keymap)),
+                Some("black") => (player(keymap.clone()), computer(keymap)),
+                Some("both") => (computer(keymap.clone()), computer(keymap)),
+                Some(side) => exit(&format!(
+                    "no side '{}' for the computer, expected white, black or both",
+                    side
+                )),
+            };
+
+            Engine::new(game, renderer, p1, p2)
+                .with_themes(themes)
+                .run()?;
+        }
+    }
+
+    Ok(())
+}
 
-    let engine = Engine::new(game, renderer, p1, p2);
+fn exit(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    std::process::exit(1);
+}
+
+fn setup() -> Result<(Options, Chess, Vec<Theme>, Keymap), String> {
+    let options = Options::parse(std::env::args().skip(1))?;
+
+    let variant = match options.variant.as_deref() {
+        Some(name) => Some(variant::by_name(name).ok_or(format!(
+            "unknown variant '{}', expected one of: {}",
+            name,
+            variant::NAMES.join(", ")
+        ))?),
+        None => None,
+    };
+
+    let mut game = match (&options.fen, options.chess960.as_deref()) {
+        (Some(fen), _) => {
+            // a FEN only describes the position, the rules come from --variant
+            //
+            let variant = variant.unwrap_or_else(|| Arc::new(Standard));
+
+            Chess::from_fen_variant(fen, variant)
+                .map_err(|err| format!("unable to load FEN: {}", err))?
+        }
+        (None, Some("random")) => Chess::random_chess960(),
+        (None, Some(index)) => match index.parse::<usize>() {
+            Ok(index) if index < 960 => Chess::chess960(index),
+            _ => return Err(format!("no Chess960 position '{}', expected 0-959", index)),
+        },
+        (None, None) => match variant {
+            Some(variant) => Chess::with_variant(variant),
+            None => Chess::default(),
+        },
+    };
+
+    if let (Some(path), None) = (&options.book, &options.build_book) {
+        let book =
+            Book::open(path).map_err(|err| format!("unable to load book '{}': {}", path, err))?;
+        game.set_book(Arc::new(book));
+    }
+
+    if let Some(dir) = &options.syzygy {
+        let tablebase = Tablebase::open(dir)
+            .map_err(|err| format!("unable to load tablebases from '{}': {}", dir, err))?;
+        game.set_tablebase(Arc::new(tablebase));
+    }
+
+    if let Some(path) = &options.puzzles {
+        // the replies are played from the solution, not searched for
+        //
+        if options.ai.is_some() {
+            return Err("--puzzles can't be used with --ai".into());
+        }
+        game.open_puzzles(path)?;
+    }
+
+    let themes = load_themes(options.theme.as_deref())?;
+
+    let keymap = match &options.keys {
+        Some(path) => Keymap::load(path)
+            .map_err(|err| format!("unable to load key map '{}': {:?}", path, err))?,
+        None => Keymap::default(),
+    };
+
+    Ok((options, game, themes, keymap))
+}
+
+/// How many plies into each game a book built from PGN goes.
+///
+const BOOK_PLIES: usize = 30;
+
+/// Write a Polyglot book of the openings played in the games of a PGN
+/// file to `out`.
+///
+fn build_book(pgn: &str, out: Option<&str>) -> Result<(), String> {
+    let out = out.ok_or("--build-book needs --book to name the book to write")?;
+
+    let text =
+        std::fs::read_to_string(pgn).map_err(|err| format!("unable to read '{}': {}", pgn, err))?;
+
+    let mut builder = BookBuilder::new(BOOK_PLIES);
+    let (used, errors) = builder.add_pgn(&text);
+    for err in &errors {
+        eprintln!("skipped the rest of {}", err);
+    }
+
+    let book = builder.build();
+    book.save(out)
+        .map_err(|err| format!("unable to write '{}': {}", out, err))?;
 
-    engine.run()?;
+    println!(
+        "{} games, {} book entries written to {}",
+        used,
+        book.len(),
+        out
+    );
 
     Ok(())
 }
+
+/// Build the list of themes to cycle through. A `--theme` argument naming
+/// a built-in theme or a theme file moves that theme to the front.
+///
+fn load_themes(name: Option<&str>) -> Result<Vec<Theme>, String> {
+    let mut themes = Theme::builtins();
+
+    if let Some(name) = name {
+        let theme = match Theme::builtin(name) {
+            Some(theme) => theme,
+            None => Theme::load(name)
+                .map_err(|err| format!("unable to load theme '{}': {:?}", name, err))?,
+        };
+
+        themes.retain(|t| t.name != theme.name);
+        themes.insert(0, theme);
+    }
+
+    Ok(themes)
+}
@@ -0,0 +1,241 @@
+use crate::core::fen::CastlingNotation;
+use crate::core::{notation, BughouseGame, Player, BOARD_NAMES};
+
+use super::{ClientMessage, Host, Outbox, Seat, ServerMessage};
+
+/// The seats in the order they are filled: both sides of board A, then
+/// both sides of board B. White on A partners Black on B.
+///
+const SEATS: [Seat; 4] = [
+    Seat {
+        board: 0,
+        player: Player::White,
+    },
+    Seat {
+        board: 0,
+        player: Player::Black,
+    },
+    Seat {
+        board: 1,
+        player: Player::White,
+    },
+    Seat {
+        board: 1,
+        player: Player::Black,
+    },
+];
+
+/// Runs a game of bughouse for four players, sending everybody both
+/// boards, including the pockets, after every move.
+///
+pub struct BughouseHost {
+    game: BughouseGame,
+    seated: [bool; 4],
+    started: bool,
+    /// Whether the result has been sent.
+    over: bool,
+}
+
+impl BughouseHost {
+    pub fn new() -> Self {
+        Self {
+            game: BughouseGame::new(),
+            seated: [false; 4],
+            started: false,
+            over: false,
+        }
+    }
+
+    /// Play a move `seat` has sent, if it is legal.
+    ///
+    fn attempt(&mut self, seat: Seat, text: &str) -> Outbox {
+        let game = &mut self.game.boards[seat.board];
+
+        if seat.player != game.turn {
+            return vec![(seat, notice("It is not your turn"))];
+        }
+
+        let Some((from, m)) = notation::parse_move(&game.board, seat.player, text) else {
+            return vec![(seat, notice("Illegal move"))];
+        };
+
+        game.play(from, m);
+
+        // a capture may have filled the other board's pocket
+        //
+        self.game.update();
+
+        self.progress()
+    }
+
+    /// Both boards, or the result once either game is decided.
+    ///
+    fn progress(&mut self) -> Outbox {
+        if self.game.outcome().is_some() {
+            self.over = true;
+            return self.game_over();
+        }
+
+        let mut outbox = Vec::new();
+
+        for board in 0..2 {
+            let message = self.position(board);
+
+            outbox.extend(SEATS.map(|seat| (seat, message.clone())));
+        }
+
+        outbox
+    }
+
+    fn position(&self, board: usize) -> ServerMessage {
+        let game = &self.game.boards[board];
+
+        ServerMessage::Position {
+            board,
+            fen: game.to_fen_with(CastlingNotation::Shredder),
+            clocks: [Player::White, Player::Black]
+                .map(|p| game.timers[p as usize].remaining() as u64),
+        }
+    }
+
+    fn game_over(&self) -> Outbox {
+        let mut outbox = Vec::new();
+
+        for (board, game) in self.game.boards.iter().enumerate() {
+            let message = ServerMessage::Over {
+                board,
+                outcome: game.outcome.expect("Both games are over"),
+                fen: game.to_fen_with(CastlingNotation::Shredder),
+            };
+
+            outbox.extend(SEATS.map(|seat| (seat, message.clone())));
+        }
+
+        outbox
+    }
+}
+
+impl Default for BughouseHost {
+    fn default() -> Self {
+        BughouseHost::new()
+    }
+}
+
+impl Host for BughouseHost {
+    fn free_seat(&self) -> Option<Seat> {
+        SEATS
+            .into_iter()
+            .zip(self.seated)
+            .find(|(_, seated)| !seated)
+            .map(|(seat, _)| seat)
+    }
+
+    /// Seat a player, starting both games once all four are here.
+    ///
+    fn join(&mut self, seat: Seat) -> Outbox {
+        self.seated[index(seat)] = true;
+
+        let mut outbox = vec![(
+            seat,
+            ServerMessage::Welcome {
+                seat,
+                variant: "bughouse".into(),
+                hidden: false,
+            },
+        )];
+
+        let waiting = self.seated.iter().filter(|seated| !**seated).count();
+
+        if self.started {
+            // back after losing the connection
+            //
+            let messages = match self.over {
+                true => self.game_over(),
+                false => self.progress(),
+            };
+
+            outbox.extend(messages.into_iter().filter(|(s, _)| *s == seat));
+            outbox.extend(others(seat, &format!("{} is back", self.describe(seat))));
+        } else if waiting == 0 {
+            self.started = true;
+            self.game.start();
+
+            outbox.extend(self.progress());
+            for seat in SEATS {
+                outbox.push((seat, ServerMessage::Announce("The game has started".into())));
+            }
+        } else {
+            let text = match waiting {
+                1 => "Waiting for 1 more player".into(),
+                n => format!("Waiting for {} more players", n),
+            };
+
+            for seat in SEATS {
+                outbox.push((seat, ServerMessage::Notice(text.clone())));
+            }
+        }
+
+        outbox
+    }
+
+    fn leave(&mut self, seat: Seat) -> Outbox {
+        self.seated[index(seat)] = false;
+
+        others(seat, &format!("{} has left the game", self.describe(seat)))
+    }
+
+    fn handle(&mut self, seat: Seat, message: ClientMessage) -> Outbox {
+        if !self.started {
+            return vec![(seat, notice("Waiting for the other players"))];
+        }
+        if self.over {
+            return vec![(seat, notice("The game is over"))];
+        }
+
+        match message {
+            ClientMessage::Resign => {
+                self.game.boards[seat.board].resign_player(seat.player);
+                self.game.update();
+                self.progress()
+            }
+            ClientMessage::Move(text) => self.attempt(seat, &text),
+        }
+    }
+
+    fn describe(&self, seat: Seat) -> String {
+        format!("{} on board {}", seat.player, BOARD_NAMES[seat.board])
+    }
+
+    /// Let a fallen flag decide the match.
+    ///
+    fn tick(&mut self) -> Outbox {
+        if !self.started || self.over {
+            return Vec::new();
+        }
+
+        self.game.update();
+
+        match self.game.outcome() {
+            Some(_) => self.progress(),
+            None => Vec::new(),
+        }
+    }
+}
+
+fn index(seat: Seat) -> usize {
+    seat.board * 2 + seat.player as usize
+}
+
+/// Tell everybody but `seat` something.
+///
+fn others(seat: Seat, text: &str) -> Outbox {
+    SEATS
+        .into_iter()
+        .filter(|s| *s != seat)
+        .map(|s| (s, ServerMessage::Notice(text.into())))
+        .collect()
+}
+
+fn notice(text: &str) -> ServerMessage {
+    ServerMessage::Notice(text.into())
+}
@@ -0,0 +1,286 @@
+use std::{
+    io,
+    rc::Rc,
+    sync::mpsc::{self, Receiver},
+    time::Duration,
+};
+
+use message_io::network::{Endpoint, NetEvent, Transport};
+use message_io::node::{self, NodeHandler, NodeTask};
+
+use crate::{
+    core::{
+        fen::{self, FenError},
+        validate, variant, BughouseGame, Chess, Player, Request,
+    },
+    input::{CrosstermInput, Error, Input, Keymap},
+};
+
+use super::{net, ClientMessage, Seat, ServerMessage};
+
+/// How long to wait for the server to seat us.
+///
+const WELCOME_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What the network thread passes on to the game.
+///
+enum Event {
+    Message(ServerMessage),
+    Disconnected,
+}
+
+struct Connection {
+    handler: NodeHandler<()>,
+    server: Endpoint,
+    events: Receiver<Event>,
+    _task: NodeTask,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // let the network thread finish, so the task can be dropped
+        //
+        self.handler.stop();
+    }
+}
+
+/// Plays a game refereed over the network. Keys are read from the
+/// terminal as with [`CrosstermInput`], but moves go to the referee and
+/// the position comes back from it. Clones share the connection, so one
+/// input can be cloned to serve both sides of the board.
+///
+#[derive(Clone)]
+pub struct NetworkInput {
+    local: CrosstermInput,
+    connection: Rc<Connection>,
+    seat: Seat,
+    variant: String,
+    hidden: bool,
+}
+
+impl NetworkInput {
+    /// Connect to the referee at `addr`, e.g. `127.0.0.1:7878`, and
+    /// wait to be given a seat.
+    ///
+    pub fn connect(addr: &str, keymap: Keymap) -> io::Result<Self> {
+        let (handler, listener) = node::split::<()>();
+
+        let (server, _) = handler.network().connect_sync(Transport::FramedTcp, addr)?;
+
+        let (sender, events) = mpsc::channel();
+
+        let task = listener.for_each_async(move |event| {
+            let event = match event.network() {
+                NetEvent::Message(_, data) => match net::decode(data) {
+                    Some(message) => Event::Message(message),
+                    None => return,
+                },
+                NetEvent::Disconnected(_) => Event::Disconnected,
+                _ => return,
+            };
+            // the game may already be gone if we are shutting down
+            //
+            let _ = sender.send(event);
+        });
+
+        let connection = Rc::new(Connection {
+            handler,
+            server,
+            events,
+            _task: task,
+        });
+
+        let (seat, variant, hidden) = match connection.events.recv_timeout(WELCOME_TIMEOUT) {
+            Ok(Event::Message(ServerMessage::Welcome {
+                seat,
+                variant,
+                hidden,
+            })) => (seat, variant, hidden),
+            Ok(Event::Message(ServerMessage::Notice(text))) => return Err(io::Error::other(text)),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "the referee did not seat us",
+                ))
+            }
+        };
+
+        Ok(Self {
+            local: CrosstermInput::new(keymap),
+            connection,
+            seat,
+            variant,
+            hidden,
+        })
+    }
+
+    /// The name of the variant the referee is running.
+    ///
+    pub fn variant(&self) -> &str {
+        &self.variant
+    }
+
+    /// A game to play our seat in, waiting for the referee's position.
+    ///
+    pub fn game(&self) -> io::Result<Chess> {
+        let variant = variant::by_name(&self.variant).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown variant '{}'", self.variant),
+            )
+        })?;
+
+        let mut game = Chess::with_variant(variant);
+        self.seat_at(&mut game, self.seat.player);
+
+        Ok(game)
+    }
+
+    /// A game of bughouse to play our seat in. The keys play our board,
+    /// while our partner plays the other one, facing the other way.
+    ///
+    pub fn bughouse(&self) -> BughouseGame {
+        let mut game = BughouseGame::new();
+        let Seat { board, player } = self.seat;
+
+        game.active = board;
+        self.seat_at(&mut game.boards[board], player);
+        self.seat_at(&mut game.boards[1 - board], player.opponent());
+
+        game
+    }
+
+    fn seat_at(&self, game: &mut Chess, player: Player) {
+        game.refereed = true;
+        game.view.perspective = player;
+        game.view.follow_turn = false;
+
+        if self.hidden {
+            game.view.visible_to = Some(player);
+        }
+    }
+
+    /// Take in what the referee has sent since last time.
+    ///
+    fn events(&self) -> impl Iterator<Item = Event> + '_ {
+        self.connection.events.try_iter()
+    }
+
+    fn send_requests(&self, game: &mut Chess) {
+        for request in std::mem::take(&mut game.requests) {
+            self.send(&match request {
+                Request::Move(text) => ClientMessage::Move(text),
+                Request::Resign => ClientMessage::Resign,
+            });
+        }
+    }
+
+    fn send(&self, message: &ClientMessage) {
+        let connection = &self.connection;
+
+        connection
+            .handler
+            .network()
+            .send(connection.server, &net::encode(message));
+    }
+}
+
+impl Input for NetworkInput {
+    fn update(&mut self, game: &mut Chess) -> Result<(), Error> {
+        for event in self.events() {
+            match event {
+                Event::Message(message) => receive(game, message),
+                Event::Disconnected => lost(game),
+            }
+        }
+
+        self.local.update(game)?;
+        self.send_requests(game);
+
+        Ok(())
+    }
+
+    fn update_bughouse(&mut self, game: &mut BughouseGame) -> Result<(), Error> {
+        let ours = self.seat.board;
+
+        for event in self.events() {
+            match event {
+                Event::Message(
+                    message @ (ServerMessage::Position { board, .. }
+                    | ServerMessage::Over { board, .. }),
+                ) if board < game.boards.len() => receive(&mut game.boards[board], message),
+                Event::Message(message) => receive(&mut game.boards[ours], message),
+                Event::Disconnected => lost(&mut game.boards[ours]),
+            }
+        }
+
+        let game = &mut game.boards[ours];
+
+        self.local.update(game)?;
+        self.send_requests(game);
+
+        Ok(())
+    }
+}
+
+fn lost(game: &mut Chess) {
+    game.view.message = Some("Lost the connection to the referee".into());
+}
+
+fn receive(game: &mut Chess, message: ServerMessage) {
+    match message {
+        // we are seated before the game begins
+        //
+        ServerMessage::Welcome { .. } => {}
+        ServerMessage::Position { fen, clocks, .. } => {
+            // with the opponent's pieces hidden there is too little to judge
+            //
+            let judge = game.view.visible_to.is_none();
+            set_position(game, &fen, judge);
+
+            for (timer, time) in game.timers.iter_mut().zip(clocks) {
+                timer.set_remaining(Duration::from_millis(time));
+            }
+        }
+        ServerMessage::Announce(text) => {
+            game.view.message = Some(text.clone());
+            game.view.announcements.push(text);
+        }
+        ServerMessage::Notice(text) => game.view.message = Some(text),
+        ServerMessage::Over { outcome, fen, .. } => {
+            // the game is over, so the whole board can be shown, even
+            // one play could not go on from, such as an exploded king
+            //
+            set_position(game, &fen, false);
+            game.view.visible_to = None;
+            game.view.announcements.push(outcome.to_string());
+            game.finish(outcome);
+        }
+    }
+}
+
+/// Take on the position the referee sent, unless `judge` is set and it
+/// is one no game could reach.
+///
+fn set_position(game: &mut Chess, text: &str, judge: bool) {
+    let fen = fen::parse_variant(text, game.board.variant.clone()).and_then(|fen| {
+        let problems = match judge {
+            true => validate(&fen.board, fen.turn),
+            false => Vec::new(),
+        };
+        match problems.is_empty() {
+            true => Ok(fen),
+            false => Err(FenError::Illegal(problems)),
+        }
+    });
+
+    match fen {
+        Ok(fen) => {
+            game.board = fen.board;
+            if game.turn != fen.turn {
+                game.change_player();
+            }
+        }
+        Err(err) => game.view.message = Some(format!("Bad position from the referee: {}", err)),
+    }
+}
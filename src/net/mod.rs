@@ -0,0 +1,12 @@
+mod bughouse;
+mod client;
+#[allow(clippy::module_inception)]
+mod net;
+mod referee;
+mod server;
+
+pub use bughouse::BughouseHost;
+pub use client::NetworkInput;
+pub use net::{ClientMessage, Seat, ServerMessage};
+pub use referee::Referee;
+pub use server::{serve, Host, Outbox};
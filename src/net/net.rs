@@ -1 +1,67 @@
-// pub fn do_thing() {}
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::core::{Outcome, Player};
+
+/// Where a player sits: the colour they play on one of the boards.
+/// Kriegspiel has only board 0.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Seat {
+    pub board: usize,
+    pub player: Player,
+}
+
+impl Seat {
+    pub fn new(board: usize, player: Player) -> Self {
+        Self { board, player }
+    }
+}
+
+/// Sent by a player to the referee.
+///
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ClientMessage {
+    /// Try to play a move, in SAN or coordinate notation.
+    Move(String),
+    Resign,
+}
+
+/// Sent by the referee to a player.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ServerMessage {
+    /// Where the player has been seated, the variant played, and whether
+    /// the opponent's pieces are hidden from them.
+    Welcome {
+        seat: Seat,
+        variant: String,
+        hidden: bool,
+    },
+    /// The position on a board as the player may see it, as a FEN string,
+    /// and the milliseconds left on each clock.
+    Position {
+        board: usize,
+        fen: String,
+        clocks: [u64; 2],
+    },
+    /// Something the referee says to both players.
+    Announce(String),
+    /// Something for this player only, such as why a message was ignored.
+    Notice(String),
+    /// The game on a board is over, and the whole board may be shown.
+    Over {
+        board: usize,
+        outcome: Outcome,
+        fen: String,
+    },
+}
+
+pub fn encode<T: Serialize>(message: &T) -> Vec<u8> {
+    bincode::serialize(message).expect("Messages can always be serialized")
+}
+
+/// Read a message, or `None` if the data is not a message of type `T`.
+///
+pub fn decode<T: DeserializeOwned>(data: &[u8]) -> Option<T> {
+    bincode::deserialize(data).ok()
+}
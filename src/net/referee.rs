@@ -0,0 +1,308 @@
+use crate::core::fen::{self, CastlingNotation};
+use crate::core::{notation, Board, Chess, MoveResult, PieceType, Player, Vec2};
+
+use super::{ClientMessage, Host, Outbox, Seat, ServerMessage};
+
+const PLAYERS: [Player; 2] = [Player::White, Player::Black];
+
+/// Runs a game of Kriegspiel: it alone knows where all the pieces are,
+/// shows each player only their own, rules on the moves they try and
+/// announces what both players are entitled to hear.
+///
+pub struct Referee {
+    game: Chess,
+    seated: [bool; 2],
+    started: bool,
+}
+
+impl Referee {
+    pub fn new(game: Chess) -> Self {
+        Self {
+            game,
+            seated: [false; 2],
+            started: false,
+        }
+    }
+
+    /// Rule on a move `player` has tried, playing it if it is legal.
+    ///
+    fn attempt(&mut self, player: Player, text: &str) -> Outbox {
+        if player != self.game.turn {
+            return vec![(seat_for(player), notice("It is not your turn"))];
+        }
+
+        let mut outbox = Vec::new();
+
+        let Some((from, m)) = notation::parse_move(&self.game.board, player, text) else {
+            announce(&mut outbox, format!("{} tried an illegal move", player));
+            return outbox;
+        };
+
+        self.game.play(from, m);
+
+        for announcement in self.announcements() {
+            announce(&mut outbox, announcement);
+        }
+
+        match self.game.outcome {
+            Some(_) => outbox.extend(self.game_over()),
+            None => outbox.extend(PLAYERS.map(|p| self.position(p))),
+        }
+
+        outbox
+    }
+
+    /// What both players hear about the move just played: whether it
+    /// captured, where any check comes from, and how many captures the
+    /// pawns of the side to move can make.
+    ///
+    fn announcements(&self) -> Vec<String> {
+        let board = &self.game.board;
+        let record = self.game.last_move().expect("A move has been played");
+
+        let mover = record.player;
+        let turn = self.game.turn;
+
+        let mut said = Vec::new();
+
+        match record.captured {
+            Some(ty) => {
+                let square = match record.result {
+                    MoveResult::Capture(pos) => pos,
+                    _ => record.to,
+                };
+                let what = match ty {
+                    PieceType::Pawn => "a pawn",
+                    _ => "a piece",
+                };
+                said.push(format!(
+                    "{} captured {} on {}",
+                    mover,
+                    what,
+                    notation::square_name(board, square)
+                ));
+            }
+            None => said.push(format!("{} moved", mover)),
+        }
+
+        if let Some(king) = board.find_king(turn) {
+            if board.variant.in_check(board, turn) {
+                for (pos, _) in board.pieces(mover) {
+                    if board.attacks(pos).contains(&king) {
+                        said.push(format!("Check {}", check_line(board, pos, king)));
+                    }
+                }
+            }
+        }
+
+        if self.game.outcome.is_none() {
+            let tries = board
+                .all_legal_moves(turn)
+                .iter()
+                .filter(|(from, m)| {
+                    matches!(board.get(*from), Some(p) if p.ty == PieceType::Pawn)
+                        && board.is_capture(m)
+                })
+                .count();
+
+            match tries {
+                0 => {}
+                1 => said.push(format!("{} has 1 pawn try", turn)),
+                n => said.push(format!("{} has {} pawn tries", turn, n)),
+            }
+        }
+
+        said
+    }
+
+    /// The position as `player` may see it: their own pieces only, with
+    /// no en passant square or halfmove clock, which would give away
+    /// whether the opponent last moved a pawn.
+    ///
+    fn position(&self, player: Player) -> (Seat, ServerMessage) {
+        let mut board = self.game.board.clone();
+
+        let hidden: Vec<Vec2> = board
+            .pieces(player.opponent())
+            .map(|(pos, _)| pos)
+            .collect();
+        for pos in hidden {
+            board.take_piece(pos);
+        }
+        board.en_passant = None;
+        board.halfmove_clock = 0;
+
+        let fen = fen::write(
+            &board,
+            self.game.turn,
+            self.game.fullmove(),
+            CastlingNotation::Shredder,
+        );
+
+        (
+            seat_for(player),
+            ServerMessage::Position {
+                board: 0,
+                fen,
+                clocks: self.clocks(),
+            },
+        )
+    }
+
+    /// The result, and the whole board, for both players.
+    ///
+    fn game_over(&self) -> Outbox {
+        let outcome = self.game.outcome.expect("The game is over");
+        let fen = self.game.to_fen_with(CastlingNotation::Shredder);
+
+        PLAYERS
+            .map(|p| {
+                let fen = fen.clone();
+                (
+                    seat_for(p),
+                    ServerMessage::Over {
+                        board: 0,
+                        outcome,
+                        fen,
+                    },
+                )
+            })
+            .into()
+    }
+
+    fn clocks(&self) -> [u64; 2] {
+        PLAYERS.map(|p| self.game.timers[p as usize].remaining() as u64)
+    }
+}
+
+impl Host for Referee {
+    /// A seat that has nobody in it, White's first.
+    ///
+    fn free_seat(&self) -> Option<Seat> {
+        PLAYERS
+            .into_iter()
+            .find(|p| !self.seated[*p as usize])
+            .map(seat_for)
+    }
+
+    /// Seat a player, starting the game once both players are here.
+    ///
+    fn join(&mut self, seat: Seat) -> Outbox {
+        let player = seat.player;
+        self.seated[player as usize] = true;
+
+        let mut outbox = vec![(
+            seat,
+            ServerMessage::Welcome {
+                seat,
+                variant: self.game.board.variant.name().into(),
+                hidden: true,
+            },
+        )];
+
+        if self.started {
+            // back after losing the connection
+            //
+            outbox.push(match self.game.outcome {
+                Some(_) => self.game_over().remove(player as usize),
+                None => self.position(player),
+            });
+            outbox.push((
+                seat_for(player.opponent()),
+                ServerMessage::Notice(format!("{} is back", player)),
+            ));
+        } else if self.seated.iter().all(|seated| *seated) {
+            self.started = true;
+            self.game.start();
+
+            outbox.extend(PLAYERS.map(|p| self.position(p)));
+            announce(&mut outbox, "The game has started".into());
+        } else {
+            outbox.push((seat_for(player), notice("Waiting for an opponent")));
+        }
+
+        outbox
+    }
+
+    fn leave(&mut self, seat: Seat) -> Outbox {
+        let player = seat.player;
+        self.seated[player as usize] = false;
+
+        vec![(
+            seat_for(player.opponent()),
+            ServerMessage::Notice(format!("{} has left the game", player)),
+        )]
+    }
+
+    fn handle(&mut self, seat: Seat, message: ClientMessage) -> Outbox {
+        let player = seat.player;
+
+        if !self.started {
+            return vec![(seat_for(player), notice("Waiting for an opponent"))];
+        }
+        if self.game.outcome.is_some() {
+            return vec![(seat_for(player), notice("The game is over"))];
+        }
+
+        match message {
+            ClientMessage::Resign => {
+                self.game.resign_player(player);
+                self.game_over()
+            }
+            ClientMessage::Move(text) => self.attempt(player, &text),
+        }
+    }
+}
+
+fn announce(outbox: &mut Outbox, text: String) {
+    for player in PLAYERS {
+        outbox.push((seat_for(player), ServerMessage::Announce(text.clone())));
+    }
+}
+
+/// The one seat for `player` in a game of Kriegspiel.
+///
+fn seat_for(player: Player) -> Seat {
+    Seat::new(0, player)
+}
+
+fn notice(text: &str) -> ServerMessage {
+    ServerMessage::Notice(text.into())
+}
+
+/// Where a check from the piece at `from` on the king at `king` comes
+/// from, as the referee describes it.
+///
+fn check_line(board: &Board, from: Vec2, king: Vec2) -> &'static str {
+    let (dx, dy) = (from.x - king.x, from.y - king.y);
+
+    if dx == 0 {
+        "on the file"
+    } else if dy == 0 {
+        "on the rank"
+    } else if dx.abs() == dy.abs() {
+        let other = Vec2::new(1, -dx.signum() * dy.signum());
+
+        if diagonal_length(board, king, Vec2::new(dx.signum(), dy.signum()))
+            >= diagonal_length(board, king, other)
+        {
+            "on the long diagonal"
+        } else {
+            "on the short diagonal"
+        }
+    } else {
+        "by a knight"
+    }
+}
+
+/// The number of squares on the diagonal through `pos` in direction `dir`.
+///
+fn diagonal_length(board: &Board, pos: Vec2, dir: Vec2) -> usize {
+    let count = |dir: Vec2| {
+        (1i16..)
+            .take_while(|i| board.contains(pos + dir * *i))
+            .count()
+    };
+
+    1 + count(dir) + count(dir * -1)
+}
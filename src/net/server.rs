@@ -0,0 +1,116 @@
+use std::{io, time::Duration};
+
+use message_io::network::{Endpoint, NetEvent, Transport};
+use message_io::node::{self, NodeEvent};
+
+use super::{net, ClientMessage, Seat, ServerMessage};
+
+/// How often the host is given the chance to act on its own, e.g. to
+/// notice a fallen flag.
+///
+const TICK: Duration = Duration::from_millis(100);
+
+/// Messages to send, each to one of the seats.
+///
+pub type Outbox = Vec<(Seat, ServerMessage)>;
+
+/// A game run by the server for players connected over the network,
+/// such as Kriegspiel, run by a [`super::Referee`].
+///
+pub trait Host {
+    /// A seat that has nobody in it, or `None` if the game is full.
+    ///
+    fn free_seat(&self) -> Option<Seat>;
+
+    /// Seat a player who has just connected.
+    ///
+    fn join(&mut self, seat: Seat) -> Outbox;
+
+    /// Free a seat whose player has lost their connection.
+    ///
+    fn leave(&mut self, seat: Seat) -> Outbox;
+
+    fn handle(&mut self, seat: Seat, message: ClientMessage) -> Outbox;
+
+    /// Who sits in `seat`, for the log.
+    ///
+    fn describe(&self, seat: Seat) -> String {
+        seat.player.to_string()
+    }
+
+    /// Called every so often whether or not anything has happened.
+    ///
+    fn tick(&mut self) -> Outbox {
+        Vec::new()
+    }
+}
+
+/// Run `host` for the players who connect to `addr`, e.g. `0.0.0.0:7878`,
+/// seating them in the order the host chooses. A player who loses their
+/// connection may connect again to take their seat back. Runs until the
+/// process is stopped.
+///
+pub fn serve(mut host: impl Host, addr: &str) -> io::Result<()> {
+    let (handler, listener) = node::split::<()>();
+
+    let (_, local) = handler.network().listen(Transport::FramedTcp, addr)?;
+    println!("Listening on {}", local);
+
+    let mut seats: Vec<(Seat, Endpoint)> = Vec::new();
+
+    handler.signals().send(());
+
+    listener.for_each(move |event| {
+        let outbox = match event {
+            NodeEvent::Signal(()) => {
+                handler.signals().send_with_timer((), TICK);
+                host.tick()
+            }
+            NodeEvent::Network(event) => match event {
+                NetEvent::Accepted(endpoint, _) => match host.free_seat() {
+                    Some(seat) => {
+                        println!("{} joined from {}", host.describe(seat), endpoint.addr());
+                        seats.push((seat, endpoint));
+                        host.join(seat)
+                    }
+                    None => {
+                        let full = ServerMessage::Notice("The game is full".into());
+                        handler.network().send(endpoint, &net::encode(&full));
+                        handler.network().remove(endpoint.resource_id());
+                        Vec::new()
+                    }
+                },
+                NetEvent::Message(endpoint, data) => {
+                    match (seat_of(&seats, endpoint), net::decode(data)) {
+                        (Some(seat), Some(message)) => host.handle(seat, message),
+                        _ => Vec::new(),
+                    }
+                }
+                NetEvent::Disconnected(endpoint) => match seat_of(&seats, endpoint) {
+                    Some(seat) => {
+                        println!("{} left", host.describe(seat));
+                        seats.retain(|(_, e)| *e != endpoint);
+                        host.leave(seat)
+                    }
+                    None => Vec::new(),
+                },
+                NetEvent::Connected(..) => Vec::new(),
+            },
+        };
+
+        for (seat, message) in outbox {
+            for (_, endpoint) in seats.iter().filter(|(s, _)| *s == seat) {
+                handler.network().send(*endpoint, &net::encode(&message));
+            }
+        }
+    });
+
+    Ok(())
+}
+
+fn seat_of(seats: &[(Seat, Endpoint)], endpoint: Endpoint) -> Option<Seat> {
+    seats
+        .iter()
+        .find(|(_, e)| *e == endpoint)
+        .map(|(seat, _)| *seat)
+}
@@ -1,7 +1,7 @@
 pub mod theme;
 pub mod tui;
 
-use crate::core::Chess;
+use crate::core::{BughouseGame, Chess};
 
 pub use theme::Theme;
 
@@ -12,5 +12,12 @@ pub trait Renderer {
     fn init(&mut self) -> Result<(), Error>;
     fn set_theme(&mut self, theme: Theme);
     fn render(&mut self, game: &mut Chess) -> Result<(), Error>;
+
+    /// Show a game of bughouse. By default only the active board is shown.
+    ///
+    fn render_bughouse(&mut self, game: &mut BughouseGame) -> Result<(), Error> {
+        self.render(&mut game.boards[game.active])
+    }
+
     fn shutdown(&mut self) -> Result<(), Error>;
 }
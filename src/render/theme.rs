@@ -1,23 +1,84 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
 use tui::style::Color;
 
 use crate::core::Player;
 
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(err: toml::de::Error) -> Self {
+        Self::Parse(err)
+    }
+}
+
+/// Colours used to draw the game. Any field missing from a theme
+/// file is taken from [`Theme::default`].
+///
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Theme {
+    #[serde(default)]
+    pub name: String,
     pub black: PlayerTheme,
     pub white: PlayerTheme,
+    #[serde(with = "color")]
     pub cursor_valid: Color,
+    /// Background of the squares the last move was played from and to.
+    #[serde(with = "color")]
+    pub last_move: Color,
+    /// Background of the king's square while it is in check.
+    #[serde(with = "color")]
+    pub check: Color,
+    /// Background of squares where the selected piece can capture.
+    #[serde(with = "color")]
+    pub capture: Color,
+    /// Marker drawn on empty squares the selected piece can move to.
+    #[serde(with = "color")]
+    pub marker: Color,
+    /// Background of squares attacked by the opponent, when shown.
+    #[serde(with = "color")]
+    pub attacked: Color,
+    /// Flashed on the squares caught in an Atomic explosion.
+    #[serde(with = "color")]
+    pub explosion: Color,
+    /// Background of the squares of the move suggested as a hint.
+    #[serde(with = "color")]
+    pub hint: Color,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PlayerTheme {
+    #[serde(with = "color")]
     pub cursor: Color,
+    #[serde(with = "color")]
     pub piece: Color,
+    #[serde(with = "color")]
     pub tile: Color,
+    #[serde(with = "color")]
     pub tile_highlight: Color,
 }
 
+/// Names of the themes compiled into the binary, in the order
+/// they are cycled through at runtime.
+///
+pub const BUILTIN: [&str; 4] = ["default", "wood", "ansi", "mono"];
+
 impl Default for Theme {
     fn default() -> Self {
         Self {
+            name: "default".into(),
             black: PlayerTheme {
                 cursor: Color::Rgb(40, 60, 120),
                 piece: Color::Rgb(0, 170, 255),
@@ -31,6 +92,13 @@ impl Default for Theme {
                 tile_highlight: Color::Rgb(160, 160, 100),
             },
             cursor_valid: Color::Rgb(100, 130, 100),
+            last_move: Color::Rgb(70, 80, 40),
+            check: Color::Rgb(170, 30, 30),
+            capture: Color::Rgb(150, 70, 50),
+            marker: Color::Rgb(60, 60, 30),
+            attacked: Color::Rgb(70, 30, 40),
+            explosion: Color::Rgb(230, 120, 20),
+            hint: Color::Rgb(40, 100, 130),
         }
     }
 }
@@ -42,4 +110,196 @@ impl Theme {
             Player::White => &self.white,
         }
     }
+
+    /// Look up one of the [`BUILTIN`] themes by name.
+    ///
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "default" => Some(Theme::default()),
+            "wood" => Some(Theme::wood()),
+            "ansi" => Some(Theme::ansi()),
+            "mono" => Some(Theme::mono()),
+            _ => None,
+        }
+    }
+
+    /// All of the built-in themes. When the terminal does not advertise
+    /// truecolor support the 16-colour theme is placed first.
+    ///
+    pub fn builtins() -> Vec<Theme> {
+        let mut themes: Vec<Theme> = BUILTIN.iter().filter_map(|n| Theme::builtin(n)).collect();
+
+        if !supports_truecolor() {
+            if let Some(i) = themes.iter().position(|t| t.name == "ansi") {
+                let ansi = themes.remove(i);
+                themes.insert(0, ansi);
+            }
+        }
+
+        themes
+    }
+
+    /// Load a theme from a TOML file. Colours are written as `"#rrggbb"`,
+    /// a 256-colour palette index such as `"238"`, or one of the 16
+    /// named terminal colours (`"dark_gray"`, `"light_blue"`, ...).
+    ///
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Theme, Error> {
+        let data = std::fs::read_to_string(path.as_ref())?;
+        let mut theme: Theme = toml::from_str(&data)?;
+
+        if theme.name.is_empty() {
+            if let Some(stem) = path.as_ref().file_stem() {
+                theme.name = stem.to_string_lossy().into();
+            }
+        }
+
+        Ok(theme)
+    }
+
+    fn wood() -> Self {
+        Self {
+            name: "wood".into(),
+            black: PlayerTheme {
+                cursor: Color::Rgb(90, 60, 150),
+                piece: Color::Rgb(20, 15, 10),
+                tile: Color::Rgb(181, 136, 99),
+                tile_highlight: Color::Rgb(170, 162, 58),
+            },
+            white: PlayerTheme {
+                cursor: Color::Rgb(150, 120, 200),
+                piece: Color::Rgb(255, 250, 240),
+                tile: Color::Rgb(240, 217, 181),
+                tile_highlight: Color::Rgb(205, 210, 106),
+            },
+            cursor_valid: Color::Rgb(110, 160, 90),
+            last_move: Color::Rgb(205, 190, 90),
+            check: Color::Rgb(220, 50, 40),
+            capture: Color::Rgb(200, 110, 80),
+            marker: Color::Rgb(90, 80, 40),
+            attacked: Color::Rgb(215, 140, 120),
+            explosion: Color::Rgb(240, 140, 30),
+            hint: Color::Rgb(120, 170, 200),
+        }
+    }
+
+    fn ansi() -> Self {
+        Self {
+            name: "ansi".into(),
+            black: PlayerTheme {
+                cursor: Color::Blue,
+                piece: Color::LightCyan,
+                tile: Color::Black,
+                tile_highlight: Color::Yellow,
+            },
+            white: PlayerTheme {
+                cursor: Color::Gray,
+                piece: Color::White,
+                tile: Color::DarkGray,
+                tile_highlight: Color::LightYellow,
+            },
+            cursor_valid: Color::Green,
+            last_move: Color::Cyan,
+            check: Color::LightRed,
+            capture: Color::Red,
+            marker: Color::Black,
+            attacked: Color::Magenta,
+            explosion: Color::LightYellow,
+            hint: Color::LightBlue,
+        }
+    }
+
+    fn mono() -> Self {
+        Self {
+            name: "mono".into(),
+            black: PlayerTheme {
+                cursor: Color::Gray,
+                piece: Color::Black,
+                tile: Color::DarkGray,
+                tile_highlight: Color::Gray,
+            },
+            white: PlayerTheme {
+                cursor: Color::Gray,
+                piece: Color::White,
+                tile: Color::Black,
+                tile_highlight: Color::Gray,
+            },
+            cursor_valid: Color::White,
+            last_move: Color::DarkGray,
+            check: Color::Gray,
+            capture: Color::Gray,
+            marker: Color::Black,
+            attacked: Color::DarkGray,
+            explosion: Color::White,
+            hint: Color::Gray,
+        }
+    }
+}
+
+fn supports_truecolor() -> bool {
+    matches!(
+        std::env::var("COLORTERM").as_deref(),
+        Ok("truecolor") | Ok("24bit")
+    )
+}
+
+/// Serde adapter storing a [`Color`] as a human friendly string.
+///
+mod color {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use tui::style::Color;
+
+    const NAMED: [(&str, Color); 17] = [
+        ("reset", Color::Reset),
+        ("black", Color::Black),
+        ("red", Color::Red),
+        ("green", Color::Green),
+        ("yellow", Color::Yellow),
+        ("blue", Color::Blue),
+        ("magenta", Color::Magenta),
+        ("cyan", Color::Cyan),
+        ("gray", Color::Gray),
+        ("dark_gray", Color::DarkGray),
+        ("light_red", Color::LightRed),
+        ("light_green", Color::LightGreen),
+        ("light_yellow", Color::LightYellow),
+        ("light_blue", Color::LightBlue),
+        ("light_magenta", Color::LightMagenta),
+        ("light_cyan", Color::LightCyan),
+        ("white", Color::White),
+    ];
+
+    pub fn serialize<S: Serializer>(col: &Color, s: S) -> Result<S::Ok, S::Error> {
+        match col {
+            Color::Rgb(r, g, b) => s.serialize_str(&format!("#{:02x}{:02x}{:02x}", r, g, b)),
+            Color::Indexed(i) => s.serialize_str(&i.to_string()),
+            named => {
+                let (name, _) = NAMED.iter().find(|(_, c)| c == named).unwrap();
+                s.serialize_str(name)
+            }
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Color, D::Error> {
+        let text = String::deserialize(d)?;
+        parse(&text).ok_or_else(|| D::Error::custom(format!("unknown colour '{}'", text)))
+    }
+
+    pub fn parse(text: &str) -> Option<Color> {
+        let text = text.trim();
+
+        if let Some(hex) = text.strip_prefix('#') {
+            if hex.len() != 6 {
+                return None;
+            }
+            let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+            return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+        }
+
+        if let Ok(index) = text.parse::<u8>() {
+            return Some(Color::Indexed(index));
+        }
+
+        let name = text.to_lowercase().replace(['-', ' '], "_");
+        NAMED.iter().find(|(n, _)| *n == name).map(|(_, c)| *c)
+    }
 }
@@ -5,18 +5,20 @@ use crossterm::{
 };
 use std::io;
 use tui::{
-    backend::CrosstermBackend,
-    layout::{Constraint, Layout},
+    backend::{Backend, CrosstermBackend},
+    layout::{Constraint, Direction, Layout, Rect},
     style::Style,
     text::{Span, Spans},
     widgets::Paragraph,
-    Terminal,
+    Frame, Terminal,
 };
 
-use crate::core::{Chess, Mode, MoveResult, Player};
+use crate::core::{Board, BughouseGame, Chess, Mode, MoveResult, Player, BOARD_NAMES};
 use crate::render::{Error, Renderer, Theme};
 
-use super::widgets::ChessWidget;
+use super::widgets::{
+    self, AnalysisWidget, ChessWidget, InfoWidget, OracleWidget, PocketWidget, ReviewWidget,
+};
 
 impl From<std::io::Error> for Error {
     fn from(_: std::io::Error) -> Self {
@@ -58,73 +60,85 @@ impl Renderer for TuiRenderer {
     }
 
     fn render(&mut self, game: &mut Chess) -> Result<(), Error> {
+        let theme = &self.theme;
+
         // render the game
         //
         self.terminal.as_mut().unwrap().draw(|frame| {
+            let (main, info) = split_info_panel(&game.board, frame.size());
+
             let layout = Layout::default()
-                .direction(tui::layout::Direction::Vertical)
-                .margin(0)
-                .constraints([
-                    Constraint::Min(1),
-                    Constraint::Length(8),
-                    Constraint::Min(1),
-                    Constraint::Min(1),
-                ])
-                .split(frame.size());
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(1)])
+                .split(main);
 
-            let widget = ChessWidget::new(&self.theme);
+            draw_board(frame, theme, layout[0], game);
 
-            frame.render_stateful_widget(widget, layout[1], game);
+            if let Some(info) = info {
+                let widget = InfoWidget::new(theme);
 
-            let theme = &self.theme;
+                frame.render_stateful_widget(widget, info, game);
+            }
 
-            // render clocks
+            // the oracle covers the info panel, or the board without one
             //
-            let white_clock = if game.turn == Player::White {
-                theme.white.piece
-            } else {
-                theme.white.tile
-            };
-
-            let black_clock = if game.turn == Player::Black {
-                theme.black.piece
-            } else {
-                theme.black.tile
-            };
-
-            let p1 = Paragraph::new(Spans::from(vec![Span::styled(
-                format!("{}", game.timers[0]),
-                Style::default().fg(white_clock),
-            )]));
-
-            frame.render_widget(p1, layout[2]);
-
-            let p2 = Paragraph::new(Spans::from(vec![
-                Span::from(" "),
-                Span::styled(
-                    format!("{}", game.timers[1]),
-                    Style::default().fg(black_clock),
-                ),
-            ]));
-
-            frame.render_widget(p2, layout[0]);
-
-            // render status line
+            if game.view.show_oracle {
+                let widget = OracleWidget::new(theme);
+
+                frame.render_stateful_widget(widget, info.unwrap_or(layout[0]), game);
+            }
+
+            if let (true, Some(info)) = (game.is_analysing(), info) {
+                let widget = AnalysisWidget::new(theme);
+
+                frame.render_stateful_widget(widget, info, game);
+            }
+
+            // the review covers everything but the status line
             //
-            if let Mode::Moving(from) = game.mode {
-                let msg = match game.get_move_result(from, game.cursor) {
-                    MoveResult::Cancel => "Cancel",
-                    MoveResult::Nothing => "Move",
-                    MoveResult::Capture(_) => "Capture",
-                    MoveResult::Castle => "Castle",
-                    MoveResult::Promotion(_) => "Promote",
-                    MoveResult::Invalid => "",
+            if game.view.review.is_some() {
+                let area = frame.size();
+                let area = Rect {
+                    height: area.height.saturating_sub(1),
+                    ..area
                 };
 
-                let status = Paragraph::new(msg);
+                frame.render_stateful_widget(ReviewWidget, area, game);
+            }
+
+            frame.render_widget(Paragraph::new(status(game)), layout[1]);
+        })?;
+
+        Ok(())
+    }
 
-                frame.render_widget(status, layout[3]);
+    fn render_bughouse(&mut self, game: &mut BughouseGame) -> Result<(), Error> {
+        let theme = &self.theme;
+
+        self.terminal.as_mut().unwrap().draw(|frame| {
+            let layout = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(1)])
+                .split(frame.size());
+
+            // the boards side by side, as the partners sit
+            //
+            let halves = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
+                .split(layout[0]);
+
+            for (board, area) in game.boards.iter_mut().zip(halves) {
+                draw_board(frame, theme, area, board);
             }
+
+            let status = format!(
+                "Board {}: {}",
+                BOARD_NAMES[game.active],
+                status(&game.boards[game.active])
+            );
+
+            frame.render_widget(Paragraph::new(status), layout[1]);
         })?;
 
         Ok(())
@@ -150,3 +164,144 @@ impl Renderer for TuiRenderer {
         self.theme = theme;
     }
 }
+
+/// Draw the board of `game` into `area`, with each player's clock and
+/// pocket above or below it.
+///
+fn draw_board<B: Backend>(frame: &mut Frame<B>, theme: &Theme, area: Rect, game: &mut Chess) {
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(0)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Min(ChessWidget::min_height(&game.board)),
+            Constraint::Length(1),
+        ])
+        .split(area);
+
+    let widget = ChessWidget::new(theme);
+
+    frame.render_stateful_widget(widget, layout[1], game);
+
+    // render clocks, the bottom clock belongs to
+    // the player facing the board.
+    //
+    let (white_area, black_area) = match game.view.perspective {
+        Player::White => (layout[2], layout[0]),
+        Player::Black => (layout[0], layout[2]),
+    };
+
+    let white_clock = if game.turn == Player::White {
+        theme.white.piece
+    } else {
+        theme.white.tile
+    };
+
+    let black_clock = if game.turn == Player::Black {
+        theme.black.piece
+    } else {
+        theme.black.tile
+    };
+
+    let p1 = Paragraph::new(Spans::from(vec![Span::styled(
+        format!("{}", game.timers[0]),
+        Style::default().fg(white_clock),
+    )]));
+
+    frame.render_widget(p1, white_area);
+
+    let p2 = Paragraph::new(Spans::from(vec![
+        Span::from(" "),
+        Span::styled(
+            format!("{}", game.timers[1]),
+            Style::default().fg(black_clock),
+        ),
+    ]));
+
+    frame.render_widget(p2, black_area);
+
+    // pockets share the clock rows, beside the board
+    //
+    if game.board.variant.has_pockets() {
+        for (player, area) in [(Player::White, white_area), (Player::Black, black_area)] {
+            let widget = PocketWidget::new(theme, player);
+
+            frame.render_stateful_widget(widget, area, game);
+        }
+    }
+}
+
+/// The status line: the command being typed, a message, or what is
+/// going on in the game.
+///
+fn status(game: &Chess) -> String {
+    if let Some(line) = &game.view.command {
+        format!(":{}\u{2588}", line)
+    } else if let Some(msg) = &game.view.message {
+        msg.clone()
+    } else if game.view.review.is_some() {
+        "Review: up and down to go through the moves".into()
+    } else if game.is_analysing() {
+        match game.analysis() {
+            Some(info) => format!(
+                "Analysis: {} at depth {}",
+                widgets::score_for_white(info.score, game.turn),
+                info.depth
+            ),
+            None => "Analysis".into(),
+        }
+    } else if let Some(outcome) = game.outcome {
+        outcome.to_string()
+    } else if let Some(player) = game.draw_offer {
+        format!("{} offers a draw", player)
+    } else if let Mode::Moving(from) = game.mode {
+        match game.get_move_result(from, game.cursor) {
+            MoveResult::Cancel => "Cancel",
+            MoveResult::Nothing => "Move",
+            MoveResult::Capture(_) => "Capture",
+            MoveResult::Castle(_) => "Castle",
+            MoveResult::Promotion(_) => "Promote",
+            MoveResult::Drop(..) | MoveResult::Invalid => "",
+        }
+        .to_string()
+    } else if let Mode::Dropping(ty) = game.mode {
+        format!("Drop {}", ty.name())
+    } else if let Mode::Editing(brush) = game.mode {
+        let brush = match brush {
+            Some((player, ty)) => format!("Place {} {}", player, ty.name()),
+            None => "Clear".into(),
+        };
+        format!(
+            "{}, {} to move, castling {}",
+            brush,
+            game.turn,
+            game.castling()
+        )
+    } else {
+        String::new()
+    }
+}
+
+/// Place the info panel beside the board when the terminal is wide
+/// enough, otherwise underneath it, or leave it out altogether.
+///
+fn split_info_panel(board: &Board, area: Rect) -> (Rect, Option<Rect>) {
+    // rows taken by the clocks and status line
+    //
+    let chrome = 3;
+
+    let (direction, size) = if area.width >= ChessWidget::min_width(board) + InfoWidget::WIDTH {
+        (Direction::Horizontal, InfoWidget::WIDTH)
+    } else if area.height >= ChessWidget::min_height(board) + chrome + InfoWidget::HEIGHT {
+        (Direction::Vertical, InfoWidget::HEIGHT)
+    } else {
+        return (area, None);
+    };
+
+    let layout = Layout::default()
+        .direction(direction)
+        .constraints([Constraint::Min(0), Constraint::Length(size)])
+        .split(area);
+
+    (layout[0], Some(layout[1]))
+}
@@ -2,3 +2,4 @@ mod engine;
 mod widgets;
 
 pub use engine::TuiRenderer;
+pub use widgets::{ChessWidget, InfoWidget, PocketWidget};
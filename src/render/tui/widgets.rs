@@ -1,93 +1,851 @@
 use crate::{
-    core::MoveResult,
-    core::{Chess, Mode, Vec2},
+    ai::{eval::Centipawns, mate_in, syzygy::Wdl, Judgement, REVIEW_DEPTH},
+    core::{notation, MoveResult},
+    core::{Board, Chess, Mode, Move, PieceType, Player, Vec2},
     render::Theme,
 };
-use tui::{layout::Rect, widgets::StatefulWidget};
+use std::time::Duration;
+use tui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph, StatefulWidget, Widget, Wrap},
+};
+
+const GLYPHS: [&str; 6] = [
+    "\u{265a}", "\u{265b}", "\u{265d}", "\u{265e}", "\u{265c}", "\u{2659}",
+];
+
+/// Drawn on empty squares the selected piece can move to.
+///
+const MARKER: &str = "\u{2022}";
+
+/// Drawn on the empty squares left by an explosion.
+///
+const BURST: &str = "\u{2738}";
+
+/// Marks a move in the move list that was played after a hint.
+///
+const HINTED: &str = "\u{2020}";
+
+/// How long the squares caught in an explosion are flashed for.
+///
+const EXPLOSION_TIME: Duration = Duration::from_millis(700);
+
+/// Columns used by the rank labels to the left of a board of up to
+/// nine ranks, and one more for a taller board.
+///
+const LABEL_WIDTH: u16 = 2;
+
+/// Rows used by the file labels underneath the board.
+///
+const LABEL_HEIGHT: u16 = 1;
 
 pub struct ChessWidget<'a> {
     theme: &'a Theme,
 }
 
+/// The squares worth highlighting, worked out once per frame.
+///
+struct Highlights {
+    targets: Vec<Move>,
+    last_move: Option<(Vec2, Vec2)>,
+    hint: Option<(Vec2, Vec2)>,
+    check: Option<Vec2>,
+    attacked: Vec<Vec2>,
+    explosion: Vec<Vec2>,
+}
+
+impl Highlights {
+    fn new(state: &Chess) -> Self {
+        let targets = match state.mode {
+            Mode::Moving(from) => state.board.get_legal_moves(from, false),
+            Mode::Dropping(ty) => state.board.drop_moves(state.turn, ty),
+            Mode::Selecting | Mode::Editing(_) => Vec::new(),
+        };
+
+        let check = state
+            .board
+            .find_king(state.turn)
+            .filter(|_| state.board.variant.in_check(&state.board, state.turn));
+
+        // with the opponent's pieces hidden, leave out anything that
+        // would give them away
+        //
+        let view = &state.view;
+
+        let attacked = if view.show_attacks {
+            let opponent = state.turn.opponent();
+            state
+                .board
+                .pieces(opponent)
+                .filter(|(_, p)| view.shows(p.player))
+                .flat_map(|(pos, _)| state.board.attacks(pos))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let last_move = state
+            .last_move()
+            .filter(|m| view.shows(m.player))
+            .map(|m| (m.from, m.to));
+
+        let explosion = match &state.view.explosion {
+            Some((squares, at)) if at.elapsed() < EXPLOSION_TIME => squares.clone(),
+            _ => Vec::new(),
+        };
+
+        Self {
+            targets,
+            last_move,
+            hint: state.current_hint(),
+            check,
+            attacked,
+            explosion,
+        }
+    }
+}
+
 impl<'a> ChessWidget<'a> {
     pub fn new(theme: &'a Theme) -> Self {
         Self { theme }
     }
+
+    /// The number of rows (and half the number of columns) each square
+    /// can occupy within `area`, or zero if the board does not fit.
+    ///
+    pub fn square_size(board: &Board, area: Rect) -> u16 {
+        let (files, ranks) = (board.width() as u16, board.height() as u16);
+
+        let w = area.width.saturating_sub(label_width(board)) / (files * 2);
+        let h = area.height.saturating_sub(LABEL_HEIGHT) / ranks;
+        w.min(h)
+    }
+
+    /// The smallest number of rows the widget needs to draw the board.
+    ///
+    pub fn min_height(board: &Board) -> u16 {
+        board.height() as u16 + LABEL_HEIGHT
+    }
+
+    /// The smallest number of columns the widget needs to draw the board.
+    ///
+    pub fn min_width(board: &Board) -> u16 {
+        board.width() as u16 * 2 + label_width(board)
+    }
+
+    /// The background colour of a square, and whether it needs a
+    /// marker to show the selected piece can move there.
+    ///
+    fn square_colour(&self, state: &Chess, hl: &Highlights, xy: Vec2) -> (Color, bool) {
+        let is_cursor = xy == state.cursor;
+        let is_white_tile = (xy.x + xy.y) % 2 == 1;
+        let target = hl.targets.iter().find(|m| m.pos == xy);
+        let mut marker = false;
+
+        let mut col = if is_white_tile {
+            self.theme.black.tile
+        } else {
+            self.theme.white.tile
+        };
+
+        if is_cursor {
+            col = self.theme.get_player(state.turn).cursor;
+
+            match state.mode {
+                Mode::Selecting => {
+                    if state.can_move_cursor_piece() {
+                        col = self.theme.cursor_valid;
+                    }
+                }
+                Mode::Moving(from) => {
+                    let result = state.get_move_result(from, xy);
+
+                    if !matches!(result, MoveResult::Invalid) {
+                        col = self.theme.cursor_valid;
+                    }
+                }
+                Mode::Dropping(_) => {
+                    if target.is_some() {
+                        col = self.theme.cursor_valid;
+                    }
+                }
+                Mode::Editing(_) => {}
+            };
+        } else if hl.explosion.contains(&xy) {
+            col = self.theme.explosion;
+        } else if let Some(m) = target {
+            if state.board.is_capture(m) {
+                col = self.theme.capture;
+            } else {
+                marker = state.board.get(xy).is_none();
+                col = if is_white_tile {
+                    self.theme.white.tile_highlight
+                } else {
+                    self.theme.black.tile_highlight
+                };
+            }
+        } else if hl.check == Some(xy) {
+            col = self.theme.check;
+        } else if matches!(hl.hint, Some((from, to)) if from == xy || to == xy) {
+            col = self.theme.hint;
+        } else if matches!(hl.last_move, Some((from, to)) if from == xy || to == xy) {
+            col = self.theme.last_move;
+        } else if hl.attacked.contains(&xy) {
+            col = self.theme.attacked;
+        }
+
+        (col, marker)
+    }
 }
 
 impl<'a> StatefulWidget for ChessWidget<'a> {
     type State = Chess;
 
-    fn render(self, area: Rect, buf: &mut tui::buffer::Buffer, state: &mut Self::State) {
-        let glyphs = [
-            "\u{265a}", "\u{265b}", "\u{265d}", "\u{265e}", "\u{265c}", "\u{2659}",
-        ];
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        // never draw outside of the buffer, even if the
+        // layout handed us a larger area.
+        //
+        let area = area.intersection(*buf.area());
+
+        let size = Self::square_size(&state.board, area);
+
+        if size == 0 {
+            render_too_small(area, buf);
+            return;
+        }
+
+        let flipped = state.view.perspective == Player::Black;
+        let hl = Highlights::new(state);
+
+        // centre the board horizontally
+        //
+        let (files, ranks) = (state.board.width(), state.board.height());
+        let label_width = label_width(&state.board);
+
+        let width = label_width + files as u16 * 2 * size;
+        let ox = area.x + (area.width - width) / 2 + label_width;
+        let oy = area.y;
 
         // render the board
         //
-        for y in 0..8 {
-            for x in 0..8 {
-                // render background
-                //
+        for y in 0..ranks {
+            for x in 0..files {
                 let xy = Vec2::new(x, y);
-                let is_cursor = xy == state.cursor;
-                let is_white_tile = (x + y) % 2 == 1;
 
-                let mut col = if is_white_tile {
-                    self.theme.black.tile
+                // screen position of the square
+                //
+                let (vx, vy) = if flipped {
+                    (files - 1 - x, ranks - 1 - y)
                 } else {
-                    self.theme.white.tile
+                    (x, y)
                 };
 
-                if is_cursor {
-                    col = self.theme.get_player(state.turn).cursor;
-
-                    match state.mode {
-                        Mode::Selecting => {
-                            if state.can_move_cursor_piece() {
-                                col = self.theme.cursor_valid;
-                            }
-                        }
-                        Mode::Moving(from) => {
-                            let result = state.get_move_result(from, xy);
-
-                            if !matches!(result, MoveResult::Invalid) {
-                                col = self.theme.cursor_valid;
-                            }
-                        }
-                    };
-                } else {
-                    match state.mode {
-                        Mode::Selecting => {}
-                        Mode::Moving(from) => {
-                            let result = state.get_move_result(from, xy);
-
-                            if !matches!(result, MoveResult::Invalid) {
-                                if is_white_tile {
-                                    col = self.theme.white.tile_highlight;
-                                } else {
-                                    col = self.theme.black.tile_highlight;
-                                }
-                            }
-                        }
-                    };
-                }
+                let sx = ox + vx as u16 * size * 2;
+                let sy = oy + vy as u16 * size;
 
-                let sx = area.x + x as u16 * 2;
-                let sy = area.y + y as u16;
+                // render background
+                //
+                let (col, marker) = self.square_colour(state, &hl, xy);
 
-                buf.get_mut(sx + 0, sy).set_bg(col);
-                buf.get_mut(sx + 1, sy).set_bg(col);
+                for dy in 0..size {
+                    for dx in 0..size * 2 {
+                        buf.get_mut(sx + dx, sy + dy).set_bg(col);
+                    }
+                }
 
                 // render piece
                 //
-                if let Some(piece) = state.board.get(Vec2::new(x as i16, y as i16)) {
-                    let glyph = glyphs[piece.ty as usize];
+                let piece = state.board.get(xy).filter(|p| state.view.shows(p.player));
+
+                if let Some(piece) = piece {
+                    let glyph = glyph(piece.ty);
                     let col = self.theme.get_player(piece.player).piece;
 
-                    buf.get_mut(sx, sy).set_symbol(glyph).set_fg(col);
+                    buf.get_mut(sx + size - 1, sy + size / 2)
+                        .set_symbol(glyph)
+                        .set_fg(col);
+                } else if hl.explosion.contains(&xy) {
+                    buf.get_mut(sx + size - 1, sy + size / 2)
+                        .set_symbol(BURST)
+                        .set_fg(self.theme.check);
+                } else if marker {
+                    buf.get_mut(sx + size - 1, sy + size / 2)
+                        .set_symbol(MARKER)
+                        .set_fg(self.theme.marker);
                 }
             }
         }
+
+        // render the rank and file labels
+        //
+        let style = Style::default().fg(self.theme.white.cursor);
+
+        let (files, ranks) = (files as u16, ranks as u16);
+
+        for i in 0..files {
+            let file = if flipped { files - 1 - i } else { i };
+
+            let label = ((b'a' + file as u8) as char).to_string();
+            buf.set_string(
+                ox + i * size * 2 + size - 1,
+                oy + ranks * size,
+                label,
+                style,
+            );
+        }
+
+        for i in 0..ranks {
+            let rank = if flipped { i + 1 } else { ranks - i };
+
+            let label = rank.to_string();
+            buf.set_string(ox - label_width, oy + i * size + size / 2, label, style);
+        }
+    }
+}
+
+/// Side panel showing whose turn it is, the captured pieces and
+/// material balance, and the moves played so far.
+///
+pub struct InfoWidget<'a> {
+    theme: &'a Theme,
+}
+
+impl<'a> InfoWidget<'a> {
+    /// Columns needed when the panel is placed beside the board.
+    ///
+    pub const WIDTH: u16 = 26;
+
+    /// Rows needed when the panel is placed underneath the board.
+    ///
+    pub const HEIGHT: u16 = 8;
+
+    pub fn new(theme: &'a Theme) -> Self {
+        Self { theme }
+    }
+
+    fn captured_line(&self, state: &Chess, player: Player) -> Spans<'static> {
+        let mut captured = state.captured_by(player);
+        captured.sort_by_key(|ty| -ty.value());
+
+        let balance = state.material(player) - state.material(player.opponent());
+        let opponent = self.theme.get_player(player.opponent()).piece;
+
+        let mut spans = vec![Span::raw(format!("{:<6}", player))];
+
+        spans.extend(
+            captured
+                .iter()
+                .map(|ty| Span::styled(glyph(*ty), Style::default().fg(opponent))),
+        );
+
+        if balance > 0 {
+            spans.push(Span::raw(format!(" +{}", balance)));
+        }
+
+        Spans::from(spans)
+    }
+}
+
+impl<'a> StatefulWidget for InfoWidget<'a> {
+    type State = Chess;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let area = area.intersection(*buf.area());
+
+        let block = Block::default().borders(Borders::LEFT);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let turn = Style::default()
+            .fg(self.theme.get_player(state.turn).piece)
+            .add_modifier(Modifier::BOLD);
+
+        let headline = match state.outcome {
+            Some(outcome) => outcome.to_string(),
+            None => format!("{} to move", state.turn),
+        };
+
+        let mut lines = vec![Spans::from(Span::styled(headline, turn)), Spans::default()];
+
+        // in a refereed game the captures and moves are not known, so
+        // what the referee has announced is shown instead
+        //
+        if !state.refereed {
+            lines.push(self.captured_line(state, Player::White));
+            lines.push(self.captured_line(state, Player::Black));
+            lines.push(Spans::default());
+        }
+
+        let hints = [Player::White, Player::Black].map(|player| state.hints(player));
+        if hints != [0, 0] {
+            lines.push(Spans::from(format!(
+                "Hints: white {}, black {}",
+                hints[0], hints[1]
+            )));
+            lines.push(Spans::default());
+        }
+
+        if let Some(puzzles) = &state.puzzles {
+            let puzzle = puzzles.current();
+            let rating = puzzle.rating.map(|r| format!(" ({})", r));
+            let stats = &puzzles.stats;
+
+            lines.push(Spans::from(format!(
+                "Puzzle {}{}",
+                puzzle.id,
+                rating.unwrap_or_default()
+            )));
+            lines.push(Spans::from(format!(
+                "Rating {:.0}, streak {}/{}",
+                stats.rating, stats.streak, stats.best_streak
+            )));
+            lines.push(Spans::from(match (puzzles.solved, puzzles.failed) {
+                (true, false) => "Solved".to_string(),
+                (true, true) => "Solved at the second try".to_string(),
+                (false, true) => format!("Failed, {} to play", puzzles.player),
+                (false, false) => format!("{} to play and win", puzzles.player),
+            }));
+            lines.push(Spans::default());
+        }
+
+        // variants other than standard chess get a line of their own
+        //
+        let variant = &state.board.variant;
+
+        if variant.name() != "standard" {
+            lines.insert(1, Spans::from(format!("Variant: {}", variant.name())));
+
+            if let Some(info) = variant.info(&state.board) {
+                lines.insert(2, Spans::from(info));
+            }
+        }
+
+        // show as many of the most recent moves as will fit
+        //
+        let moves = if state.refereed {
+            state
+                .view
+                .announcements
+                .iter()
+                .map(|a| Spans::from(a.as_str()))
+                .collect()
+        } else {
+            move_list(state)
+        };
+
+        let room = (inner.height as usize).saturating_sub(lines.len());
+        let skip = moves.len().saturating_sub(room);
+
+        lines.extend(moves.into_iter().skip(skip));
+
+        Paragraph::new(lines).render(inner, buf);
+    }
+}
+
+/// The moves played so far, two to a line. Moves played after asking
+/// for a hint are marked with [`HINTED`].
+///
+fn move_list(state: &Chess) -> Vec<Spans<'static>> {
+    let mut sans: Vec<String> = state
+        .history
+        .iter()
+        .map(|r| match r.hinted {
+            true => format!("{}{}", r.san, HINTED),
+            false => r.san.clone(),
+        })
+        .collect();
+
+    // a game set up with black to move starts half way through a line
+    //
+    if let Some(Player::Black) = state.history.first().map(|r| r.player) {
+        sans.insert(0, "...".into());
+    }
+
+    sans.chunks(2)
+        .enumerate()
+        .map(|(i, pair)| {
+            let number = state.first_move as usize + i;
+            let black = pair.get(1).map_or("", String::as_str);
+            Spans::from(format!("{:>3}. {:<8}{}", number, pair[0], black))
+        })
+        .collect()
+}
+
+/// One player's pocket of captured pieces waiting to be dropped, with
+/// the piece being dropped highlighted.
+///
+pub struct PocketWidget<'a> {
+    theme: &'a Theme,
+    player: Player,
+}
+
+impl<'a> PocketWidget<'a> {
+    pub fn new(theme: &'a Theme, player: Player) -> Self {
+        Self { theme, player }
+    }
+}
+
+impl<'a> StatefulWidget for PocketWidget<'a> {
+    type State = Chess;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let area = area.intersection(*buf.area());
+
+        let piece = Style::default().fg(self.theme.get_player(self.player).piece);
+
+        let selected = match state.mode {
+            Mode::Dropping(ty) if state.turn == self.player => Some(ty),
+            _ => None,
+        };
+
+        let mut spans = Vec::new();
+
+        for (ty, count) in state.board.pocket(self.player) {
+            let style = if selected == Some(ty) {
+                piece.bg(self.theme.cursor_valid)
+            } else {
+                piece
+            };
+
+            spans.push(Span::styled(glyph(ty), style));
+
+            if count > 1 {
+                spans.push(Span::raw(count.to_string()));
+            }
+            spans.push(Span::raw(" "));
+        }
+
+        Paragraph::new(Spans::from(spans))
+            .alignment(Alignment::Right)
+            .render(area, buf);
+    }
+}
+
+/// The endgame oracle, drawn over the info panel: what the tablebases
+/// say about the position, and about each move from it.
+///
+pub struct OracleWidget<'a> {
+    theme: &'a Theme,
+}
+
+impl<'a> OracleWidget<'a> {
+    pub fn new(theme: &'a Theme) -> Self {
+        Self { theme }
+    }
+}
+
+impl<'a> StatefulWidget for OracleWidget<'a> {
+    type State = Chess;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let area = area.intersection(*buf.area());
+
+        Clear.render(area, buf);
+
+        let block = Block::default().borders(Borders::ALL).title("Oracle");
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let turn = state.turn;
+        let style = Style::default()
+            .fg(self.theme.get_player(turn).piece)
+            .add_modifier(Modifier::BOLD);
+
+        let Some(verdict) = state.oracle().cloned() else {
+            Paragraph::new("Not in the tablebases").render(inner, buf);
+            return;
+        };
+
+        let headline = match verdict.wdl {
+            Wdl::Draw => "Draw".to_string(),
+            wdl => format!("{} to move: {}, DTZ {}", turn, wdl, verdict.dtz.abs()),
+        };
+
+        let mut lines = vec![Spans::from(Span::styled(headline, style)), Spans::default()];
+
+        // each move with its result for the player making it, and the
+        // plies to the next capture or pawn move
+        //
+        lines.extend(verdict.moves.iter().map(|m| {
+            let san = notation::san(&state.board, m.from, &m.m);
+            let dtz = match m.wdl {
+                Wdl::Draw => String::new(),
+                _ => m.dtz.abs().to_string(),
+            };
+            Spans::from(format!("{:<8}{:<13}{:>4}", san, m.wdl, dtz))
+        }));
+
+        Paragraph::new(lines).render(inner, buf);
+    }
+}
+
+/// The search's view of the position while analysing: a bar showing
+/// who is better, how deep the search has got, and its best lines.
+///
+pub struct AnalysisWidget<'a> {
+    theme: &'a Theme,
+}
+
+impl<'a> AnalysisWidget<'a> {
+    pub fn new(theme: &'a Theme) -> Self {
+        Self { theme }
+    }
+}
+
+impl<'a> StatefulWidget for AnalysisWidget<'a> {
+    type State = Chess;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let area = area.intersection(*buf.area());
+
+        Clear.render(area, buf);
+
+        let block = Block::default().borders(Borders::ALL).title("Analysis");
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let Some(info) = state.analysis() else {
+            let text = match state.outcome {
+                Some(outcome) => outcome.to_string(),
+                None => "Searching...".into(),
+            };
+            Paragraph::new(text).render(inner, buf);
+            return;
+        };
+
+        // white's share of the bar, from the score for white
+        //
+        let sign = match state.turn {
+            Player::White => 1,
+            Player::Black => -1,
+        };
+        let share = match info.mate() {
+            Some(mate) if mate * sign > 0 => 1.0,
+            Some(_) => 0.0,
+            None => 1.0 / (1.0 + 10f64.powf(-(info.score * sign) as f64 / 400.0)),
+        };
+        let white = (share * inner.width as f64).round() as usize;
+        let black = (inner.width as usize).saturating_sub(white);
+
+        let bar = Spans::from(vec![
+            Span::styled(
+                "\u{2588}".repeat(white),
+                Style::default().fg(self.theme.white.piece),
+            ),
+            Span::styled(
+                "\u{2588}".repeat(black),
+                Style::default().fg(self.theme.black.piece),
+            ),
+        ]);
+
+        let headline = Style::default().add_modifier(Modifier::BOLD);
+
+        let mut lines = vec![
+            bar,
+            Spans::from(Span::styled(
+                format!(
+                    "{}  depth {}",
+                    score_for_white(info.score, state.turn),
+                    info.depth
+                ),
+                headline,
+            )),
+            Spans::from(format!("{} kN/s", info.nps() / 1000)),
+        ];
+
+        for line in &info.lines {
+            lines.push(Spans::default());
+            lines.push(Spans::from(format!(
+                "{} {}",
+                score_for_white(line.score, state.turn),
+                line_san(state, &line.pv)
+            )));
+        }
+
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: true })
+            .render(inner, buf);
+    }
+}
+
+/// The review of the game's moves: each player's accuracy and count of
+/// bad moves, then every move with its score and judgement, the one
+/// selected highlighted.
+///
+pub struct ReviewWidget;
+
+impl StatefulWidget for ReviewWidget {
+    type State = Chess;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let area = area.intersection(*buf.area());
+
+        Clear.render(area, buf);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Review at depth {}", REVIEW_DEPTH));
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let selected = state.view.review.unwrap_or_default();
+        let Some(review) = state.review().cloned() else {
+            let text = match state.review_progress() {
+                Some((done, total)) => format!("Reviewing... {}/{} positions", done, total),
+                None => "No review".into(),
+            };
+            Paragraph::new(text).render(inner, buf);
+            return;
+        };
+
+        let headline = Style::default().add_modifier(Modifier::BOLD);
+        let mut lines = Vec::new();
+
+        for player in [Player::White, Player::Black] {
+            let Some(accuracy) = review.accuracy(player) else {
+                continue;
+            };
+
+            let counts: Vec<String> = [
+                Judgement::Inaccuracy,
+                Judgement::Mistake,
+                Judgement::Blunder,
+            ]
+            .iter()
+            .map(|j| format!("{} {}", review.count(player, *j), j.symbol()))
+            .collect();
+
+            lines.push(Spans::from(Span::styled(
+                format!(
+                    "{:<6} accuracy {:>5.1}%   {}",
+                    player,
+                    accuracy,
+                    counts.join("  ")
+                ),
+                headline,
+            )));
+        }
+        lines.push(Spans::default());
+
+        // scroll the moves to keep the selected one in view
+        //
+        let rows = (inner.height as usize).saturating_sub(lines.len()).max(1);
+        let first = selected.saturating_sub(rows - 1);
+
+        for (i, m) in review.moves.iter().enumerate().skip(first).take(rows) {
+            let ply = i + review.black_first as usize;
+            let number = review.first_move as usize + ply / 2;
+            let number = match (m.player, i) {
+                (Player::White, _) => format!("{}.", number),
+                (Player::Black, 0) => format!("{}...", number),
+                (Player::Black, _) => String::new(),
+            };
+
+            let verdict = match (&m.best, m.judgement >= Judgement::Inaccuracy) {
+                (Some(best), true) => format!("{}, {} was best", m.judgement, best),
+                _ => m.judgement.to_string(),
+            };
+
+            let mut style = Style::default();
+            if m.judgement >= Judgement::Mistake {
+                style = style.add_modifier(Modifier::BOLD);
+            }
+            if i == selected {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+
+            lines.push(Spans::from(Span::styled(
+                format!(
+                    "{:>5} {:<9} {:>6}  {}",
+                    number,
+                    format!("{}{}", m.san, m.judgement.symbol()),
+                    m.eval().unwrap_or_default(),
+                    verdict
+                ),
+                style,
+            )));
+        }
+
+        Paragraph::new(lines).render(inner, buf);
+    }
+}
+
+/// A search score for the player to move, as pawns in white's favour,
+/// or moves to mate, e.g. `+0.35` or `#-3`.
+///
+pub fn score_for_white(score: Centipawns, turn: Player) -> String {
+    let sign = match turn {
+        Player::White => 1,
+        Player::Black => -1,
+    };
+
+    match mate_in(score) {
+        Some(mate) => format!("#{}", mate * sign),
+        None => format!("{:+.2}", (score * sign) as f64 / 100.0),
+    }
+}
+
+/// A line of play from the game's position in SAN, numbered, e.g.
+/// `12... Nf6 13. Bg5`.
+///
+fn line_san(state: &Chess, pv: &[(Vec2, Move)]) -> String {
+    let mut board = state.board.clone();
+    let mut turn = state.turn;
+    let mut number = state.fullmove();
+    let mut words = Vec::new();
+
+    for (i, (from, m)) in pv.iter().enumerate() {
+        match turn {
+            Player::White => words.push(format!("{}.", number)),
+            Player::Black if i == 0 => words.push(format!("{}...", number)),
+            Player::Black => {}
+        }
+
+        words.push(notation::san(&board, *from, m));
+        board.make_move(*from, m);
+
+        if turn == Player::Black {
+            number += 1;
+        }
+        turn = turn.opponent();
+    }
+
+    words.join(" ")
+}
+
+fn glyph(ty: PieceType) -> &'static str {
+    match ty {
+        PieceType::King => GLYPHS[0],
+        PieceType::Queen => GLYPHS[1],
+        PieceType::Bishop => GLYPHS[2],
+        PieceType::Knight => GLYPHS[3],
+        PieceType::Rook => GLYPHS[4],
+        PieceType::Pawn => GLYPHS[5],
+        PieceType::Fairy(f) => f.glyph,
+    }
+}
+
+fn label_width(board: &Board) -> u16 {
+    if board.height() > 9 {
+        LABEL_WIDTH + 1
+    } else {
+        LABEL_WIDTH
+    }
+}
+
+fn render_too_small(area: Rect, buf: &mut Buffer) {
+    let lines = ["Terminal too small", "please resize"];
+
+    for (i, line) in lines.iter().enumerate().take(area.height as usize) {
+        buf.set_stringn(
+            area.x,
+            area.y + i as u16,
+            line,
+            area.width as usize,
+            Style::default(),
+        );
     }
 }